@@ -15,6 +15,7 @@ class IsingData(ExperimentOutput):
         self.mag_susceptibility = []
         self.elapsed_time       = -1
         self.correlation_length = []
+        self.extra_columns      = {}    # optional columns (exact reference...) by header name
 
     @override
     def parse_output(self, line_number: int, line: str):
@@ -34,6 +35,24 @@ class IsingData(ExperimentOutput):
             self.specific_heat.append(float(slines[3]))
             self.mag_susceptibility.append(float(slines[4]))
             self.correlation_length.append(float(slines[5]))
+            for name, value in zip(self.observables[6:], slines[6:]):
+                self.extra_columns.setdefault(name.strip(), []).append(float(value))
+
+    @property
+    def binder_cumulant(self) -> list:
+        return self.extra_columns.get("binder_cumulant", [])
+
+    @property
+    def correlation_length_x(self) -> list:
+        return self.extra_columns.get("correlation_length_x", [])
+
+    @property
+    def correlation_length_y(self) -> list:
+        return self.extra_columns.get("correlation_length_y", [])
+
+    def error(self, observable: str) -> list:
+        """Error bars of a column, eg error("specific_heat"): binning for energy_density & magnetisation, jackknife for the rest."""
+        return self.extra_columns.get(f"{observable.replace(' ', '_')}_error", [])
                        
 
 class RustIsingExperimentCreator:
@@ -44,12 +63,34 @@ class RustIsingExperimentCreator:
         self.builder.set_output_type(IsingData)
         self.builder.set_scale_variable_names(["Lx", "Ly"])
         
-    def new_from_parameters(self, therm_steps: dict, measure_steps: dict, temperatures: np.ndarray, measure_corr_length: bool = False) -> RustExperiment:
+    def new_from_parameters(self, therm_steps: dict, measure_steps: dict, temperatures: np.ndarray, measure_corr_length: bool = False, lattice: str = "square", J: float = 1.0, algorithm: str = "metropolis", boundary_x: str = "periodic", boundary_y: str = "periodic", graph_file: str = "none", sigma: float | None = None, transverse_field: float | None = None, trotter_slices: int = 32, exact_reference: bool = False, time_series: str = "none", reweight_temperatures: np.ndarray | None = None, struct_fact_interval: int = 0, correlation_function: bool = False, cluster_interval: int = 0, domain_interval: int = 0, histogram_bins: int = 0, relaxation_samples: int = 0, initial_state: str = "ordered") -> RustExperiment:
         
         cargo_toml_path = self.proj_dir  / "Cargo.toml"
         self.builder.set_cargo_toml_path(cargo_toml_path)
         self.builder.add_static_parameter("temperatures", temperatures)
         self.builder.add_static_parameter("measure_corr_len", measure_corr_length)
+        self.builder.add_static_parameter("lattice", lattice)
+        self.builder.add_static_parameter("J", J)
+        self.builder.add_static_parameter("algorithm", algorithm)
+        self.builder.add_static_parameter("boundary_x", boundary_x)
+        self.builder.add_static_parameter("boundary_y", boundary_y)
+        self.builder.add_static_parameter("graph_file", graph_file)
+        self.builder.add_static_parameter("exact_reference", exact_reference)
+        self.builder.add_static_parameter("time_series", time_series)
+        self.builder.add_static_parameter("struct_fact_interval", struct_fact_interval)
+        self.builder.add_static_parameter("correlation_function", correlation_function)
+        self.builder.add_static_parameter("cluster_interval", cluster_interval)
+        self.builder.add_static_parameter("domain_interval", domain_interval)
+        self.builder.add_static_parameter("histogram_bins", histogram_bins)
+        self.builder.add_static_parameter("relaxation_samples", relaxation_samples)
+        self.builder.add_static_parameter("initial_state", initial_state)
+        if reweight_temperatures is not None:
+            self.builder.add_static_parameter("reweight_temperatures", reweight_temperatures)
+        if sigma is not None:
+            self.builder.add_static_parameter("sigma", sigma)
+        if transverse_field is not None:
+            self.builder.add_static_parameter("transverse_field", transverse_field)
+            self.builder.add_static_parameter("trotter_slices", trotter_slices)
         self.builder.add_scaling_parameter("therm_steps", therm_steps)
         self.builder.add_scaling_parameter("measure_steps", measure_steps)
         return self.builder.build()
@@ -64,6 +105,56 @@ def get_lengths(exp: RustExperiment) -> list[int]:
 def get_results(exp: RustExperiment) -> dict[int, IsingData]:
     return exp.get_results()
 
+def load_time_series(file_name: str | Path) -> tuple[np.ndarray, np.ndarray]:
+    """(energy, spin_sum) per sweep, from the csv or binary files written with time_series="csv" / "binary"."""
+    if Path(file_name).suffix == ".bin":
+        data = np.fromfile(file_name, dtype="<f8").reshape(-1, 2)
+    else:
+        data = np.loadtxt(file_name, delimiter=",", skiprows=1, ndmin=2)
+    return data[:, 0], data[:, 1]
+
+def _load_two_tables(file_name: str | Path) -> tuple[np.ndarray, np.ndarray]:
+    first, second = Path(file_name).read_text().split("\n\n")
+    return np.loadtxt(first.splitlines()[1:], delimiter=",", ndmin=2), np.loadtxt(second.splitlines()[1:], delimiter=",", ndmin=2)
+
+def load_structure_factor(file_name: str | Path) -> tuple[np.ndarray, np.ndarray]:
+    """(kx, ky, S) of every mode & (k, S, modes) of the radial average, from the files written with struct_fact_interval > 0."""
+    return _load_two_tables(file_name)
+
+def load_correlation_function(file_name: str | Path) -> tuple[np.ndarray, np.ndarray]:
+    """(r, G along x, G along y) & (r, G, pairs) of the radial average, from the files written with correlation_function=True."""
+    return _load_two_tables(file_name)
+
+def _load_values_and_table(file_name: str | Path) -> tuple[dict[str, float], np.ndarray]:
+    first, second = Path(file_name).read_text().split("\n\n")
+    header, values = first.splitlines()[:2]
+    named_values = {name.strip(): float(value) for name, value in zip(header.split(","), values.split(","))}
+    return named_values, np.loadtxt(second.splitlines()[1:], delimiter=",", ndmin=2)
+
+def load_cluster_statistics(file_name: str | Path) -> tuple[dict[str, float], np.ndarray]:
+    """Largest fraction, wrapping probability & clusters per spin of both kinds, and (s, like-spin n_s, FK n_s), from the files written with cluster_interval > 0."""
+    return _load_values_and_table(file_name)
+
+def load_finite_size_scaling(file_name: str | Path) -> tuple[dict[str, float], np.ndarray]:
+    """Tc, nu, beta/nu & gamma/nu with their errors, and (L, T, x, U, m L^(beta/nu), chi L^(-gamma/nu)) of every point, from the finite_size_scaling binary."""
+    return _load_values_and_table(file_name)
+
+def load_histograms(file_name: str | Path) -> tuple[np.ndarray, np.ndarray]:
+    """(m, P(m)) & (e, P(e)) per spin, from the files written with histogram_bins > 0."""
+    return _load_two_tables(file_name)
+
+def load_relaxation(file_name: str | Path) -> np.ndarray:
+    """(sweep, m, m error, m^2, Binder cumulant, e, e error) rows, from the files written with relaxation_samples > 0."""
+    return np.loadtxt(file_name, delimiter=",", skiprows=1, ndmin=2)
+
+def load_domain_series(file_name: str | Path) -> np.ndarray:
+    """(sweep, domain-wall length, number of domains, Euler characteristic) rows, from the files written with domain_interval > 0 (thermalisation included)."""
+    return np.loadtxt(file_name, delimiter=",", skiprows=1, ndmin=2)
+
+def load_critical_temperature_search(file_name: str | Path) -> tuple[dict[str, float], np.ndarray]:
+    """Tc, its error, converged & the number of rounds, and (round, L, T, U, U error) of every run, from the critical_temperature binary."""
+    return _load_values_and_table(file_name)
+
     
     
 if __name__ == "__main":
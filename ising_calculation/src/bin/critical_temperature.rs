@@ -0,0 +1,108 @@
+// Adaptive search of Tc from the Binder cumulant crossings of L x L lattices: cargo run --release --bin critical_temperature -- parameter_file.txt
+// with the parameters
+//     sizes: 8, 16, 32
+//     temperature_range: 2.0, 2.5
+//     therm_steps: 10000
+//     measure_steps: 100000
+//     outputfile: results/tc_search.txt
+// and optionally "points" (temperatures per round, at least 4), "precision" (target temperature spacing), "max_rounds", "algorithm", "lattice" & "J".
+use ising_calculation::critical_temperature::{TcSearch, search_critical_temperature};
+use ising_calculation::{ExperimentParam, UpdateAlgorithm, IsingHamiltonian};
+use periodic_array_2d_lib::LatticeGeometry;
+use std::env;
+use std::path::Path;
+use parameter_reader::ParameterReader;
+
+
+const J: f64                = 1_f64;
+const EXTERN_MAG: f64       = 0_f64;
+const POINTS: usize         = 9;
+const PRECISION: f64        = 1E-3;
+const MAX_ROUNDS: usize     = 10;
+const PARAMETERS: [&str; 5] = [
+    "sizes",
+    "temperature_range",
+    "therm_steps",
+    "measure_steps",
+    "outputfile"
+];
+
+fn optional_parameter(reader: &ParameterReader, name: &str) -> Option<String>
+{
+    reader.parse_parameters(&[name], ":").ok().map(|params| params[name].clone())
+}
+
+fn main()
+{
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2
+    {
+        println!("Usage: Cargo run --release --bin critical_temperature -- parameter_file.txt");
+        std::process::exit(1);
+    }
+
+    let reader = ParameterReader::build(&args[1]).unwrap_or_else(|e|
+    {
+        println!("Failed to create parameter reader: {e}");
+        std::process::exit(1);
+    });
+    let params = reader.parse_parameters(&PARAMETERS, ":").unwrap_or_else(|err|
+    {
+        println!("Missing parameters / failed to parse line: {err}");
+        std::process::exit(1);
+    });
+
+    let sizes: Vec<usize>           = params["sizes"].split(", ").map(|l| l.parse().expect("!! failed to parse \"sizes\"")).collect();
+    let range: Vec<f64>             = params["temperature_range"].split(", ").map(|t| t.parse().expect("!! failed to parse \"temperature_range\"")).collect();
+    let thermalisation_steps: usize = params["therm_steps"].parse().expect("!! Could not parse \"therm_steps\"");
+    let measurement_steps: usize    = params["measure_steps"].parse().expect("!! Could not parse \"measure_steps\"");
+    let outputfile: String          = params["outputfile"].parse().expect("!! Could not parse \"outputfile\"");
+    let points: usize               = optional_parameter(&reader, "points").map_or(POINTS, |p| p.parse().expect("!! Could not parse \"points\""));
+    let precision: f64              = optional_parameter(&reader, "precision").map_or(PRECISION, |p| p.parse().expect("!! Could not parse \"precision\""));
+    let max_rounds: usize           = optional_parameter(&reader, "max_rounds").map_or(MAX_ROUNDS, |r| r.parse().expect("!! Could not parse \"max_rounds\""));
+    let algorithm: UpdateAlgorithm  = optional_parameter(&reader, "algorithm").map_or(UpdateAlgorithm::Metropolis, |a| a.parse().expect("!! Could not parse \"algorithm\""));
+    let lattice: LatticeGeometry    = optional_parameter(&reader, "lattice").map_or(LatticeGeometry::Square, |l| l.parse().expect("!! Could not parse \"lattice\""));
+    let interaction_term: f64       = optional_parameter(&reader, "J").map_or(J, |j| j.parse().expect("!! Could not parse \"J\""));
+
+    let mut sizes = sizes;
+    sizes.sort_unstable();
+    let search = TcSearch { sizes, bracket: (range[0], range[1]), points, precision, max_rounds };
+    let param  = ExperimentParam
+    {
+        hamiltonian:          IsingHamiltonian::new(interaction_term, EXTERN_MAG),
+        lattice,
+        algorithm,
+        thermalisation_steps,
+        measurement_steps,
+        ..Default::default()                    // temperatures: set by every round
+    };
+    println!("Searching Tc with the {algorithm:?} algorithm for L = {:?} ({lattice:?} lattice, J={interaction_term}) in [{}, {}], {points} temperatures per round down to a spacing of {precision}",
+             search.sizes, search.bracket.0, search.bracket.1);
+
+    let now    = std::time::SystemTime::now();
+    let result = search_critical_temperature::<i8, _>(&search, &param).unwrap_or_else(|e|
+    {
+        println!("Could not perform the Tc search: {e:?}");
+        std::process::exit(1);
+    });
+    for (round, crossing_round) in result.rounds.iter().enumerate()
+    {
+        println!("Round {round}: T in [{}, {}], crossings {:?}", crossing_round.temperatures[0], crossing_round.temperatures[points - 1], crossing_round.crossings);
+    }
+    println!("Tc = {} +- {} after {}s", result.critical_temperature, result.error, now.elapsed().unwrap().as_secs());
+    if !result.converged
+    {
+        println!("The search didn't reach the precision {precision} in {max_rounds} rounds.");
+    }
+    else if result.error > precision
+    {
+        println!("The statistical error is larger than the precision {precision}: more measure_steps are needed.");
+    }
+
+    println!("Saving result as \"{outputfile}\".");
+    result.write_to_file(Path::new(&outputfile), &search.sizes).unwrap_or_else(|e|
+    {
+        println!("Could not write to file: {e}.");
+        std::process::exit(1);
+    });
+}
@@ -0,0 +1,93 @@
+// Finite-size-scaling collapse of the summary files of several lattice sizes: cargo run --release --bin finite_size_scaling -- parameter_file.txt
+// with the parameters
+//     sizes: 16, 32, 64
+//     files: results/L16.txt, results/L32.txt, results/L64.txt
+//     outputfile: results/collapse.txt
+// and optionally "temperature_range: low, high" (only the temperatures around Tc) & "resamples: n" (bootstrap fits for the errors).
+use ising_calculation::finite_size_scaling::{SizeData, fit_collapse};
+use std::env;
+use std::path::Path;
+use parameter_reader::ParameterReader;
+use xorshifts::Xoshiro256pp;
+
+
+const RESAMPLES: usize      = 100;
+const PARAMETERS: [&str; 3] = [
+    "sizes",
+    "files",
+    "outputfile"
+];
+
+fn optional_parameter(reader: &ParameterReader, name: &str) -> Option<String>
+{
+    reader.parse_parameters(&[name], ":").ok().map(|params| params[name].clone())
+}
+
+fn main()
+{
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2
+    {
+        println!("Usage: Cargo run --release --bin finite_size_scaling -- parameter_file.txt");
+        std::process::exit(1);
+    }
+
+    let reader = ParameterReader::build(&args[1]).unwrap_or_else(|e|
+    {
+        println!("Failed to create parameter reader: {e}");
+        std::process::exit(1);
+    });
+    let params = reader.parse_parameters(&PARAMETERS, ":").unwrap_or_else(|err|
+    {
+        println!("Missing parameters / failed to parse line: {err}");
+        std::process::exit(1);
+    });
+
+    let sizes: Vec<f64>     = params["sizes"].split(", ").map(|l| l.parse().expect("!! failed to parse \"sizes\"")).collect();
+    let files: Vec<&str>    = params["files"].split(", ").map(str::trim).collect();
+    let outputfile: String  = params["outputfile"].parse().expect("!! Could not parse \"outputfile\"");
+    let resamples: usize    = optional_parameter(&reader, "resamples").map_or(RESAMPLES, |r| r.parse().expect("!! Could not parse \"resamples\""));
+    let temperature_range: Option<(f64, f64)> = optional_parameter(&reader, "temperature_range").filter(|r| !r.is_empty() && r.to_lowercase() != "none").map(|r|
+    {
+        let bounds: Vec<f64> = r.split(", ").map(|t| t.parse().expect("!! failed to parse \"temperature_range\"")).collect();
+        (bounds[0], bounds[1])
+    });
+    if sizes.len() != files.len()
+    {
+        println!("{} sizes for {} files.", sizes.len(), files.len());
+        std::process::exit(1);
+    }
+
+    let data: Vec<SizeData> = sizes.iter().zip(&files).map(|(&size, file)|
+    {
+        let size_data = SizeData::read_from_file(size, Path::new(file), temperature_range).unwrap_or_else(|e|
+        {
+            println!("Could not read \"{file}\": {e}.");
+            std::process::exit(1);
+        });
+        if !size_data.has_error_bars()
+        {
+            println!("No error bars in \"{file}\": 1% errors are assumed for the collapse, and the bootstrap errors ignore its noise.");
+        }
+        size_data
+    }).collect();
+
+    println!("Collapsing {} sizes with {resamples} bootstrap resamples", data.len());
+    let fit = fit_collapse(&data, resamples, &mut Xoshiro256pp::from_os()).unwrap_or_else(|e|
+    {
+        println!("Could not collapse the data: {e:?}");
+        std::process::exit(1);
+    });
+    println!("Tc       = {} +- {}", fit.critical_temperature.0, fit.critical_temperature.1);
+    println!("nu       = {} +- {}", fit.nu.0, fit.nu.1);
+    println!("beta/nu  = {} +- {}", fit.beta_over_nu.0, fit.beta_over_nu.1);
+    println!("gamma/nu = {} +- {}", fit.gamma_over_nu.0, fit.gamma_over_nu.1);
+    println!("Collapse qualities (Binder, m, chi): {:?}", fit.quality);
+
+    println!("Saving result as \"{outputfile}\".");
+    fit.write_to_file(Path::new(&outputfile), &data).unwrap_or_else(|e|
+    {
+        println!("Could not write to file: {e}.");
+        std::process::exit(1);
+    });
+}
@@ -0,0 +1,137 @@
+use num_traits::Float;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use monte_carlo_lib::clusters::ClusterStatistics;
+
+// Averages over the measurements of the clusters of one kind (see monte_carlo_lib::clusters)
+#[derive(Debug, Default, Clone)]
+pub struct ClusterAverages<T> where T: Float
+{
+    size_density_sum: Vec<T>,       // sum of n_s = (number of clusters of size s) / N, at index s - 1
+    largest_fraction_sum: T,
+    clusters_per_spin_sum: T,
+    wrapping_count: usize,
+    number_of_measures: usize,
+}
+
+impl<T> ClusterAverages<T> where T: Float
+{
+    pub fn add(&mut self, statistics: &ClusterStatistics)
+    {
+        let number_of_spins = T::from(statistics.number_of_spins).unwrap();
+        if self.size_density_sum.len() < statistics.number_of_spins
+        {
+            self.size_density_sum.resize(statistics.number_of_spins, T::zero());
+        }
+        for (size, count) in statistics.size_distribution()
+        {
+            self.size_density_sum[size - 1] = self.size_density_sum[size - 1] + T::from(count).unwrap() / number_of_spins;
+        }
+        self.largest_fraction_sum  = self.largest_fraction_sum + T::from(statistics.largest_fraction()).unwrap();
+        self.clusters_per_spin_sum = self.clusters_per_spin_sum + T::from(statistics.clusters.len()).unwrap() / number_of_spins;
+        self.wrapping_count       += statistics.wraps() as usize;
+        self.number_of_measures   += 1;
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool
+    {
+        self.number_of_measures == 0
+    }
+    fn average(&self, sum: T) -> T
+    {
+        sum / T::from(self.number_of_measures).unwrap()
+    }
+    // <largest cluster size> / N
+    pub fn largest_fraction(&self) -> T
+    {
+        self.average(self.largest_fraction_sum)
+    }
+    pub fn clusters_per_spin(&self) -> T
+    {
+        self.average(self.clusters_per_spin_sum)
+    }
+    // Fraction of the configurations with a cluster wrapping around the lattice
+    pub fn wrapping_probability(&self) -> T
+    {
+        self.average(T::from(self.wrapping_count).unwrap())
+    }
+    // (s, <n_s>) for the sizes which appeared
+    pub fn size_density(&self) -> Vec<(usize, T)>
+    {
+        self.size_density_sum.iter().enumerate().filter(|(_, n)| !n.is_zero()).map(|(i, &n)| (i + 1, self.average(n))).collect()
+    }
+}
+
+// Like-spin & Fortuin-Kasteleyn clusters, measured every ExperimentParam::cluster_interval sweeps
+#[derive(Debug, Default, Clone)]
+pub struct ClusterMeasurements<T> where T: Float
+{
+    pub like_spin: ClusterAverages<T>,
+    pub fortuin_kasteleyn: ClusterAverages<T>,
+}
+
+impl<T> ClusterMeasurements<T> where T: Float + std::fmt::Display
+{
+    // One line of averages for both kinds, then an empty line & "size, like_spin_density, fk_density" for the size distributions n_s
+    pub fn write_to_file(&self, file_name: &Path) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "like_spin_largest_fraction, like_spin_wrapping_probability, like_spin_clusters_per_spin, fk_largest_fraction, fk_wrapping_probability, fk_clusters_per_spin")?;
+        let (like_spin, fk) = (&self.like_spin, &self.fortuin_kasteleyn);
+        writeln!(&mut file, "{}, {}, {}, {}, {}, {}", like_spin.largest_fraction(), like_spin.wrapping_probability(), like_spin.clusters_per_spin(),
+                                                       fk.largest_fraction(), fk.wrapping_probability(), fk.clusters_per_spin())?;
+        writeln!(&mut file)?;
+        writeln!(&mut file, "size, like_spin_density, fk_density")?;
+        let (like_spin, fortuin_kasteleyn) = (self.like_spin.size_density(), self.fortuin_kasteleyn.size_density());
+        let mut sizes: Vec<usize> = like_spin.iter().chain(&fortuin_kasteleyn).map(|&(s, _)| s).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        let density = |distribution: &[(usize, T)], size: usize| distribution.binary_search_by_key(&size, |&(s, _)| s).map_or(T::zero(), |i| distribution[i].1);
+        for size in sizes
+        {
+            writeln!(&mut file, "{size}, {}, {}", density(&like_spin, size), density(&fortuin_kasteleyn, size))?;
+        }
+        file.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use monte_carlo_lib::clusters::Cluster;
+    use crate::{ExperimentParam, IsingHamiltonian, CalculationError, perform_metropolis_computation_parallel};
+
+    #[test]
+    fn test_cluster_averages()
+    {
+        let cluster  = |size, wraps_x| Cluster { size, wraps_x, wraps_y: false };
+        let mut averages = ClusterAverages::<f64>::default();
+        averages.add(&ClusterStatistics { clusters: vec![cluster(6, true), cluster(1, false), cluster(1, false)], number_of_spins: 8 });
+        averages.add(&ClusterStatistics { clusters: vec![cluster(4, false), cluster(2, false), cluster(1, false), cluster(1, false)], number_of_spins: 8 });
+        assert_eq!(averages.largest_fraction(), 0.625_f64);
+        assert_eq!(averages.wrapping_probability(), 0.5_f64);
+        assert_eq!(averages.clusters_per_spin(), 7_f64 / 16_f64);
+        assert_eq!(averages.size_density(), [(1, 0.25_f64), (2, 1_f64 / 16_f64), (4, 1_f64 / 16_f64), (6, 1_f64 / 16_f64)]);
+        // sum_s s n_s = 1
+        assert_eq!(averages.size_density().iter().map(|&(s, n)| s as f64 * n).sum::<f64>(), 1_f64);
+    }
+
+    #[test]
+    fn test_driver_needs_a_labelable_array()
+    {
+        let param = ExperimentParam
+        {
+            temperatures:      vec![1_f64],
+            hamiltonian:       IsingHamiltonian::new(1_f64, 0_f64),
+            measurement_steps: 10,
+            cluster_interval:  1,
+            ..Default::default()
+        };
+        let results = perform_metropolis_computation_parallel::<i8,f64,_>(6, 6, &param).unwrap();
+        assert!(results[0].clusters.like_spin.largest_fraction() > 0.5_f64);
+        assert!(matches!(perform_metropolis_computation_parallel::<i8,f64,_>(2, 6, &param), Err(CalculationError::IncompatibleGeometry(_))));
+    }
+}
@@ -0,0 +1,180 @@
+// Adaptive search of Tc from the crossings of the Binder cumulants U4(T, L) of several sizes, in place of "run, look, refine the temperatures":
+// every round runs all the L x L lattices (perform_metropolis_computation_parallel) on equally spaced temperatures of the bracket, locates the
+// crossing of the two largest sizes, and shrinks the bracket to one grid spacing on both sides of it, until the spacing is below the target
+// precision: the next spacing is 2 / (points - 1) times the current one, hence at least 4 points per round. Without crossing, the bracket is widened around its centre. The statistical error of the crossing is the jackknife one,
+// the crossing being recomputed without each bin of both runs.
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{ExperimentParam, CalculationError, MonteCarloResults, perform_metropolis_computation_parallel, statistics};
+use crate::finite_size_scaling::{SizeData, binder_crossing};
+use monte_carlo_lib::hamiltonian::Hamiltonian;
+use periodic_array_2d_lib::SpinValue;
+
+const MIN_POINTS: usize = 4;    // with 3 points the spacing would stay the same from one round to the next, with 2 it would double
+
+#[derive(Debug, Clone)]
+pub struct TcSearch
+{
+    pub sizes: Vec<usize>,              // L of the L x L lattices, the last two give the estimate
+    pub bracket: (f64, f64),            // first temperature range
+    pub points: usize,                  // temperatures per round
+    pub precision: f64,                 // target grid spacing around the crossing
+    pub max_rounds: usize,
+}
+
+// Binder cumulants (value, jackknife error) of every size at the temperatures of one round
+#[derive(Debug, Default, Clone)]
+pub struct CrossingRound
+{
+    pub temperatures: Vec<f64>,
+    pub binder_cumulants: Vec<Vec<(f64, f64)>>,     // by size, then temperature
+    pub crossings: Vec<Option<f64>>,                 // of the consecutive sizes (L_0, L_1), (L_1, L_2)...
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TcSearchResult
+{
+    pub critical_temperature: f64,      // NaN if the two largest sizes never crossed
+    pub error: f64,
+    pub converged: bool,                // the last grid spacing reached the precision
+    pub rounds: Vec<CrossingRound>,
+}
+
+fn binder_data(size: usize, temperatures: &[f64], results: &[MonteCarloResults<f64>]) -> SizeData
+{
+    SizeData
+    {
+        size:            size as f64,
+        temperatures:    temperatures.to_vec(),
+        binder_cumulant: results.iter().map(|r| r.binder_cumulant()).zip(binder_errors(results)).collect(),
+        ..Default::default()
+    }
+}
+
+fn binder_errors(results: &[MonteCarloResults<f64>]) -> Vec<f64>
+{
+    results.iter().map(|r| statistics::jackknife_error(&r.jackknife_samples().iter().map(|s| s.binder_cumulant()).collect::<Vec<f64>>())).collect()
+}
+
+// Jackknife error of the crossing of two runs with the same number of bins, NaN if the curves don't cross without one of the bins
+// (leaving those bins out would underestimate the error when the crossing is the least determined)
+fn crossing_error(small: (usize, &[MonteCarloResults<f64>]), large: (usize, &[MonteCarloResults<f64>]), temperatures: &[f64]) -> f64
+{
+    let samples     = |results: &[MonteCarloResults<f64>]| results.iter().map(|r| r.jackknife_samples()).collect::<Vec<_>>();
+    let (small_samples, large_samples) = (samples(small.1), samples(large.1));
+    let binder      = |size: usize, samples: &[Vec<MonteCarloResults<f64>>], bin: usize| SizeData
+    {
+        size:            size as f64,
+        temperatures:    temperatures.to_vec(),
+        binder_cumulant: samples.iter().map(|s| (s[bin].binder_cumulant(), 0_f64)).collect(),
+        ..Default::default()
+    };
+    let number_of_bins = small_samples.iter().chain(&large_samples).map(Vec::len).min().unwrap_or(0);
+    let crossings: Option<Vec<f64>> = (0..number_of_bins).map(|bin| binder_crossing(&binder(small.0, &small_samples, bin), &binder(large.0, &large_samples, bin))).collect();
+    crossings.map_or(f64::NAN, |crossings| statistics::jackknife_error(&crossings))
+}
+
+pub fn search_critical_temperature<S, H>(search: &TcSearch, param: &ExperimentParam<f64, H>) -> Result<TcSearchResult, CalculationError>
+    where S: SpinValue<f64> + Send,
+          H: Hamiltonian<S, f64> + Clone + Sync,
+{
+    if search.sizes.len() < 2 || search.points < MIN_POINTS || search.bracket.0 >= search.bracket.1 || search.bracket.0 <= 0_f64
+    {
+        return Err(CalculationError::ScalingError(format!("The Tc search needs two sizes, {MIN_POINTS} points & a positive temperature bracket")));
+    }
+    let mut result        = TcSearchResult { critical_temperature: f64::NAN, error: f64::NAN, ..Default::default() };
+    let (mut low, mut high) = search.bracket;
+    for _ in 0..search.max_rounds
+    {
+        let spacing      = (high - low) / (search.points - 1) as f64;
+        let temperatures: Vec<f64> = (0..search.points).map(|i| low + spacing * i as f64).collect();
+        let round_param  = ExperimentParam { temperatures: temperatures.clone(), ..param.with_hamiltonian(param.hamiltonian.clone()) };
+        let runs         = search.sizes.iter().map(|&size| perform_metropolis_computation_parallel::<S,f64,H>(size, size, &round_param)).collect::<Result<Vec<_>, _>>()?;
+        let data: Vec<SizeData> = search.sizes.iter().zip(&runs).map(|(&size, results)| binder_data(size, &temperatures, results)).collect();
+        let crossings: Vec<Option<f64>> = data.windows(2).map(|pair| binder_crossing(&pair[0], &pair[1])).collect();
+        let estimate     = *crossings.last().unwrap();
+        result.rounds.push(CrossingRound { temperatures: temperatures.clone(), binder_cumulants: data.into_iter().map(|d| d.binder_cumulant).collect(), crossings });
+
+        match estimate
+        {
+            Some(tc) =>
+            {
+                let last = search.sizes.len() - 1;
+                result.critical_temperature = tc;
+                result.error                = crossing_error((search.sizes[last - 1], &runs[last - 1]), (search.sizes[last], &runs[last]), &temperatures);
+                if spacing <= search.precision
+                {
+                    result.converged = true;
+                    break;
+                }
+                (low, high) = ((tc - spacing).max(0.5_f64 * tc), tc + spacing);
+            },
+            None =>
+            {
+                let (centre, width) = ((low + high) / 2_f64, high - low);
+                (low, high)         = ((centre - width).max(0.5_f64 * low), centre + width);
+            },
+        }
+    }
+    Ok(result)
+}
+
+impl TcSearchResult
+{
+    // One line "critical_temperature, critical_temperature_error, converged, rounds" (converged: 1 or 0), then an empty line &
+    // "round, size, temp, binder_cumulant, binder_cumulant_error" for every run
+    pub fn write_to_file(&self, file_name: &Path, sizes: &[usize]) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "critical_temperature, critical_temperature_error, converged, rounds")?;
+        writeln!(&mut file, "{}, {}, {}, {}", self.critical_temperature, self.error, self.converged as usize, self.rounds.len())?;
+        writeln!(&mut file)?;
+        writeln!(&mut file, "round, size, temp, binder_cumulant, binder_cumulant_error")?;
+        for (round, crossing_round) in self.rounds.iter().enumerate()
+        {
+            for (size, binder_cumulants) in sizes.iter().zip(&crossing_round.binder_cumulants)
+            {
+                for (temp, (binder, error)) in crossing_round.temperatures.iter().zip(binder_cumulants)
+                {
+                    writeln!(&mut file, "{round}, {size}, {temp}, {binder}, {error}")?;
+                }
+            }
+        }
+        file.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{IsingHamiltonian, UpdateAlgorithm};
+    use monte_carlo_lib::lattice::exact_critical_temperature;
+    use periodic_array_2d_lib::LatticeGeometry;
+
+    #[test]
+    fn test_search_finds_the_square_lattice_tc()
+    {
+        let param = ExperimentParam
+        {
+            hamiltonian:          IsingHamiltonian::new(1_f64, 0_f64),
+            algorithm:            UpdateAlgorithm::Wolff,
+            thermalisation_steps: 200,
+            measurement_steps:    8000,
+            ..Default::default()
+        };
+        // The bracket doesn't contain the crossing at first: it has to be widened, then refined
+        let search = TcSearch { sizes: vec![4, 8], bracket: (2.5_f64, 2.9_f64), points: 5, precision: 0.1_f64, max_rounds: 8 };
+        let result = search_critical_temperature::<i8, _>(&search, &param).unwrap();
+        assert!(result.converged, "{result:?}");
+        assert!(result.rounds[0].crossings[0].is_none());
+        let exact  = exact_critical_temperature(LatticeGeometry::Square, 1_f64).unwrap();
+        assert!((result.critical_temperature - exact).abs() < 0.05_f64 + 3_f64 * result.error, "Tc = {} +- {}, exact {exact}", result.critical_temperature, result.error);
+        assert!(result.error > 0_f64 && result.error < 0.1_f64, "{result:?}");
+
+        // The grid has to shrink from one round to the next
+        assert!(search_critical_temperature::<i8, _>(&TcSearch { points: 3, ..search }, &param).is_err());
+    }
+}
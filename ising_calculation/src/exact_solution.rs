@@ -0,0 +1,441 @@
+// Exact results of the zero-field ferromagnetic (J > 0) Ising model on the square lattice (k_B = 1), all per spin:
+// * infinite lattice (Onsager, Yang): energy, specific heat & spontaneous magnetisation,
+// * finite rows x columns torus (Kaufman, Ferdinand & Fisher): log of the partition function, energy & specific heat.
+// Any (small) lattice, coupling & field can also be summed over exactly with enumerate_results(), used to validate the update algorithms.
+// Used as a reference for the Monte-Carlo results (see MonteCarloResults::write_to_file()).
+use std::f64::consts::PI;
+use num::Complex;
+use periodic_array_2d_lib::{SpinLattice, SpinValue};
+use monte_carlo_lib::hamiltonian::Hamiltonian;
+
+use crate::MonteCarloResults;
+
+pub const MAX_ENUMERATED_SPINS: usize = 25;
+
+// Complete elliptic integrals of the first & second kind K(k) & E(k) (modulus k), from the arithmetic-geometric mean
+fn elliptic_integrals(modulus: f64) -> (f64, f64)
+{
+    let mut a        = 1_f64;
+    let mut b        = (1_f64 - modulus * modulus).max(0_f64).sqrt();
+    if b == 0_f64
+    {
+        return (f64::INFINITY, 1_f64);
+    }
+    let mut c        = modulus;
+    let mut power    = 0.5_f64;                 // 2^(n-1)
+    let mut sum      = power * c * c;
+    while c.abs() > f64::EPSILON * a
+    {
+        (a, b, c) = ((a + b) / 2_f64, (a * b).sqrt(), (a - b) / 2_f64);
+        power    *= 2_f64;
+        sum      += power * c * c;
+    }
+    let first_kind = PI / (2_f64 * a);
+    (first_kind, first_kind * (1_f64 - sum))
+}
+
+fn onsager_modulus(temp: f64, interaction_term: f64) -> (f64, f64)
+{
+    let coupling = 2_f64 * interaction_term / temp;  // 2 beta J
+    (coupling, 2_f64 * coupling.sinh() / coupling.cosh().powi(2))
+}
+
+// u = -J coth(2 beta J) [1 + 2/pi (2 tanh^2(2 beta J) - 1) K(k)],   k = 2 sinh(2 beta J) / cosh^2(2 beta J)
+pub fn onsager_energy(temp: f64, interaction_term: f64) -> f64
+{
+    let (coupling, modulus) = onsager_modulus(temp, interaction_term);
+    let factor              = 2_f64 * coupling.tanh().powi(2) - 1_f64;
+    let first_kind          = elliptic_integrals(modulus).0;
+    let elliptic_term       = if first_kind.is_finite() {factor * first_kind} else {0_f64}; // K(k) diverges at Tc, where the factor vanishes
+    -interaction_term / coupling.tanh() * (1_f64 + 2_f64 / PI * elliptic_term)
+}
+
+// c = du/dT = -(a^2 / 2J) du/da with a = 2 beta J, using dK/dk = E(k) / (k (1 - k^2)) - K(k) / k & dk/da = 2 (1 - 2 tanh^2 a) / cosh a.
+// Infinite at Tc.
+pub fn onsager_specific_heat(temp: f64, interaction_term: f64) -> f64
+{
+    let (coupling, modulus)       = onsager_modulus(temp, interaction_term);
+    let (first_kind, second_kind) = elliptic_integrals(modulus);
+    if !first_kind.is_finite()
+    {
+        return f64::INFINITY;
+    }
+    let tanh                = coupling.tanh();
+    let factor              = 2_f64 * tanh * tanh - 1_f64;
+    let first_kind_slope    = second_kind / (modulus * (1_f64 - modulus * modulus)) - first_kind / modulus;
+    let modulus_slope       = -2_f64 * factor / coupling.cosh();
+    let bracket             = 1_f64 + 2_f64 / PI * factor * first_kind;
+    let bracket_slope       = 2_f64 / PI * (4_f64 * tanh * (1_f64 - tanh * tanh) * first_kind + factor * first_kind_slope * modulus_slope);
+    let energy_slope        = bracket / coupling.sinh().powi(2) - bracket_slope / tanh;  // d(u/J)/da
+    -coupling * coupling / 2_f64 * energy_slope
+}
+
+// m = (1 - sinh^-4(2 beta J))^(1/8) below Tc, 0 above
+pub fn onsager_magnetisation(temp: f64, interaction_term: f64) -> f64
+{
+    let coupling = 2_f64 * interaction_term / temp;
+    (1_f64 - coupling.sinh().powi(-4)).max(0_f64).powf(0.125)
+}
+
+
+// f(K), f'(K) & f''(K) for the functions of K = beta J entering the partition function
+#[derive(Debug, Clone, Copy)]
+struct Derivatives
+{
+    value: f64,
+    first: f64,
+    second: f64,
+}
+
+// Kaufman's spectrum: cosh(gamma_k) = cosh(2K) coth(2K) - cos(pi k / columns) for k > 0, gamma_0 = 2K + ln(tanh(K)) (negative below Tc)
+fn kaufman_gamma(k: usize, columns: usize, coupling: f64) -> Derivatives
+{
+    let (sinh_2k, cosh_2k) = ((2_f64 * coupling).sinh(), (2_f64 * coupling).cosh());
+    if k == 0
+    {
+        return Derivatives
+        {
+            value:  2_f64 * coupling + coupling.tanh().ln(),
+            first:  2_f64 + 2_f64 / sinh_2k,
+            second: -4_f64 * cosh_2k / sinh_2k.powi(2),
+        }
+    }
+    let c        = cosh_2k * cosh_2k / sinh_2k;
+    let c_first  = 2_f64 * cosh_2k * (1_f64 - 1_f64 / sinh_2k.powi(2));
+    let c_second = 4_f64 * sinh_2k - 4_f64 / sinh_2k + 8_f64 * cosh_2k.powi(2) / sinh_2k.powi(3);
+    let gamma    = (c - (PI * k as f64 / columns as f64).cos()).acosh();
+    let first    = c_first / gamma.sinh();
+    Derivatives { value: gamma, first, second: (c_second - gamma.cosh() * first * first) / gamma.sinh() }
+}
+
+// ln|Z_i| (with its derivatives) & sign of Z_i = prod_r 2 cosh(rows gamma_k / 2) or prod_r 2 sinh(rows gamma_k / 2), k = 2r + offset
+fn kaufman_term(rows: usize, columns: usize, coupling: f64, offset: usize, hyperbolic_sine: bool) -> (Derivatives, f64)
+{
+    let half_rows  = rows as f64 / 2_f64;
+    let mut term   = Derivatives { value: 0_f64, first: 0_f64, second: 0_f64 };
+    let mut sign   = 1_f64;
+    for r in 0..columns
+    {
+        let gamma = kaufman_gamma(2 * r + offset, columns, coupling);
+        let x     = half_rows * gamma.value;
+        // d ln(2 cosh x) = tanh(x) dx,   d ln|2 sinh x| = coth(x) dx
+        let (log_factor, ratio, ratio_derivative) = match hyperbolic_sine
+        {
+            false => (x.abs() + (-2_f64 * x.abs()).exp().ln_1p(), x.tanh(), x.cosh().powi(-2)),
+            true  => (x.abs() + (-(-2_f64 * x.abs()).exp()).ln_1p(), 1_f64 / x.tanh(), -x.sinh().powi(-2)),
+        };
+        if hyperbolic_sine && x.is_sign_negative()
+        {
+            sign = -sign;
+        }
+        term.value  += log_factor;
+        term.first  += half_rows * ratio * gamma.first;
+        term.second += half_rows * half_rows * ratio_derivative * gamma.first.powi(2) + half_rows * ratio * gamma.second;
+    }
+    (term, sign)
+}
+
+// ln Z & its derivatives with respect to K, Z = 1/2 (2 sinh(2K))^(N/2) (Z_1 + Z_2 + Z_3 + Z_4)
+fn torus_log_partition_derivatives(rows: usize, columns: usize, coupling: f64) -> Derivatives
+{
+    let n_spins = (rows * columns) as f64;
+    let (sinh_2k, cosh_2k) = ((2_f64 * coupling).sinh(), (2_f64 * coupling).cosh());
+    let terms   = [(1, false), (1, true), (0, false), (0, true)].map(|(offset, sine)| kaufman_term(rows, columns, coupling, offset, sine));
+
+    // Signed log-sum-exp, Z_4 vanishing exactly at gamma_0 = 0
+    let terms   = terms.iter().filter(|(term, _)| term.value.is_finite());
+    let largest = terms.clone().map(|(term, _)| term.value).fold(f64::NEG_INFINITY, f64::max);
+    let total   = terms.clone().map(|(term, sign)| sign * (term.value - largest).exp()).sum::<f64>();
+    let weights = terms.map(|(term, sign)| (sign * (term.value - largest).exp() / total, term));
+    let (first, second) = weights.fold((0_f64, 0_f64), |(first, second), (weight, term)| (first + weight * term.first, second + weight * (term.second + term.first.powi(2))));
+
+    Derivatives
+    {
+        value:  -2_f64.ln() + n_spins / 2_f64 * (2_f64 * sinh_2k).ln() + largest + total.ln(),
+        first:  n_spins * cosh_2k / sinh_2k + first,
+        second: -2_f64 * n_spins / sinh_2k.powi(2) + second - first * first,
+    }
+}
+
+fn check_torus(rows: usize, columns: usize, temp: f64, interaction_term: f64)
+{
+    assert!(rows > 0 && columns > 0, "Empty lattice");
+    assert!(temp > 0_f64 && interaction_term > 0_f64, "Only for T > 0 & ferromagnetic couplings");
+}
+
+pub fn torus_log_partition_function(rows: usize, columns: usize, temp: f64, interaction_term: f64) -> f64
+{
+    check_torus(rows, columns, temp, interaction_term);
+    torus_log_partition_derivatives(rows, columns, interaction_term / temp).value
+}
+
+// u = -J d(ln Z)/dK / N
+pub fn torus_energy(rows: usize, columns: usize, temp: f64, interaction_term: f64) -> f64
+{
+    check_torus(rows, columns, temp, interaction_term);
+    -interaction_term * torus_log_partition_derivatives(rows, columns, interaction_term / temp).first / (rows * columns) as f64
+}
+
+// c = K^2 d^2(ln Z)/dK^2 / N
+pub fn torus_specific_heat(rows: usize, columns: usize, temp: f64, interaction_term: f64) -> f64
+{
+    check_torus(rows, columns, temp, interaction_term);
+    let coupling = interaction_term / temp;
+    coupling * coupling * torus_log_partition_derivatives(rows, columns, coupling).second / (rows * columns) as f64
+}
+
+// Calls visit(spin_sum, energy, sigma_qx, sigma_qy) for all the 2^N states of spins, in Gray code order so that every state costs a single delta_energy().
+// sigma_qx = sum_n s_n exp(i qx x_n), qx = 2pi / columns & sigma_qy = sum_n s_n exp(i qy y_n), qy = 2pi / rows, as in the FourierTransformer.
+fn visit_states<L, S, H>(spins: &mut L, hamiltonian: &H, mut visit: impl FnMut(f64, f64, Complex<f64>, Complex<f64>))
+    where L: SpinLattice<S,f64>,
+          S: SpinValue<f64>,
+          H: Hamiltonian<S,f64>,
+{
+    let n_spins = spins.total_number() as usize;
+    let columns = spins.columns() as usize;
+    let rows    = spins.rows() as usize;
+    let kernels: Vec<Complex<f64>>   = (0..columns).map(|x| (Complex::i() * 2_f64 * PI * x as f64 / columns as f64).exp()).collect();
+    let kernels_y: Vec<Complex<f64>> = (0..rows).map(|y| (Complex::i() * 2_f64 * PI * y as f64 / rows as f64).exp()).collect();
+    (0..n_spins).for_each(|n| *spins.at_index_mut(n) = S::one());
+
+    let mut spin_sum = n_spins as f64;
+    let mut energy   = hamiltonian.total_energy(spins);
+    let mut sigma_qx = (0..n_spins).map(|n| kernels[n % columns]).sum::<Complex<f64>>();
+    let mut sigma_qy = (0..n_spins).map(|n| kernels_y[(n / columns) % rows]).sum::<Complex<f64>>();
+    visit(spin_sum, energy, sigma_qx, sigma_qy);
+    for state in 1..(1_usize << n_spins)
+    {
+        let n        = state.trailing_zeros() as usize;
+        energy      += hamiltonian.delta_energy(spins, n);
+        let spin     = -spins.at_index(n);
+        *spins.at_index_mut(n) = spin;
+        spin_sum    += 2_f64 * spin.as_();
+        sigma_qx    += kernels[n % columns] * (2_f64 * spin.as_());
+        sigma_qy    += kernels_y[(n / columns) % rows] * (2_f64 * spin.as_());
+        visit(spin_sum, energy, sigma_qx, sigma_qy);
+    }
+}
+
+// Exact Boltzmann averages of the observables measured by the Monte-Carlo driver, ie <|M|>, <M^2>, <|M|^3>, <M^4>, <E>, <E^2>, S(0), S(qx) & S(qy),
+// for the lattice of spins (any geometry & boundaries, its spins are overwritten) with at most MAX_ENUMERATED_SPINS spins.
+pub fn enumerate_results<L, S, H>(spins: &mut L, hamiltonian: &H, temp: f64) -> MonteCarloResults<f64>
+    where L: SpinLattice<S,f64>,
+          S: SpinValue<f64>,
+          H: Hamiltonian<S,f64>,
+{
+    let n_spins = spins.total_number() as usize;
+    assert!(n_spins <= MAX_ENUMERATED_SPINS, "{n_spins} spins: too many states to enumerate");
+    assert!(temp > 0_f64, "Only for T > 0");
+
+    // Boltzmann weights relative to the ground state, to avoid overflows at low temperatures
+    let mut ground_energy = f64::INFINITY;
+    visit_states(spins, hamiltonian, |_, energy, _, _| ground_energy = ground_energy.min(energy));
+
+    let mut partition_function = 0_f64;
+    let mut sums               = MonteCarloResults::<f64>::default();
+    visit_states(spins, hamiltonian, |spin_sum, energy, sigma_qx, sigma_qy|
+    {
+        let weight               = (-(energy - ground_energy) / temp).exp();
+        partition_function      += weight;
+        sums.spins_sum_avg      += weight * spin_sum.abs();
+        sums.spins_sqr_avg      += weight * spin_sum * spin_sum;
+        sums.spins_abs_cube_avg += weight * spin_sum.abs().powi(3);
+        sums.spins_quad_avg     += weight * spin_sum.powi(4);
+        sums.energy_avg         += weight * energy;
+        sums.energy_sqr_avg     += weight * energy * energy;
+        sums.struct_fact_q0     += weight * spin_sum * spin_sum;
+        sums.struct_fact_qx     += weight * sigma_qx.norm_sqr();
+        sums.struct_fact_qy     += weight * sigma_qy.norm_sqr();
+    });
+    MonteCarloResults
+    {
+        spins_sum_avg:      sums.spins_sum_avg / partition_function,
+        spins_sqr_avg:      sums.spins_sqr_avg / partition_function,
+        spins_abs_cube_avg: sums.spins_abs_cube_avg / partition_function,
+        spins_quad_avg:     sums.spins_quad_avg / partition_function,
+        energy_avg:         sums.energy_avg / partition_function,
+        energy_sqr_avg:     sums.energy_sqr_avg / partition_function,
+        struct_fact_q0:     sums.struct_fact_q0 / (partition_function * n_spins as f64),
+        struct_fact_qx:     sums.struct_fact_qx / (partition_function * n_spins as f64),
+        struct_fact_qy:     sums.struct_fact_qy / (partition_function * n_spins as f64),
+        ..Default::default()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{ExperimentParam, IsingHamiltonian, UpdateAlgorithm, perform_metropolis_computation_parallel};
+    use crate::statistics;
+    use periodic_array_2d_lib::{LatticeGeometry, BoundaryCondition, PeriodicArray2D};
+
+    // Monte-Carlo averages have to be within SIGMAS error bars of the exact values (a few hundred comparisons in all)
+    const SIGMAS: f64 = 5_f64;
+
+    // Sum over the 2^(rows*columns) states: (ln Z, <E>/N, C/N)
+    fn enumerate_torus(rows: usize, columns: usize, temp: f64) -> (f64, f64, f64)
+    {
+        let n_spins = rows * columns;
+        let spin    = |state: usize, i: usize, j: usize| if (state >> ((i % rows) * columns + j % columns)) & 1 == 1 {1_f64} else {-1_f64};
+        let (mut z, mut e_sum, mut e_sqr_sum) = (0_f64, 0_f64, 0_f64);
+        for state in 0..(1 << n_spins)
+        {
+            let energy  = -(0..rows).flat_map(|i| (0..columns).map(move |j| (i, j))).map(|(i, j)| spin(state, i, j) * (spin(state, i + 1, j) + spin(state, i, j + 1))).sum::<f64>();
+            let weight  = (-energy / temp).exp();
+            z          += weight;
+            e_sum      += energy * weight;
+            e_sqr_sum  += energy * energy * weight;
+        }
+        let energy = e_sum / z;
+        (z.ln(), energy / n_spins as f64, (e_sqr_sum / z - energy * energy) / (temp * temp * n_spins as f64))
+    }
+
+    #[test]
+    fn test_torus_matches_enumeration()
+    {
+        for (rows, columns) in [(4, 4), (3, 4), (4, 3), (2, 5)]
+        {
+            for temp in [1_f64, 2.269_f64, 3.5_f64]
+            {
+                let (log_z, energy, specific_heat) = enumerate_torus(rows, columns, temp);
+                assert!((torus_log_partition_function(rows, columns, temp, 1_f64) - log_z).abs() < 1E-9, "{rows}x{columns}, T={temp}: ln Z");
+                assert!((torus_energy(rows, columns, temp, 1_f64) - energy).abs() < 1E-9, "{rows}x{columns}, T={temp}: energy");
+                assert!((torus_specific_heat(rows, columns, temp, 1_f64) - specific_heat).abs() < 1E-8, "{rows}x{columns}, T={temp}: specific heat");
+            }
+        }
+    }
+
+    #[test]
+    fn test_onsager_solution()
+    {
+        let t_c = 2_f64 / (1_f64 + 2_f64.sqrt()).ln();
+        assert!((onsager_energy(t_c, 1_f64) + 2_f64.sqrt()).abs() < 1E-12, "u(Tc) = -sqrt(2)");
+        assert!((onsager_magnetisation(2_f64, 1_f64) - 0.9113194).abs() < 1E-6);
+        assert_eq!(onsager_magnetisation(2.5_f64, 1_f64), 0_f64);
+        assert!((onsager_energy(0.5_f64, 1_f64) + 2_f64).abs() < 1E-6, "ground state");
+        // Large tori converge to the infinite lattice away from Tc
+        for temp in [1.8_f64, 3_f64]
+        {
+            assert!((torus_energy(64, 64, temp, 1_f64) - onsager_energy(temp, 1_f64)).abs() < 1E-8, "T={temp}: energy");
+            assert!((torus_specific_heat(64, 64, temp, 1_f64) - onsager_specific_heat(temp, 1_f64)).abs() < 1E-6, "T={temp}: specific heat");
+        }
+    }
+
+    #[test]
+    fn test_monte_carlo_matches_exact_torus()
+    {
+        const L: usize = 8;
+        let n_spins    = (L * L) as f64;
+        for algorithm in [UpdateAlgorithm::Metropolis, UpdateAlgorithm::Wolff]
+        {
+            let param = ExperimentParam
+            {
+                temperatures:         vec![1.8_f64, 2.3_f64, 3_f64],
+                hamiltonian:          IsingHamiltonian::new(1_f64, 0_f64),
+                algorithm,
+                thermalisation_steps: 1000,
+                measurement_steps:    40000,
+                ..Default::default()
+            };
+            let results = perform_metropolis_computation_parallel::<i8,f64,_>(L, L, &param).unwrap();
+            for (&temp, result) in param.temperatures.iter().zip(&results)
+            {
+                let energy        = result.energy_avg / n_spins;
+                let specific_heat = (result.energy_sqr_avg - result.energy_avg.powi(2)) / (temp * temp * n_spins);
+                let exact_energy  = torus_energy(L, L, temp, 1_f64);
+                let exact_heat    = torus_specific_heat(L, L, temp, 1_f64);
+                let energy_error  = statistics::binning_error(&result.bins.iter().map(|bin| bin.energy_avg).collect::<Vec<f64>>()) / n_spins;
+                let heat_error    = statistics::jackknife_error(&result.jackknife_samples().iter().map(|s| (s.energy_sqr_avg - s.energy_avg.powi(2)) / (temp * temp * n_spins)).collect::<Vec<f64>>());
+                assert!((energy - exact_energy).abs() < SIGMAS * energy_error, "{algorithm:?}, T={temp}: E/N = {energy} +- {energy_error}, exact: {exact_energy}");
+                assert!((specific_heat - exact_heat).abs() < SIGMAS * heat_error, "{algorithm:?}, T={temp}: C/N = {specific_heat} +- {heat_error}, exact: {exact_heat}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_enumeration_matches_torus()
+    {
+        let mut spins = PeriodicArray2D::<i8,f64>::new_with(3, 4, ||1).unwrap();
+        for temp in [1_f64, 2.269_f64, 3.5_f64]
+        {
+            let exact = enumerate_results(&mut spins, &IsingHamiltonian::new(1_f64, 0_f64), temp);
+            assert!((exact.energy_avg / 12_f64 - torus_energy(3, 4, temp, 1_f64)).abs() < 1E-9, "T={temp}: energy");
+            assert!(((exact.energy_sqr_avg - exact.energy_avg.powi(2)) / (temp * temp * 12_f64) - torus_specific_heat(3, 4, temp, 1_f64)).abs() < 1E-8, "T={temp}: specific heat");
+            assert!((exact.struct_fact_q0 - exact.spins_sqr_avg / 12_f64).abs() < 1E-12);
+        }
+    }
+
+    type Observable = fn(&MonteCarloResults<f64>) -> f64;
+
+    // Every update algorithm & every observable of perform_metropolis_computation_parallel() against the exact averages,
+    // on small lattices with couplings, fields & boundaries for which the algorithms take different code paths
+    #[test]
+    fn test_algorithms_match_enumeration()
+    {
+        let cases = [
+            (LatticeGeometry::Square,     BoundaryCondition::Periodic,     4, 4,  1_f64,   0_f64, 2.5_f64),
+            (LatticeGeometry::Square,     BoundaryCondition::Periodic,     4, 4,  1_f64, 0.3_f64,   3_f64),
+            (LatticeGeometry::Square,     BoundaryCondition::Open,         3, 4,  1_f64,   0_f64,   2_f64),
+            (LatticeGeometry::Triangular, BoundaryCondition::Periodic,     4, 4, -1_f64, 0.5_f64,   2_f64),
+            (LatticeGeometry::Square,     BoundaryCondition::FixedUp,      3, 3,  1_f64,   0_f64,   3_f64),
+            (LatticeGeometry::Square,     BoundaryCondition::Helical,      4, 4,  1_f64, 0.2_f64, 2.5_f64),
+            (LatticeGeometry::Triangular, BoundaryCondition::Helical,      3, 4,  1_f64,   0_f64,   4_f64),
+            (LatticeGeometry::Square,     BoundaryCondition::Antiperiodic, 4, 4,  1_f64,   0_f64,   2_f64),
+            (LatticeGeometry::Honeycomb,  BoundaryCondition::Periodic,     4, 4,  1_f64, 0.1_f64, 1.5_f64),
+        ];
+        for (lattice, boundary, rows, columns, interaction_term, extern_mag, temp) in cases
+        {
+            let hamiltonian = IsingHamiltonian::new(interaction_term, extern_mag);
+            let mut spins   = PeriodicArray2D::<i8,f64>::new_with_geometry(rows as i32, columns as i32, lattice, ||1).unwrap();
+            spins.set_boundary_conditions(boundary, boundary).unwrap();
+            let exact       = enumerate_results(&mut spins, &hamiltonian, temp);
+
+            for algorithm in [UpdateAlgorithm::Metropolis, UpdateAlgorithm::HeatBath, UpdateAlgorithm::Wolff]
+            {
+                let param = ExperimentParam
+                {
+                    temperatures:         vec![temp; 4],
+                    hamiltonian,
+                    lattice,
+                    boundaries:           (boundary, boundary),
+                    algorithm,
+                    thermalisation_steps: 1000,
+                    measurement_steps:    20000,
+                    measure_struct_fact:  true,
+                    record_time_series:   true,
+                    ..Default::default()
+                };
+                let results = perform_metropolis_computation_parallel::<i8,f64,_>(rows, columns, &param).unwrap();
+                for result in &results
+                {
+                    let series = &result.time_series;
+                    assert_eq!(series.len(), param.measurement_steps);
+                    assert!((series.energy.iter().sum::<f64>() / series.len() as f64 - result.energy_avg).abs() < 1E-9 * result.energy_avg.abs());
+                    assert!((series.spin_sum.iter().map(|m| m * m).sum::<f64>() / series.len() as f64 - result.spins_sqr_avg).abs() < 1E-9 * result.spins_sqr_avg);
+                }
+                // Average of the independent runs, with the error bars of their bins
+                let average = |observable: Observable| results.iter().map(observable).sum::<f64>() / results.len() as f64;
+                let error   = |observable: Observable| results.iter().map(|r| statistics::binning_error(&r.bins.iter().map(observable).collect::<Vec<f64>>()).powi(2)).sum::<f64>().sqrt() / results.len() as f64;
+                let observables: [(&str, Observable); 10] = [
+                    ("<E>",           |r| r.energy_avg),
+                    ("<|M|>",         |r| r.spins_sum_avg),
+                    ("var(E)",        |r| r.energy_sqr_avg - r.energy_avg.powi(2)),
+                    ("var(|M|)",      |r| r.spins_sqr_avg - r.spins_sum_avg.powi(2)),
+                    ("<|M|^3>",       |r| r.spins_abs_cube_avg),
+                    ("<M^4>",         |r| r.spins_quad_avg),
+                    ("S(0)",          |r| r.struct_fact_q0),
+                    ("S(qx)",         |r| r.struct_fact_qx),
+                    ("S(qy)",         |r| r.struct_fact_qy),
+                    ("U4",            |r| r.binder_cumulant()),
+                ];
+                for (name, observable) in observables
+                {
+                    let (measured, expected, sigma) = (average(observable), observable(&exact), error(observable));
+                    assert!((measured - expected).abs() < SIGMAS * sigma + 1E-9 * expected.abs(), "{lattice:?} {boundary:?} J={interaction_term} h={extern_mag}, {algorithm:?}: {name} = {measured} +- {sigma}, exact: {expected}");
+                }
+            }
+        }
+    }
+}
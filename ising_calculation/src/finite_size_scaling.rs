@@ -0,0 +1,374 @@
+// Finite-size-scaling collapse of the summary files (MonteCarloResults::write_to_file) of several lattice sizes L:
+//     U(T, L)                  = f_U((T - Tc) L^{1/nu})
+//     m(T, L) L^{beta/nu}      = f_m((T - Tc) L^{1/nu})
+//     chi(T, L) L^{-gamma/nu}  = f_chi((T - Tc) L^{1/nu})
+// The quality of a collapse is measured like Houdayer & Hartmann (PRB 70, 014418): every point is compared with the linear interpolation
+// of each other size at the same scaling variable, in units of the combined error bars (S ~ 1 when the curves agree within their errors).
+// Tc & nu come from the Binder cumulant, which has no prefactor, then beta/nu & gamma/nu from m & chi with Tc & nu fixed.
+// The errors are the spread of the fits to parametric bootstrap resamples (every point moved by a gaussian of its error bar).
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use monte_carlo_lib::MonteCarloRngInterface;
+use crate::CalculationError;
+
+const RELATIVE_ERROR_FALLBACK: f64 = 1E-2;     // error bars of the files without them (older runs), relative to the value
+const SIMPLEX_ITERATIONS: usize    = 400;
+const SIMPLEX_RESTARTS: usize      = 3;        // the simplex is rebuilt around the last minimum, in case it collapsed too early
+
+// (value, error) of one observable at every temperature
+#[derive(Debug, Default, Clone)]
+pub struct SizeData
+{
+    pub size: f64,
+    pub temperatures: Vec<f64>,
+    pub magnetisation: Vec<(f64, f64)>,
+    pub susceptibility: Vec<(f64, f64)>,
+    pub binder_cumulant: Vec<(f64, f64)>,
+}
+
+impl SizeData
+{
+    // The columns are found by name, so that the optional ones (exact reference...) don't matter.
+    // Missing error columns (files written before the error bars) give NaN errors, see has_error_bars()
+    pub fn read_from_file(size: f64, file_name: &Path, temperature_range: Option<(f64, f64)>) -> std::io::Result<Self>
+    {
+        let content   = std::fs::read_to_string(file_name)?;
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let header    = lines.next().ok_or_else(|| std::io::Error::other("Empty summary file"))?;
+        let names: Vec<&str> = header.split(',').map(|name| name.split(':').next().unwrap_or("").trim()).collect();
+        let column    = |name: &str| names.iter().position(|&n| n == name);
+        let required  = |name: &str| column(name).ok_or_else(|| std::io::Error::other(format!("No \"{name}\" column in \"{}\"", file_name.display())));
+        let (temp, magnetisation, susceptibility, binder) = (required("temp")?, required("magnetisation")?, required("susceptibility")?, required("binder_cumulant")?);
+        let errors    = (column("magnetisation_error"), column("susceptibility_error"), column("binder_cumulant_error"));
+
+        let mut data  = SizeData { size, ..Default::default() };
+        for line in lines
+        {
+            let values = line.split(',').map(|v| v.trim().parse::<f64>()).collect::<Result<Vec<f64>, _>>().map_err(std::io::Error::other)?;
+            let value  = |index: Option<usize>| index.and_then(|i| values.get(i).copied()).unwrap_or(f64::NAN);
+            let t      = value(Some(temp));
+            if temperature_range.is_some_and(|(low, high)| t < low || t > high)
+            {
+                continue;
+            }
+            data.temperatures.push(t);
+            data.magnetisation.push((value(Some(magnetisation)), value(errors.0)));
+            data.susceptibility.push((value(Some(susceptibility)), value(errors.1)));
+            data.binder_cumulant.push((value(Some(binder)), value(errors.2)));
+        }
+        Ok(data)
+    }
+    pub fn has_error_bars(&self) -> bool
+    {
+        self.magnetisation.iter().chain(&self.susceptibility).chain(&self.binder_cumulant).any(|&(_, error)| error.is_finite() && error > 0_f64)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScalingFit
+{
+    pub critical_temperature: (f64, f64),   // (value, error)
+    pub nu: (f64, f64),
+    pub beta_over_nu: (f64, f64),
+    pub gamma_over_nu: (f64, f64),
+    pub quality: [f64; 3],                  // S of the Binder cumulant, magnetisation & susceptibility collapses
+}
+
+fn magnetisation(data: &SizeData) -> &[(f64, f64)]
+{
+    &data.magnetisation
+}
+fn susceptibility(data: &SizeData) -> &[(f64, f64)]
+{
+    &data.susceptibility
+}
+fn binder_cumulant(data: &SizeData) -> &[(f64, f64)]
+{
+    &data.binder_cumulant
+}
+
+// (x, y, dy) of one size, by increasing x
+type Curve = Vec<(f64, f64, f64)>;
+
+fn error_bar(value: f64, error: f64) -> f64
+{
+    if error.is_finite() && error > 0_f64 {error} else {RELATIVE_ERROR_FALLBACK * value.abs()}
+}
+
+// Scaling variable x = (T - Tc) L^{1/nu} & y = value L^{exponent}
+fn scaled_curves(data: &[SizeData], observable: fn(&SizeData) -> &[(f64, f64)], critical_temperature: f64, nu: f64, exponent: f64) -> Vec<Curve>
+{
+    data.iter().map(|size_data|
+    {
+        let (x_scale, y_scale) = (size_data.size.powf(1_f64 / nu), size_data.size.powf(exponent));
+        let mut curve: Curve   = size_data.temperatures.iter().zip(observable(size_data)).map(|(&t, &(value, error))| ((t - critical_temperature) * x_scale, value * y_scale, error_bar(value, error) * y_scale)).collect();
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        curve
+    }).collect()
+}
+
+// Houdayer-Hartmann quality: mean of (y - Y)^2 / (dy^2 + dY^2), (Y, dY) interpolated on the other curves which cover x.
+// Infinite if the curves don't overlap at all.
+fn collapse_quality(curves: &[Curve]) -> f64
+{
+    let (mut sum, mut comparisons) = (0_f64, 0_usize);
+    for (i, curve) in curves.iter().enumerate()
+    {
+        for other in curves.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, other)| other)
+        {
+            for &(x, y, dy) in curve
+            {
+                let segment = other.windows(2).find(|w| w[0].0 <= x && x <= w[1].0);
+                if let Some([(x0, y0, dy0), (x1, y1, dy1)]) = segment.map(|w| [w[0], w[1]])
+                {
+                    let t        = if x1 > x0 {(x - x0) / (x1 - x0)} else {0_f64};
+                    let (y_other, dy_other) = (y0 + t * (y1 - y0), dy0 + t * (dy1 - dy0));
+                    let variance = dy * dy + dy_other * dy_other;
+                    sum         += (y - y_other).powi(2) / variance.max(f64::MIN_POSITIVE);
+                    comparisons += 1;
+                }
+            }
+        }
+    }
+    if comparisons == 0 {f64::INFINITY} else {sum / comparisons as f64}
+}
+
+// Nelder-Mead simplex minimisation, starting with the steps along every axis
+fn minimise(f: impl Fn(&[f64]) -> f64, start: &[f64], steps: &[f64]) -> Vec<f64>
+{
+    let dimension = start.len();
+    let mut best  = start.to_vec();
+    for restart in 0..SIMPLEX_RESTARTS
+    {
+        let scale = 0.5_f64.powi(restart as i32);
+        let mut simplex: Vec<(Vec<f64>, f64)> = (0..=dimension).map(|k|
+        {
+            let mut point = best.clone();
+            if k > 0
+            {
+                point[k - 1] += scale * steps[k - 1];
+            }
+            let value = f(&point);
+            (point, value)
+        }).collect();
+        for _ in 0..SIMPLEX_ITERATIONS
+        {
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let centroid: Vec<f64> = (0..dimension).map(|d| simplex[..dimension].iter().map(|(p, _)| p[d]).sum::<f64>() / dimension as f64).collect();
+            let towards  = |factor: f64| -> Vec<f64> { centroid.iter().zip(&simplex[dimension].0).map(|(c, w)| c + factor * (c - w)).collect() };
+            let reflected = towards(1_f64);
+            let value_r   = f(&reflected);
+            if value_r < simplex[0].1
+            {
+                let expanded = towards(2_f64);
+                let value_e  = f(&expanded);
+                simplex[dimension] = if value_e < value_r {(expanded, value_e)} else {(reflected, value_r)};
+            }
+            else if value_r < simplex[dimension - 1].1
+            {
+                simplex[dimension] = (reflected, value_r);
+            }
+            else
+            {
+                let contracted = towards(-0.5_f64);
+                let value_c    = f(&contracted);
+                if value_c < simplex[dimension].1
+                {
+                    simplex[dimension] = (contracted, value_c);
+                }
+                else
+                {
+                    let lowest = simplex[0].0.clone();
+                    for (point, value) in simplex.iter_mut().skip(1)
+                    {
+                        point.iter_mut().zip(&lowest).for_each(|(p, l)| *p = l + 0.5_f64 * (*p - l));
+                        *value = f(point);
+                    }
+                }
+            }
+        }
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        best = simplex[0].0.clone();
+    }
+    best
+}
+
+// Linear interpolation of (temperature, value) points, NaN outside of them
+fn interpolate(temperatures: &[f64], values: &[(f64, f64)], temp: f64) -> f64
+{
+    let mut points: Vec<(f64, f64)> = temperatures.iter().zip(values).map(|(&t, &(v, _))| (t, v)).collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points.windows(2).find(|w| w[0].0 <= temp && temp <= w[1].0).map_or(f64::NAN, |w|
+    {
+        let t = if w[1].0 > w[0].0 {(temp - w[0].0) / (w[1].0 - w[0].0)} else {0_f64};
+        w[0].1 + t * (w[1].1 - w[0].1)
+    })
+}
+
+// Temperature where the Binder cumulants of two sizes cross (the one of the sizes' common temperatures where their difference
+// changes sign, refined linearly), None if they don't cross
+pub fn binder_crossing(small: &SizeData, large: &SizeData) -> Option<f64>
+{
+    let mut temperatures: Vec<f64> = small.temperatures.iter().chain(&large.temperatures).copied().collect();
+    temperatures.sort_by(f64::total_cmp);
+    temperatures.dedup();
+    let difference = |t: f64| interpolate(&large.temperatures, &large.binder_cumulant, t) - interpolate(&small.temperatures, &small.binder_cumulant, t);
+    let differences: Vec<(f64, f64)> = temperatures.iter().map(|&t| (t, difference(t))).filter(|(_, d)| d.is_finite()).collect();
+    differences.windows(2).find(|w| w[0].1 == 0_f64 || w[0].1.signum() != w[1].1.signum()).map(|w|
+    {
+        let ((t0, d0), (t1, d1)) = (w[0], w[1]);
+        if d0 == d1 {t0} else {t0 - d0 * (t1 - t0) / (d1 - d0)}
+    })
+}
+
+// Slope of ln(value at Tc) against ln L between the smallest & largest sizes: a first guess of the exponents
+fn log_slope(data: &[SizeData], observable: fn(&SizeData) -> &[(f64, f64)], critical_temperature: f64, fallback: f64) -> f64
+{
+    let (first, last) = (&data[0], &data[data.len() - 1]);
+    let value         = |d: &SizeData| interpolate(&d.temperatures, observable(d), critical_temperature);
+    let slope         = (value(last).ln() - value(first).ln()) / (last.size.ln() - first.size.ln());
+    if slope.is_finite() {slope} else {fallback}
+}
+
+// (Tc, nu, beta/nu, gamma/nu) & the qualities, starting from `start`
+fn fit_once(data: &[SizeData], start: [f64; 4], temperature_step: f64) -> ([f64; 4], [f64; 3])
+{
+    let binder_quality = |p: &[f64]| if p[1] > 0_f64 {collapse_quality(&scaled_curves(data, binder_cumulant, p[0], p[1], 0_f64))} else {f64::INFINITY};
+    let critical       = minimise(binder_quality, &start[..2], &[temperature_step, 0.2_f64]);
+    let (tc, nu)       = (critical[0], critical[1]);
+
+    let exponent_quality = |observable: fn(&SizeData) -> &[(f64, f64)], sign: f64| move |p: &[f64]| collapse_quality(&scaled_curves(data, observable, tc, nu, sign * p[0]));
+    let beta_over_nu     = minimise(exponent_quality(magnetisation, 1_f64), &start[2..3], &[0.1_f64])[0];
+    let gamma_over_nu    = minimise(exponent_quality(susceptibility, -1_f64), &start[3..4], &[0.3_f64])[0];
+    let quality          = [binder_quality(&critical), exponent_quality(magnetisation, 1_f64)(&[beta_over_nu]), exponent_quality(susceptibility, -1_f64)(&[gamma_over_nu])];
+    ([tc, nu, beta_over_nu, gamma_over_nu], quality)
+}
+
+// Gaussian number from two uniform ones (Box-Muller)
+fn gaussian<R: MonteCarloRngInterface<f64>>(rng: &mut R) -> f64
+{
+    let (u, v) = (rng.generate_rand_float(f64::MIN_POSITIVE, 1_f64), rng.generate_rand_float(0_f64, 1_f64));
+    (-2_f64 * u.ln()).sqrt() * (2_f64 * std::f64::consts::PI * v).cos()
+}
+
+// data: at least two sizes, by any order. resamples: number of bootstrap fits for the errors (0: no errors)
+pub fn fit_collapse<R: MonteCarloRngInterface<f64>>(data: &[SizeData], resamples: usize, rng: &mut R) -> Result<ScalingFit, CalculationError>
+{
+    let mut data = data.to_vec();
+    data.sort_by(|a, b| a.size.total_cmp(&b.size));
+    if data.len() < 2 || data.iter().any(|d| d.temperatures.len() < 2)
+    {
+        return Err(CalculationError::ScalingError(String::from("At least two sizes with two temperatures each are needed")));
+    }
+    let (low, high)  = data.iter().flat_map(|d| &d.temperatures).fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &t| (low.min(t), high.max(t)));
+    let start_tc     = binder_crossing(&data[0], &data[data.len() - 1]).unwrap_or((low + high) / 2_f64);
+    let start        = [start_tc, 1_f64, -log_slope(&data, magnetisation, start_tc, 0.1_f64), log_slope(&data, susceptibility, start_tc, 1.5_f64)];
+    let step         = 0.1_f64 * (high - low);
+    let (best, quality) = fit_once(&data, start, step);
+
+    let mut samples: Vec<[f64; 4]> = Vec::with_capacity(resamples);
+    let mut resampled = data.clone();
+    for _ in 0..resamples
+    {
+        for (sample, original) in resampled.iter_mut().zip(&data)
+        {
+            for (values, original_values) in [(&mut sample.magnetisation, &original.magnetisation), (&mut sample.susceptibility, &original.susceptibility), (&mut sample.binder_cumulant, &original.binder_cumulant)]
+            {
+                for (value, &(original_value, error)) in values.iter_mut().zip(original_values)
+                {
+                    value.0 = original_value + if error.is_finite() {error * gaussian(rng)} else {0_f64};
+                }
+            }
+        }
+        samples.push(fit_once(&resampled, best, step).0);
+    }
+    let error = |k: usize|
+    {
+        if samples.len() < 2
+        {
+            return f64::NAN;
+        }
+        let mean = samples.iter().map(|s| s[k]).sum::<f64>() / samples.len() as f64;
+        (samples.iter().map(|s| (s[k] - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64).sqrt()
+    };
+    Ok(ScalingFit
+    {
+        critical_temperature: (best[0], error(0)),
+        nu:                   (best[1], error(1)),
+        beta_over_nu:         (best[2], error(2)),
+        gamma_over_nu:        (best[3], error(3)),
+        quality,
+    })
+}
+
+impl ScalingFit
+{
+    // One line with the fit, then an empty line & "size, temp, scaling_variable, binder_cumulant, scaled_magnetisation, scaled_susceptibility"
+    // for every point, to plot the collapses
+    pub fn write_to_file(&self, file_name: &Path, data: &[SizeData]) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "critical_temperature, critical_temperature_error, nu, nu_error, beta_over_nu, beta_over_nu_error, gamma_over_nu, gamma_over_nu_error, binder_quality, magnetisation_quality, susceptibility_quality")?;
+        let [binder, magnetisation, susceptibility] = self.quality;
+        writeln!(&mut file, "{}, {}, {}, {}, {}, {}, {}, {}, {binder}, {magnetisation}, {susceptibility}", self.critical_temperature.0, self.critical_temperature.1,
+                 self.nu.0, self.nu.1, self.beta_over_nu.0, self.beta_over_nu.1, self.gamma_over_nu.0, self.gamma_over_nu.1)?;
+        writeln!(&mut file)?;
+        writeln!(&mut file, "size, temp, scaling_variable, binder_cumulant, scaled_magnetisation, scaled_susceptibility")?;
+        for size_data in data
+        {
+            let size = size_data.size;
+            for (k, &temp) in size_data.temperatures.iter().enumerate()
+            {
+                let x = (temp - self.critical_temperature.0) * size.powf(1_f64 / self.nu.0);
+                writeln!(&mut file, "{size}, {temp}, {x}, {}, {}, {}", size_data.binder_cumulant[k].0, size_data.magnetisation[k].0 * size.powf(self.beta_over_nu.0),
+                         size_data.susceptibility[k].0 * size.powf(-self.gamma_over_nu.0))?;
+            }
+        }
+        file.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use xorshifts::Xoshiro256pp;
+
+    #[test]
+    fn test_collapse_of_exact_scaling_data()
+    {
+        // 2D Ising exponents, smooth scaling functions & 1% error bars
+        let (tc, nu, beta_over_nu, gamma_over_nu) = (2.269_f64, 1_f64, 0.125_f64, 1.75_f64);
+        let data: Vec<SizeData> = [8_f64, 16_f64, 32_f64].iter().map(|&size|
+        {
+            let temperatures: Vec<f64> = (0..21).map(|i| 2.1_f64 + 0.02_f64 * i as f64).collect();
+            let x           = |t: f64| (t - tc) * size.powf(1_f64 / nu);
+            let with_error  = |v: f64| (v, 0.01_f64 * v);
+            SizeData
+            {
+                size,
+                binder_cumulant: temperatures.iter().map(|&t| with_error(2_f64 / 3_f64 / (1_f64 + (0.8_f64 * x(t)).exp()) + 0.05_f64)).collect(),
+                magnetisation:   temperatures.iter().map(|&t| with_error(size.powf(-beta_over_nu) * (1.2_f64 - (0.5_f64 * x(t)).tanh()))).collect(),
+                susceptibility:  temperatures.iter().map(|&t| with_error(size.powf(gamma_over_nu) * 0.05_f64 / (1_f64 + 0.1_f64 * x(t).powi(2)))).collect(),
+                temperatures,
+            }
+        }).collect();
+
+        assert!((binder_crossing(&data[0], &data[2]).unwrap() - tc).abs() < 1E-3);
+        let mut rng = Xoshiro256pp::new(1, 2, 3, 4);
+        let fit     = fit_collapse(&data, 8, &mut rng).unwrap();
+        assert!((fit.critical_temperature.0 - tc).abs() < 1E-3, "{fit:?}");
+        assert!((fit.nu.0 - nu).abs() < 1E-2, "{fit:?}");
+        assert!((fit.beta_over_nu.0 - beta_over_nu).abs() < 1E-2, "{fit:?}");
+        assert!((fit.gamma_over_nu.0 - gamma_over_nu).abs() < 1E-2, "{fit:?}");
+        // Only the linear interpolation errors are left
+        assert!(fit.quality.iter().all(|&s| s < 0.2_f64), "{fit:?}");
+        // The bootstrap errors are of the order of the 1% error bars
+        assert!(fit.critical_temperature.1 > 0_f64 && fit.critical_temperature.1 < 1E-2, "{fit:?}");
+        assert!(fit.gamma_over_nu.1 > 0_f64 && fit.gamma_over_nu.1 < 5E-2, "{fit:?}");
+
+        assert!(fit_collapse(&data[..1], 0, &mut rng).is_err());
+    }
+}
@@ -1,10 +1,11 @@
 use periodic_array_2d_lib::PhysicalObservable;
-use periodic_array_2d_lib::PeriodicArray2D;
+use periodic_array_2d_lib::SpinLattice;
 use periodic_array_2d_lib::SpinValue;
 
 pub struct FourierTransformer<P>
 {
-    fourier_kernels: Vec<Complex<P>>,
+    fourier_kernels: Vec<Complex<P>>,       // exp(i qx x), qx = 2pi / columns
+    fourier_kernels_y: Vec<Complex<P>>,     // exp(i qy y), qy = 2pi / rows
 }
 
 
@@ -12,39 +13,43 @@ use num::{Complex};
 use std::f64::consts::PI;
 impl<P> FourierTransformer<P> where P: PhysicalObservable 
 {
-    pub fn new(Lx: usize) -> Self 
+    pub fn new(Ly: usize, Lx: usize) -> Self 
     {
-        let qx  = 2_f64 * PI / Lx as f64;
-        let qx = P::from(qx).unwrap(); // cast to f32 if necessary
-
-        let fourier_kernels: Vec<Complex<P>> = (0..Lx).map(|x|  (Complex::<P>::i() * qx * (P::from(x as f64).unwrap())).exp() ).collect();
-        Self {fourier_kernels}
+        let kernels = |L: usize|
+        {
+            let q = P::from(2_f64 * PI / L as f64).unwrap(); // cast to f32 if necessary
+            (0..L).map(|x|  (Complex::<P>::i() * q * (P::from(x as f64).unwrap())).exp() ).collect::<Vec<Complex<P>>>()
+        };
+        Self {fourier_kernels: kernels(Lx), fourier_kernels_y: kernels(Ly)}
     }
-    pub fn take_fourier_transform<S>(&self, spins: &PeriodicArray2D<S,P>) -> (P, Complex<P>) 
-        where S: SpinValue<P>, 
+    // sigma_q / sqrt(N) at q = 0, (qx, 0) & (0, qy). Works for any dimension: the coordinates of site n are always x = n % columns & y = (n / columns) % rows
+    pub fn take_fourier_transform<L, S>(&self, spins: &L) -> (P, Complex<P>, Complex<P>) 
+        where L: SpinLattice<S,P>,
+              S: SpinValue<P>, 
     {
 
-        let (Ly, Lx)     = spins.shape();
-        let factor       = 1_f64 / ((Lx*Ly) as f64).sqrt();
+        let Lx           = spins.columns() as usize;
+        let Ly           = self.fourier_kernels_y.len();
+        let factor       = 1_f64 / (spins.total_number() as f64).sqrt();
         let factor_real  = P::from(factor).unwrap();
         let factor_cmplx = Complex { re: factor_real, im: P::default() };
 
         let mut spin_q0 = P::default();
         let mut spin_qx = Complex::<P>::default();
+        let mut spin_qy = Complex::<P>::default();
 
-        for y in spins.rows_range()
+        for n in 0..spins.total_number() as usize
         {
-            for x in spins.columns_range()
-            {
-                let s_real  = spins.at_unchecked(y, x).as_();
-                let s_cmplx = Complex { re: s_real, im: P::default() };
-
-                let exp_iqx = self.fourier_kernels[x as usize];  
-                
-                spin_q0 += factor_real * s_real;
-                spin_qx  = spin_qx + factor_cmplx * s_cmplx * exp_iqx;
-            }
+            let s_real  = spins.at_index(n).as_();
+            let s_cmplx = Complex { re: s_real, im: P::default() };
+
+            let exp_iqx = self.fourier_kernels[n % Lx];  
+            let exp_iqy = self.fourier_kernels_y[(n / Lx) % Ly];
+            
+            spin_q0 += factor_real * s_real;
+            spin_qx  = spin_qx + factor_cmplx * s_cmplx * exp_iqx;
+            spin_qy  = spin_qy + factor_cmplx * s_cmplx * exp_iqy;
         }
-        (spin_q0, spin_qx)
+        (spin_q0, spin_qx, spin_qy)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,134 @@
+use num_traits::Float;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::CalculationError;
+
+// Histogram of the measurements of one run, made from the time series once the run is over (so that the range is the one of the data).
+// The spin sum & the energy only take discrete levels (steps of 2 & 4J for the nearest-neighbour model): every bin holds the same number
+// of whole levels (bins narrower than the spacing being widened to one level), so that P(m) & P(E) don't get a comb pattern from the binning.
+#[derive(Debug, Default, Clone)]
+pub struct Histogram<T> where T: Float
+{
+    pub low: T,
+    pub bin_width: T,
+    pub counts: Vec<usize>,
+}
+
+impl<T> Histogram<T> where T: Float
+{
+    // At most `bins` bins of the levels low, low + spacing... high, the bins being centred on the levels when there are fewer levels than bins.
+    // When the levels don't split evenly, the empty levels needed to fill the last bin are spread over both ends.
+    pub fn from_levels(values: &[T], low: T, high: T, spacing: T, bins: usize) -> Result<Self, CalculationError>
+    {
+        if bins == 0 || spacing <= T::zero() || spacing.is_nan() || high < low
+        {
+            return Err(CalculationError::HistogramError(String::from("A histogram needs at least one bin, a positive level spacing & low <= high")));
+        }
+        let levels         = ((high - low) / spacing).round().to_usize().unwrap_or(0) + 1;
+        let levels_per_bin = levels.div_ceil(bins);
+        let number_of_bins = levels.div_ceil(levels_per_bin);
+        let padding        = number_of_bins * levels_per_bin - levels;
+        let bin_width      = spacing * T::from(levels_per_bin).unwrap();
+        let low            = low - spacing * (T::from(padding / 2).unwrap() + T::from(0.5).unwrap());   // bin edges halfway between two levels
+        let mut counts     = vec![0; number_of_bins];
+        for &value in values
+        {
+            let bin = ((value - low) / bin_width).floor().to_usize().unwrap_or(0).min(number_of_bins - 1);
+            counts[bin] += 1;
+        }
+        Ok(Self { low, bin_width, counts })
+    }
+    // Smallest gap between two different values (up to round-off in the accumulated energies): the spacing of the levels visited by the run
+    fn level_spacing(values: &[T]) -> T
+    {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let scale      = sorted.iter().fold(T::one(), |scale, v| scale.max(v.abs()));
+        let tolerance  = scale * T::from(1E-9).unwrap();
+        let spacing    = sorted.windows(2).map(|pair| pair[1] - pair[0]).filter(|&gap| gap > tolerance).fold(T::infinity(), T::min);
+        if spacing.is_finite() {spacing} else {T::one()}
+    }
+    // Range of the data
+    pub fn over_range(values: &[T], bins: usize) -> Result<Self, CalculationError>
+    {
+        let (low, high) = values.iter().fold((T::infinity(), T::neg_infinity()), |(low, high), &v| (low.min(v), high.max(v)));
+        let (low, high) = if low <= high {(low, high)} else {(T::zero(), T::zero())};
+        Self::from_levels(values, low, high, Self::level_spacing(values), bins)
+    }
+    // Range [-max |v|, max |v|], so that both sides of P(m) show up even if only one of them was visited
+    pub fn symmetric(values: &[T], bins: usize) -> Result<Self, CalculationError>
+    {
+        let high = values.iter().fold(T::zero(), |high, &v| high.max(v.abs()));
+        Self::from_levels(values, -high, high, Self::level_spacing(values), bins)
+    }
+    pub fn centre(&self, bin: usize) -> T
+    {
+        self.low + self.bin_width * (T::from(bin).unwrap() + T::from(0.5).unwrap())
+    }
+    // Probability densities: sum_i density_i * bin_width = 1
+    pub fn densities(&self) -> Vec<T>
+    {
+        let total = T::from(self.counts.iter().sum::<usize>().max(1)).unwrap();
+        self.counts.iter().map(|&count| T::from(count).unwrap() / (total * self.bin_width)).collect()
+    }
+}
+
+impl<T> Histogram<T> where T: Float + std::fmt::Display
+{
+    // P(m) then an empty line & P(e), per spin (m = M / N, e = E / N): "magnetisation, probability" & "energy_density, probability"
+    pub fn write_to_file(file_name: &Path, magnetisation: &Histogram<T>, energy: &Histogram<T>, num_spins: T) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        for (name, histogram) in [("magnetisation", magnetisation), ("energy_density", energy)]
+        {
+            if name != "magnetisation"
+            {
+                writeln!(&mut file)?;
+            }
+            writeln!(&mut file, "{name}, probability")?;
+            for (bin, density) in histogram.densities().into_iter().enumerate()
+            {
+                writeln!(&mut file, "{}, {}", histogram.centre(bin) / num_spins, density * num_spins)?;
+            }
+        }
+        file.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_histograms()
+    {
+        let spin_sums = [4_f64, 4_f64, -4_f64, 2_f64];
+        let symmetric = Histogram::symmetric(&spin_sums, 4).unwrap();
+        // 5 levels (-4, -2... 4) in bins of 2 levels, the empty sixth one at the top
+        assert_eq!((symmetric.low, symmetric.bin_width), (-5_f64, 4_f64));
+        assert_eq!(symmetric.counts, [1, 1, 2]);
+        assert_eq!(symmetric.centre(0), -3_f64);
+        assert_eq!(symmetric.densities().iter().map(|d| d * symmetric.bin_width).sum::<f64>(), 1_f64);
+
+        let energies = Histogram::over_range(&[-8_f64, -4_f64, -4_f64, 0_f64], 2).unwrap();
+        assert_eq!(energies.counts, [3, 1]);
+
+        // Every level visited once: flat counts, where 5 equal bins over [0, 22] would hold 3, 2, 2, 3 & 2 levels
+        let levels: Vec<f64> = (0..12).map(|k| 2_f64 * k as f64).collect();
+        assert_eq!(Histogram::over_range(&levels, 5).unwrap().counts, [3, 3, 3, 3]);
+        // More bins than levels: one bin per level, centred on it (up to round-off in the data)
+        let one_per_level = Histogram::over_range(&[-2_f64, 2_f64 + 1E-12, 6_f64], 10).unwrap();
+        assert_eq!(one_per_level.counts, [1, 1, 1]);
+        assert!((one_per_level.centre(1) - 2_f64).abs() < 1E-9);
+        assert!(Histogram::symmetric(&spin_sums, 0).is_err());
+
+        // Per spin: P(m) dm = P(M) dM
+        let file_name = std::env::temp_dir().join(format!("histogram_test_{}.txt", std::process::id()));
+        Histogram::write_to_file(&file_name, &symmetric, &energies, 4_f64).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_name).unwrap(), "magnetisation, probability\n-0.75, 0.25\n0.25, 0.25\n1.25, 0.5\n\nenergy_density, probability\n-1.5, 0.375\n0.5, 0.125\n");
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -4,12 +4,34 @@ use num_traits::{AsPrimitive};
 use rayon::{self, iter::{IntoParallelIterator, ParallelIterator}};
 mod monte_carlo_results;
 mod fourier_transformer;
+pub mod exact_solution;
+pub mod transfer_matrix;
+pub mod time_series;
+pub mod statistics;
+pub mod reweighting;
+pub mod structure_factor;
+pub mod cluster_statistics;
+pub mod finite_size_scaling;
+pub mod critical_temperature;
+pub mod histogram;
+pub mod relaxation;
 
 pub use monte_carlo_results::MonteCarloResults;
+pub use time_series::{TimeSeries, TimeSeriesFormat, DomainSeries};
+pub use structure_factor::StructureFactor;
+pub use cluster_statistics::ClusterMeasurements;
+pub use histogram::Histogram;
+use structure_factor::StructureFactorMeter;
 use fourier_transformer::FourierTransformer;
 
-use periodic_array_2d_lib::{PeriodicArray2D, PeriodicArrayError, SpinValue, PhysicalObservable};
-use monte_carlo_lib::{ising_state, metropolis, SpinEnergyFluctuation, MonteCarloRngInterface};
+use periodic_array_2d_lib::{PeriodicArray2D, PeriodicArray3D, SpinGraph, PeriodicArrayError, SpinValue, PhysicalObservable, LatticeGeometry, BoundaryCondition, SpinLattice, ArrayRngInterface};
+use monte_carlo_lib::{ising_state, metropolis, heat_bath, wolff, SpinEnergyFluctuation, MonteCarloRngInterface};
+pub use monte_carlo_lib::hamiltonian::{Hamiltonian, IsingHamiltonian};
+pub use monte_carlo_lib::long_range::LongRangeIsingHamiltonian;
+pub use monte_carlo_lib::transverse_field::TransverseFieldIsingHamiltonian;
+use monte_carlo_lib::long_range::LongRangeCluster;
+use monte_carlo_lib::clusters::{ClusterFinder, ClusterStatistics};
+use monte_carlo_lib::domains::DomainGeometry;
 use xorshifts::Xoshiro256pp;
 
 
@@ -18,23 +40,335 @@ pub enum CalculationError
 {
     NegativeTempError,
     ArrayInitError(PeriodicArrayError),
+    IncompatibleHamiltonian(String),
+    IncompatibleGeometry(String),
+    ReweightingError(String),
+    ScalingError(String),
+    HistogramError(String),
+    IncompatibleAlgorithm(String),
 }
 
-pub struct ExperimentParam<P> where P: PhysicalObservable  
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateAlgorithm
+{
+    #[default]
+    Metropolis,
+    HeatBath,
+    Wolff,
+}
+
+impl std::str::FromStr for UpdateAlgorithm
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "metropolis"              => Ok(UpdateAlgorithm::Metropolis),
+            "heat_bath" | "heatbath"  => Ok(UpdateAlgorithm::HeatBath),
+            "wolff"                   => Ok(UpdateAlgorithm::Wolff),
+            _                         => Err(format!("Unknown algorithm \"{s}\", expected metropolis, heat_bath or wolff.")),
+        }
+    }
+}
+
+// Default: square periodic lattice, Metropolis, no temperature, no sweep & no optional measurement, to be completed with
+// ExperimentParam { temperatures, hamiltonian, measurement_steps, .., ..Default::default() }
+#[derive(Default)]
+pub struct ExperimentParam<P, H = IsingHamiltonian<P>> where P: PhysicalObservable  
 {
     pub temperatures: Vec<P>,
-    pub interaction_term: P,
-    pub extern_mag: P,
+    pub hamiltonian: H,
+    pub lattice: LatticeGeometry,                              // 2D lattices, the 3D, long-range & transverse-field drivers need square & periodic
+    pub boundaries: (BoundaryCondition, BoundaryCondition),   // (rows, columns)
+    pub algorithm: UpdateAlgorithm,
     pub thermalisation_steps: usize,      
     pub measurement_steps: usize,
     pub measure_struct_fact: bool,
+    pub record_time_series: bool,                              // keep the measurement of every sweep in MonteCarloResults::time_series
+    pub struct_fact_interval: usize,                           // full S(k) every struct_fact_interval sweeps (0: never), 2D lattices only
+    pub cluster_interval: usize,                               // like-spin & Fortuin-Kasteleyn clusters every cluster_interval sweeps (0: never), 2D arrays without any axis of 2 sites only
+    pub domain_interval: usize,                                // domain walls, domains & Euler characteristic every domain_interval sweeps, thermalisation included (0: never)
+    pub histogram_bins: usize,                                 // bins of the P(M) & P(E) histograms (0: none)
+}
+
+impl<P, H> ExperimentParam<P, H> where P: PhysicalObservable
+{
+    // Same experiment for another model
+    pub fn with_hamiltonian<H2>(&self, hamiltonian: H2) -> ExperimentParam<P, H2>
+    {
+        ExperimentParam
+        {
+            temperatures:         self.temperatures.clone(),
+            hamiltonian,
+            lattice:              self.lattice,
+            boundaries:           self.boundaries,
+            algorithm:            self.algorithm,
+            thermalisation_steps: self.thermalisation_steps,
+            measurement_steps:    self.measurement_steps,
+            measure_struct_fact:  self.measure_struct_fact,
+            record_time_series:   self.record_time_series,
+            struct_fact_interval: self.struct_fact_interval,
+            cluster_interval:     self.cluster_interval,
+            domain_interval:      self.domain_interval,
+            histogram_bins:       self.histogram_bins,
+        }
+    }
+}
+
+// File written next to the summary file "dir/name.txt": "dir/name_{suffix}"
+pub fn companion_file_name(summary_file: &str, suffix: &str) -> std::path::PathBuf
+{
+    let summary = std::path::Path::new(summary_file);
+    let stem    = summary.file_stem().and_then(|s| s.to_str()).unwrap_or("results");
+    summary.with_file_name(format!("{stem}_{suffix}"))
+}
+
+// One Monte-Carlo sweep, so that the driver doesn't depend on which algorithms are available for a given Hamiltonian
+trait LatticeUpdater<L, H, S, P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    fn sweep(&mut self, spins: &mut L, rng: &mut Xoshiro256pp, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>;
+}
+
+// Domain measurements: the Euler characteristic needs the faces of a 2D array
+trait DomainLattice<S, P>: SpinLattice<S,P> + Sized where S: SpinValue<P>, P: PhysicalObservable
+{
+    fn domain_geometry(&self, finder: &mut ClusterFinder) -> DomainGeometry
+    {
+        DomainGeometry::measure(self, finder)
+    }
+}
+
+impl<S, P> DomainLattice<S, P> for PeriodicArray2D<S,P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    fn domain_geometry(&self, finder: &mut ClusterFinder) -> DomainGeometry
+    {
+        DomainGeometry::measure_2d(self, finder)
+    }
+}
+impl<S, P> DomainLattice<S, P> for PeriodicArray3D<S,P> where S: SpinValue<P>, P: PhysicalObservable {}
+impl<S, P> DomainLattice<S, P> for SpinGraph<S,P> where S: SpinValue<P>, P: PhysicalObservable {}
+
+// Cluster measurements: the wrapping needs the rows & columns of a 2D array
+trait ClusterLattice<S, P>: SpinLattice<S,P> + Sized where S: SpinValue<P>, P: PhysicalObservable
+{
+    fn can_label_clusters(&self) -> bool
+    {
+        false
+    }
+    // Like-spin & Fortuin-Kasteleyn clusters
+    fn label_clusters<H: Hamiltonian<S,P>>(&self, _finder: &mut ClusterFinder, _rng: &mut Xoshiro256pp, _temp: P, _hamiltonian: &H) -> Option<(ClusterStatistics, ClusterStatistics)>
+    {
+        None
+    }
 }
 
+impl<S, P> ClusterLattice<S, P> for PeriodicArray2D<S,P> where S: SpinValue<P>, P: PhysicalObservable, Xoshiro256pp: MonteCarloRngInterface<P>
+{
+    fn can_label_clusters(&self) -> bool
+    {
+        ClusterFinder::can_label(self)
+    }
+    fn label_clusters<H: Hamiltonian<S,P>>(&self, finder: &mut ClusterFinder, rng: &mut Xoshiro256pp, temp: P, hamiltonian: &H) -> Option<(ClusterStatistics, ClusterStatistics)>
+    {
+        Some((finder.label_like_spin_clusters(self), finder.label_fortuin_kasteleyn_clusters(self, rng, temp, hamiltonian)))
+    }
+}
+impl<S, P> ClusterLattice<S, P> for PeriodicArray3D<S,P> where S: SpinValue<P>, P: PhysicalObservable {}
+impl<S, P> ClusterLattice<S, P> for SpinGraph<S,P> where S: SpinValue<P>, P: PhysicalObservable {}
 
-pub fn perform_metropolis_computation_parallel<S,P>(rows: usize, columns: usize, param: &ExperimentParam<P>) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
+// One sweep of the chosen algorithm, the Wolff cluster keeps its buffers for the whole run
+enum SpinUpdater
+{
+    Metropolis,
+    HeatBath,
+    Wolff(wolff::WolffCluster),
+}
+
+impl SpinUpdater
+{
+    fn new(algorithm: UpdateAlgorithm, number_of_spins: usize) -> Self
+    {
+        match algorithm
+        {
+            UpdateAlgorithm::Metropolis => SpinUpdater::Metropolis,
+            UpdateAlgorithm::HeatBath   => SpinUpdater::HeatBath,
+            UpdateAlgorithm::Wolff      => SpinUpdater::Wolff(wolff::WolffCluster::new(number_of_spins)),
+        }
+    }
+    fn sweep<R, L, H, S, P>(&mut self, spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+              L: SpinLattice<S,P>,
+              H: Hamiltonian<S,P>,
+              S: SpinValue<P>, 
+              P: PhysicalObservable,
+    {
+        match self
+        {
+            SpinUpdater::Metropolis       => metropolis::perform_metropolis_sweep(spins, rng, temp, hamiltonian),
+            SpinUpdater::HeatBath         => heat_bath::perform_heat_bath_sweep(spins, rng, temp, hamiltonian),
+            SpinUpdater::Wolff(cluster)   => cluster.perform_wolff_sweep(spins, rng, temp, hamiltonian),
+        }
+    }
+}
+
+impl<L, H, S, P> LatticeUpdater<L, H, S, P> for SpinUpdater
+    where L: SpinLattice<S,P>,
+          H: Hamiltonian<S,P>,
+          S: SpinValue<P>, 
+          P: PhysicalObservable,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{
+    fn sweep(&mut self, spins: &mut L, rng: &mut Xoshiro256pp, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
+    {
+        SpinUpdater::sweep(self, spins, rng, temp, hamiltonian)
+    }
+}
+
+// Long-range couplings: the nearest-neighbour Wolff clusters are replaced by the Luijten-Bloete ones
+enum LongRangeUpdater
+{
+    Metropolis,
+    HeatBath,
+    Cluster(LongRangeCluster),
+}
+
+impl LongRangeUpdater
+{
+    fn new(algorithm: UpdateAlgorithm, number_of_spins: usize) -> Self
+    {
+        match algorithm
+        {
+            UpdateAlgorithm::Metropolis => LongRangeUpdater::Metropolis,
+            UpdateAlgorithm::HeatBath   => LongRangeUpdater::HeatBath,
+            UpdateAlgorithm::Wolff      => LongRangeUpdater::Cluster(LongRangeCluster::new(number_of_spins)),
+        }
+    }
+}
+
+impl<L, S, P> LatticeUpdater<L, LongRangeIsingHamiltonian<P>, S, P> for LongRangeUpdater
+    where L: SpinLattice<S,P>,
+          S: SpinValue<P>, 
+          P: PhysicalObservable,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{
+    fn sweep(&mut self, spins: &mut L, rng: &mut Xoshiro256pp, temp: P, hamiltonian: &LongRangeIsingHamiltonian<P>) -> SpinEnergyFluctuation<P>
+    {
+        match self
+        {
+            LongRangeUpdater::Metropolis       => metropolis::perform_metropolis_sweep(spins, rng, temp, hamiltonian),
+            LongRangeUpdater::HeatBath         => heat_bath::perform_heat_bath_sweep(spins, rng, temp, hamiltonian),
+            LongRangeUpdater::Cluster(cluster) => cluster.perform_cluster_sweep(spins, rng, temp, hamiltonian),
+        }
+    }
+}
+
+
+pub fn perform_metropolis_computation_parallel<S,P,H>(rows: usize, columns: usize, param: &ExperimentParam<P,H>) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
     where P:     PhysicalObservable + Send + Sync,     // Send&Sync: to work with parallelIterator
+          usize: AsPrimitive<P>,                        
+          S:     SpinValue<P> + Send,
+          H:     Hamiltonian<S,P> + Clone + Sync,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{   
+    let init_state  = ||ising_state::spin_up::<S>();
+    let new_lattice = ||
+    {
+        let mut spin_2d_arr = PeriodicArray2D::new_with_geometry(rows as i32, columns as i32, param.lattice, init_state)?;
+        spin_2d_arr.set_boundary_conditions(param.boundaries.0, param.boundaries.1)?;
+        Ok(spin_2d_arr)
+    };
+    perform_computation_parallel(param, new_lattice, |n| SpinUpdater::new(param.algorithm, n))
+}
+
+// Simple cubic periodic lattice: any other lattice geometry or boundary condition is an error
+pub fn perform_metropolis_computation_parallel_3d<S,P,H>(layers: usize, rows: usize, columns: usize, param: &ExperimentParam<P,H>) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
+    where P:     PhysicalObservable + Send + Sync,
+          usize: AsPrimitive<P>,                        
+          S:     SpinValue<P> + Send,
+          H:     Hamiltonian<S,P> + Clone + Sync,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{   
+    if param.lattice != LatticeGeometry::Square || param.boundaries != (BoundaryCondition::Periodic, BoundaryCondition::Periodic)
+    {
+        return Err(CalculationError::IncompatibleGeometry(format!("The 3D lattice is simple cubic & periodic, not {:?} with {:?} x {:?} boundaries", param.lattice, param.boundaries.0, param.boundaries.1)));
+    }
+    let init_state = ||ising_state::spin_up::<S>();
+    perform_computation_parallel(param, ||PeriodicArray3D::new_with(layers as i32, rows as i32, columns as i32, init_state), |n| SpinUpdater::new(param.algorithm, n))
+}
+
+// Spins on the nodes of an arbitrary (weighted) graph: every temperature starts from a copy of `graph`.
+// The geometry & boundary parameters are meaningless here, and the structure factor should not be measured (no x axis).
+pub fn perform_graph_computation_parallel<S,P,H>(graph: &SpinGraph<S,P>, param: &ExperimentParam<P,H>) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
+    where P:     PhysicalObservable + Send + Sync,
+          usize: AsPrimitive<P>,                        
+          S:     SpinValue<P> + Send,
+          H:     Hamiltonian<S,P> + Clone + Sync,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{   
+    perform_computation_parallel(param, ||Ok(graph.clone()), |n| SpinUpdater::new(param.algorithm, n))
+}
+
+// Power-law couplings on the periodic lattice the Hamiltonian was built for: a chain ([columns]), a square ([rows, columns]) or a cubic lattice.
+// Any other lattice geometry or boundary condition is an error, the "wolff" algorithm uses the Luijten-Bloete long-range clusters.
+pub fn perform_long_range_computation_parallel<S,P>(param: &ExperimentParam<P, LongRangeIsingHamiltonian<P>>) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
+    where P:     PhysicalObservable + Send + Sync,
+          usize: AsPrimitive<P>,                        
+          S:     SpinValue<P> + Send,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{   
+    if param.lattice != LatticeGeometry::Square || param.boundaries != (BoundaryCondition::Periodic, BoundaryCondition::Periodic)
+    {
+        return Err(CalculationError::IncompatibleHamiltonian(format!("The long-range couplings are summed over periodic images of hypercubic lattices, not {:?} ones with {:?} x {:?} boundaries", param.lattice, param.boundaries.0, param.boundaries.1)));
+    }
+    let init_state  = ||ising_state::spin_up::<S>();
+    let new_updater = |n| LongRangeUpdater::new(param.algorithm, n);
+    match *param.hamiltonian.lattice_shape()
+    {
+        [columns]                => perform_computation_parallel(param, ||PeriodicArray2D::new_with(1, columns as i32, init_state), new_updater),
+        [rows, columns]          => perform_computation_parallel(param, ||PeriodicArray2D::new_with(rows as i32, columns as i32, init_state), new_updater),
+        [layers, rows, columns]  => perform_computation_parallel(param, ||PeriodicArray3D::new_with(layers as i32, rows as i32, columns as i32, init_state), new_updater),
+        _                        => unreachable!("LongRangeIsingHamiltonian only supports 1 to 3 dimensions"),
+    }
+}
+
+
+// Transverse-field Ising model on a periodic chain (rows = 1) or square lattice, the Trotter slices being stacked along an extra axis.
+// Each temperature sets beta in the Suzuki-Trotter couplings, & the results are the quantum estimators per quantum spin (use shape [rows, columns]).
+// The slices are always periodic square lattices: any other lattice geometry or boundary condition is an error.
+pub fn perform_transverse_field_computation_parallel<S,P>(rows: usize, columns: usize, param: &ExperimentParam<P, TransverseFieldIsingHamiltonian<P>>) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
+    where P:     PhysicalObservable + Send + Sync,
+          usize: AsPrimitive<P>,                        
+          S:     SpinValue<P> + Send,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{   
+    if param.hamiltonian.slice_size() != rows * columns
+    {
+        return Err(CalculationError::IncompatibleHamiltonian(format!("Trotter slices of {} spins for a {rows}x{columns} lattice", param.hamiltonian.slice_size())));
+    }
+    if param.lattice != LatticeGeometry::Square || param.boundaries != (BoundaryCondition::Periodic, BoundaryCondition::Periodic)
+    {
+        return Err(CalculationError::IncompatibleHamiltonian(format!("The Trotter slices are periodic square lattices, not {:?} ones with {:?} x {:?} boundaries", param.lattice, param.boundaries.0, param.boundaries.1)));
+    }
+    let init_state  = ||ising_state::spin_up::<S>();
+    let slices      = param.hamiltonian.trotter_slices() as i32;
+    let new_updater = |n| SpinUpdater::new(param.algorithm, n);
+    match rows
+    {
+        1 => perform_computation_parallel(param, ||PeriodicArray2D::new_with(slices, columns as i32, init_state), new_updater),
+        _ => perform_computation_parallel(param, ||PeriodicArray3D::new_with(slices, rows as i32, columns as i32, init_state), new_updater),
+    }
+}
+
+
+// Dimension independent driver: each temperature gets its own lattice & RNG, and is thermalised/measured in parallel
+fn perform_computation_parallel<L,S,P,H,U>(param: &ExperimentParam<P,H>, new_lattice: impl Fn() -> Result<L, PeriodicArrayError>, new_updater: impl Fn(usize) -> U + Sync) -> Result<Vec<MonteCarloResults<P>>, CalculationError>
+    where P:     PhysicalObservable + Send + Sync,
           usize: AsPrimitive<P>,                        
           S:     SpinValue<P>,
+          L:     DomainLattice<S,P> + ClusterLattice<S,P> + Send,
+          H:     Hamiltonian<S,P> + Clone + Sync,
+          U:     LatticeUpdater<L,H,S,P>,
           Xoshiro256pp: MonteCarloRngInterface<P>
 {   
     if param.temperatures.iter().any(|x| x.is_sign_negative())
@@ -44,65 +378,110 @@ pub fn perform_metropolis_computation_parallel<S,P>(rows: usize, columns: usize,
 
     let n_values           = param.temperatures.len();
     let mut results        = vec![MonteCarloResults::<P>::default(); n_values];
+    let mut lattices       = (0..n_values).map(|_| new_lattice()).collect::<Result<Vec<L>, PeriodicArrayError>>().map_err(CalculationError::ArrayInitError)?;
+    if param.cluster_interval > 0 && !lattices.iter().all(|lattice| lattice.can_label_clusters())
+    {
+        return Err(CalculationError::IncompatibleGeometry(String::from("Cluster statistics need a 2D array without any axis of 2 sites")));
+    }
     let number_of_measures = param.measurement_steps;
     let take_fourier       = param.measure_struct_fact;
+    let bin_length         = number_of_measures.div_ceil(statistics::NUMBER_OF_BINS).max(1);
+    let number_of_bins     = number_of_measures / bin_length;
 
-    (&param.temperatures, &mut results).into_par_iter().for_each(|(&temp, result)|
+    (&param.temperatures, &mut results, &mut lattices).into_par_iter().for_each(|(&temp, result, spin_lattice)|
     {
         let mut my_rng      = Xoshiro256pp::from_os(); 
-        let init_state      = ||ising_state::spin_up::<S>();
-        let mut spin_2d_arr = PeriodicArray2D::new_with(rows as i32, columns as i32, init_state).unwrap();
-        let fourier_transf  = FourierTransformer::new(columns as usize);
-
-        let mut spin_sum_avg   = P::zero();
-        let mut energy_avg     = P::zero();
-        let mut spin_sqr_avg   = P::zero();
-        let mut energy_sqr_avg = P::zero();
-
-        let mut re_spin_q0_sqr_avg = P::zero(); //  <Re[sigma_q0]²>  
-        let mut re_spin_qx_sqr_avg = P::zero(); //  <Re[sigma_qx]²>  
-        let mut im_spin_qx_sqr_avg = P::zero();
-        for _ in 0..param.thermalisation_steps 
+        let fourier_transf  = FourierTransformer::new(spin_lattice.rows() as usize, spin_lattice.columns() as usize);
+        let mut updater     = new_updater(spin_lattice.total_number() as usize);
+        let hamiltonian     = param.hamiltonian.at_temperature(temp);
+
+        let mut time_series       = (param.record_time_series || param.histogram_bins > 0).then(|| TimeSeries::with_capacity(number_of_measures));
+        let rows                  = (spin_lattice.total_number() / spin_lattice.columns()) as usize;
+        let mut struct_fact       = StructureFactor::new(rows, spin_lattice.columns() as usize);
+        let mut struct_fact_meter = (param.struct_fact_interval > 0).then(|| StructureFactorMeter::new(rows, spin_lattice.columns() as usize));
+        let mut cluster_finder    = (param.cluster_interval > 0).then(|| ClusterFinder::new(spin_lattice.total_number() as usize));
+        let mut domain_finder     = (param.domain_interval > 0).then(|| ClusterFinder::new(spin_lattice.total_number() as usize));
+        let measure_weight: P     = P::one() / number_of_measures.as_();
+        let mut bins              = vec![MonteCarloResults::<P>::default(); number_of_bins];
+        let bin_weight: P         = P::one() / bin_length.as_();
+        for sweep in 0..param.thermalisation_steps 
         {
-            metropolis::perform_metropolis_sweep(&mut spin_2d_arr, &mut my_rng, temp, param.interaction_term, param.extern_mag);
+            if let Some(finder) = domain_finder.as_mut().filter(|_| sweep.is_multiple_of(param.domain_interval))
+            {
+                result.domains.push(sweep, spin_lattice.domain_geometry(finder));
+            }
+            updater.sweep(spin_lattice, &mut my_rng, temp, &hamiltonian);
         }
 
-        let mut spin_sum: P     = spin_2d_arr.sum_observable();  
-        let mut total_energy: P = metropolis::get_total_energy(&spin_2d_arr, param.interaction_term, param.extern_mag);
+        let mut spin_sum: P     = spin_lattice.sum_observable();  
+        let mut total_energy: P = hamiltonian.total_energy(spin_lattice);
 
-        for _ in 0..param.measurement_steps 
+        for step in 0..param.measurement_steps 
         {                                    
          
-            spin_sum_avg     += spin_sum.abs() /(number_of_measures.as_());
-            energy_avg       += total_energy/(number_of_measures.as_());
-            energy_sqr_avg   += (total_energy * total_energy) /(number_of_measures.as_());
-            spin_sqr_avg     += (spin_sum * spin_sum) /(number_of_measures.as_());
-           
+            let spin_sum_estimator = hamiltonian.measured_spin_sum(spin_sum);
+            let energy_estimator   = hamiltonian.measured_energy(spin_lattice, total_energy);
+            if let Some(series) = time_series.as_mut()
+            {
+                series.push(energy_estimator, spin_sum_estimator);
+            }
+
+            let (mut struct_fact_q0, mut struct_fact_qx, mut struct_fact_qy) = (P::zero(), P::zero(), P::zero());
             if take_fourier
             {
-                let (spin_q0, spin_qx) = fourier_transf.take_fourier_transform(&spin_2d_arr);
-                re_spin_q0_sqr_avg += spin_q0*spin_q0 /number_of_measures.as_();
-                re_spin_qx_sqr_avg += spin_qx.re*spin_qx.re /number_of_measures.as_();
-                im_spin_qx_sqr_avg += spin_qx.im*spin_qx.im /number_of_measures.as_();
+                let (spin_q0, spin_qx, spin_qy) = fourier_transf.take_fourier_transform(spin_lattice);
+                struct_fact_q0 = spin_q0*spin_q0;                               //S(q0) =  <Re[sigma_q0]²>
+                struct_fact_qx = spin_qx.re*spin_qx.re + spin_qx.im*spin_qx.im; //S(qx) =  <Re[sigma_qx]²> + <Im[sigma_qx]²>
+                struct_fact_qy = spin_qy.re*spin_qy.re + spin_qy.im*spin_qy.im;
+            }
+            result.add_measurement(spin_sum_estimator, energy_estimator, struct_fact_q0, struct_fact_qx, struct_fact_qy, measure_weight);
+            if let Some(bin) = bins.get_mut(step / bin_length)                  // the last step % bin_length measurements are not binned
+            {
+                bin.add_measurement(spin_sum_estimator, energy_estimator, struct_fact_q0, struct_fact_qx, struct_fact_qy, bin_weight);
+            }
+            if let Some(meter) = struct_fact_meter.as_mut().filter(|_| step % param.struct_fact_interval == 0)
+            {
+                struct_fact.measure(spin_lattice, meter);
+            }
+            if let Some(finder) = cluster_finder.as_mut().filter(|_| step % param.cluster_interval == 0)
+                && let Some((like_spin, fortuin_kasteleyn)) = spin_lattice.label_clusters(finder, &mut my_rng, temp, &hamiltonian)
+            {
+                result.clusters.like_spin.add(&like_spin);
+                result.clusters.fortuin_kasteleyn.add(&fortuin_kasteleyn);
+            }
+            let sweep = param.thermalisation_steps + step;
+            if let Some(finder) = domain_finder.as_mut().filter(|_| sweep.is_multiple_of(param.domain_interval))
+            {
+                result.domains.push(sweep, spin_lattice.domain_geometry(finder));
             }
 
 
-            let SpinEnergyFluctuation(dS, dE) = metropolis::perform_metropolis_sweep(&mut spin_2d_arr,
-                                                                                    &mut my_rng,
-                                                                                    temp,
-                                                                                    param.interaction_term,
-                                                                                    param.extern_mag);   
+            let SpinEnergyFluctuation(dS, dE) = updater.sweep(spin_lattice,
+                                                              &mut my_rng,
+                                                              temp,
+                                                              &hamiltonian);   
 
             spin_sum     += dS; 
             total_energy += dE;
         }
 
-        result.spins_sum_avg  = spin_sum_avg;
-        result.energy_avg     = energy_avg;
-        result.spins_sqr_avg  = spin_sqr_avg;
-        result.energy_sqr_avg = energy_sqr_avg;
-        result.struct_fact_q0 = re_spin_q0_sqr_avg;                           //S(q0) =  <Re[sigma_q0]²>
-        result.struct_fact_qx = re_spin_qx_sqr_avg + im_spin_qx_sqr_avg;      //S(qx) =  <Im[sigma_qx]²> + <Im[sigma_qx]²>
+        result.number_of_measures = number_of_measures;
+        result.energy_tau_int     = statistics::binned_autocorrelation_time(&bins.iter().map(|bin| bin.energy_avg).collect::<Vec<P>>(), bin_length, result.energy_sqr_avg - result.energy_avg.powi(2));
+        result.abs_spins_tau_int  = statistics::binned_autocorrelation_time(&bins.iter().map(|bin| bin.spins_sum_avg).collect::<Vec<P>>(), bin_length, result.spins_sqr_avg - result.spins_sum_avg.powi(2));
+        result.bins               = bins;
+        if struct_fact_meter.is_some()
+        {
+            result.struct_fact    = struct_fact;
+        }
+        if let Some(series) = time_series.as_ref().filter(|_| param.histogram_bins > 0)
+        {
+            result.magnetisation_histogram = Histogram::symmetric(&series.spin_sum, param.histogram_bins).expect("histogram_bins > 0");
+            result.energy_histogram        = Histogram::over_range(&series.energy, param.histogram_bins).expect("histogram_bins > 0");
+        }
+        if let Some(series) = time_series.filter(|_| param.record_time_series)
+        {
+            result.time_series    = series;
+        }
     });
     Ok(results)
 }
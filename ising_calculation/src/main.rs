@@ -1,7 +1,11 @@
 #![allow(non_snake_case)]
-use ising_calculation::{self, MonteCarloResults, perform_metropolis_computation_parallel};
-use ising_calculation::ExperimentParam;
-use rayon;
+use ising_calculation::{self, MonteCarloResults, perform_metropolis_computation_parallel, perform_metropolis_computation_parallel_3d, perform_graph_computation_parallel, perform_long_range_computation_parallel, perform_transverse_field_computation_parallel};
+use ising_calculation::{ExperimentParam, UpdateAlgorithm, IsingHamiltonian, LongRangeIsingHamiltonian, TransverseFieldIsingHamiltonian, TimeSeries, TimeSeriesFormat, Histogram};
+use ising_calculation::reweighting::{Reweighting, PeakObservable};
+use ising_calculation::relaxation::{InitialState, perform_relaxation_parallel};
+use ising_calculation::transfer_matrix::{TransferMatrix, StripSolution};
+use periodic_array_2d_lib::{LatticeGeometry, BoundaryCondition, SpinGraph};
+use monte_carlo_lib::ising_state;
 use std::env;
 use parameter_reader::ParameterReader;
 use num::{Zero};
@@ -10,7 +14,9 @@ use num::{Zero};
 const J: f64                        = 1_f64; 
 const EXTERN_MAG: f64               = 0_f64; 
 const MINIMUM_TEMP: f64             = 1E-6;
-const PARAMETERS: [&'static str; 7] = [
+const IMAGE_SHELLS: usize           = 2;       // periodic images summed explicitly for the long-range couplings
+const TROTTER_SLICES: usize         = 32;
+const PARAMETERS: [&str; 7]         = [
     "Lx",
     "Ly", 
     "temperatures",
@@ -20,6 +26,12 @@ const PARAMETERS: [&'static str; 7] = [
     "outputfile"
 ];
 
+// Parameters which older parameter files don't have: parse them one by one, so that a missing one falls back to its default
+fn optional_parameter(reader: &ParameterReader, name: &str) -> Option<String>
+{
+    reader.parse_parameters(&[name], ":").ok().map(|params| params[name].clone())
+}
+
 fn main() 
 {
     let args: Vec<String> = env::args().collect();
@@ -47,6 +59,29 @@ fn main()
     let outputfile: String          = params["outputfile"].parse().expect("!! Could not parse \"outputfile\"");
     let measure_corr_len: bool      = params["measure_corr_len"].to_lowercase().parse().expect("!! Could not parse structur factor");
     
+    let lattice: LatticeGeometry    = optional_parameter(&reader, "lattice").map_or(LatticeGeometry::Square, |l| l.parse().expect("!! Could not parse \"lattice\""));
+    let interaction_term: f64       = optional_parameter(&reader, "J").map_or(J, |j| j.parse().expect("!! Could not parse \"J\""));
+    let algorithm: UpdateAlgorithm  = optional_parameter(&reader, "algorithm").map_or(UpdateAlgorithm::Metropolis, |a| a.parse().expect("!! Could not parse \"algorithm\""));
+    let boundary_x: BoundaryCondition = optional_parameter(&reader, "boundary_x").map_or(BoundaryCondition::Periodic, |b| b.parse().expect("!! Could not parse \"boundary_x\""));
+    let boundary_y: BoundaryCondition = optional_parameter(&reader, "boundary_y").map_or(BoundaryCondition::Periodic, |b| b.parse().expect("!! Could not parse \"boundary_y\""));
+    let Lz: usize                   = optional_parameter(&reader, "Lz").map_or(1, |l| l.parse().expect("!! Could not parse \"Lz\"")); // Lz > 1: simple cubic lattice
+    let sigma: Option<f64>          = optional_parameter(&reader, "sigma").filter(|s| !s.is_empty() && s.to_lowercase() != "none").map(|s| s.parse().expect("!! Could not parse \"sigma\"")); // J(r) ~ 1/r^(d+sigma) couplings
+    let transverse_field: Option<f64> = optional_parameter(&reader, "transverse_field").filter(|g| !g.is_empty() && g.to_lowercase() != "none").map(|g| g.parse().expect("!! Could not parse \"transverse_field\"")); // quantum Ising (Ly = 1: chain)
+    let trotter_slices: usize       = optional_parameter(&reader, "trotter_slices").map_or(TROTTER_SLICES, |m| m.parse().expect("!! Could not parse \"trotter_slices\""));
+    let graph_file: Option<String>  = optional_parameter(&reader, "graph_file").filter(|f| !f.is_empty() && f.to_lowercase() != "none");  // replaces the lattice (Lx, Ly & Lz are ignored)
+    let time_series: Option<TimeSeriesFormat> = optional_parameter(&reader, "time_series").filter(|f| !f.is_empty() && f.to_lowercase() != "none").map(|f| f.parse().expect("!! Could not parse \"time_series\"")); // csv or binary files of E & M per sweep
+    let reweight_temperatures: Option<Vec<f64>> = optional_parameter(&reader, "reweight_temperatures").filter(|t| !t.is_empty() && t.to_lowercase() != "none")
+        .map(|t| t.split(", ").map(|t| t.parse().expect("!! failed to parse \"reweight_temperatures\"")).collect()); // multi-histogram reweighting of all the runs
+    let struct_fact_interval: usize = optional_parameter(&reader, "struct_fact_interval").map_or(0, |i| i.parse().expect("!! Could not parse \"struct_fact_interval\"")); // full S(k) every i sweeps, 0: never
+    let cluster_interval: usize     = optional_parameter(&reader, "cluster_interval").map_or(0, |i| i.parse().expect("!! Could not parse \"cluster_interval\"")); // cluster statistics every i sweeps, 0: never
+    let domain_interval: usize      = optional_parameter(&reader, "domain_interval").map_or(0, |i| i.parse().expect("!! Could not parse \"domain_interval\"")); // domain walls, domains & Euler characteristic every i sweeps, 0: never
+    let histogram_bins: usize       = optional_parameter(&reader, "histogram_bins").map_or(0, |b| b.parse().expect("!! Could not parse \"histogram_bins\"")); // P(m) & P(E) histograms, 0: none
+    let relaxation_samples: usize   = optional_parameter(&reader, "relaxation_samples").map_or(0, |r| r.parse().expect("!! Could not parse \"relaxation_samples\"")); // > 0: non-equilibrium relaxation of that many lattices over measure_steps sweeps instead
+    let initial_state: InitialState = optional_parameter(&reader, "initial_state").map_or(InitialState::Ordered, |i| i.parse().expect("!! Could not parse \"initial_state\"")); // of the relaxation: ordered or random
+    let correlation_function: bool  = optional_parameter(&reader, "correlation_function").is_some_and(|c| c.to_lowercase().parse().expect("!! Could not parse \"correlation_function\"")); // G(r), from S(k) measured every sweep by default
+    let exact_reference: bool       = optional_parameter(&reader, "exact_reference").is_some_and(|e| e.to_lowercase().parse().expect("!! Could not parse \"exact_reference\"")); // exact torus & Onsager columns
+    let transfer_matrix: bool       = optional_parameter(&reader, "transfer_matrix").is_some_and(|t| t.to_lowercase().parse().expect("!! Could not parse \"transfer_matrix\"")); // exact Lx -> infinity strip of width Ly
+
     let mut temperatures: Vec<f64>  = params["temperatures"].split(", ").map(|t| t.parse().expect("!! failed to parse \"temperatures\"") ).collect();
 
     temperatures
@@ -59,7 +94,36 @@ fn main()
         });
     
 
-    println!("Launching 2D Isig with the Metropolis algorithm for N:{Lx}x{Ly} with therm steps {thermalisation_steps} & measure_steps: {measurement_steps}");
+    let graph = graph_file.as_ref().map(|file| SpinGraph::<i8,f64>::from_edge_list_file(file, ising_state::spin_up).unwrap_or_else(|e|
+    {
+        println!("Could not read graph \"{file}\": {e}");
+        std::process::exit(1);
+    }));
+
+    if let Some(graph) = &graph
+    {
+        println!("Launching Ising on a graph with the {algorithm:?} algorithm for {} nodes & {} edges (J={interaction_term}) with therm steps {thermalisation_steps} & measure_steps: {measurement_steps}", graph.number_of_nodes(), graph.number_of_edges());
+    }
+    else if let Some(transverse_field) = transverse_field
+    {
+        println!("Launching transverse-field Ising (Gamma={transverse_field}, {trotter_slices} Trotter slices) with the {algorithm:?} algorithm for N:{Lx}x{Ly} ({lattice:?} lattice, {boundary_x:?} x {boundary_y:?} boundaries, J={interaction_term}) with therm steps {thermalisation_steps} & measure_steps: {measurement_steps}");
+        if measure_corr_len
+        {
+            println!("No correlation length for the transverse-field model: the structure factor of the Trotter lattice would be integrated over imaginary time.");
+        }
+    }
+    else if let Some(sigma) = sigma
+    {
+        println!("Launching long-range Ising (sigma={sigma}) with the {algorithm:?} algorithm for N:{Lx}x{Ly}x{Lz} ({lattice:?} lattice, {boundary_x:?} x {boundary_y:?} boundaries, J={interaction_term}) with therm steps {thermalisation_steps} & measure_steps: {measurement_steps}");
+    }
+    else if Lz > 1
+    {
+        println!("Launching 3D Ising with the {algorithm:?} algorithm for N:{Lx}x{Ly}x{Lz} ({lattice:?} lattice, {boundary_x:?} x {boundary_y:?} boundaries, J={interaction_term}) with therm steps {thermalisation_steps} & measure_steps: {measurement_steps}");
+    }
+    else
+    {
+        println!("Launching 2D Isig with the {algorithm:?} algorithm for N:{Lx}x{Ly} ({lattice:?} lattice, {boundary_x:?} x {boundary_y:?} boundaries, J={interaction_term}) with therm steps {thermalisation_steps} & measure_steps: {measurement_steps}");
+    }
     let &temp_last  = temperatures.last().unwrap();
     let &temp_first = temperatures.first().unwrap();
     let temp_len    = temperatures.len();
@@ -68,31 +132,241 @@ fn main()
     let parameters  = ExperimentParam 
     {
         temperatures, 
-        extern_mag:             EXTERN_MAG,
-        interaction_term:       J, 
+        hamiltonian:            IsingHamiltonian::new(interaction_term, EXTERN_MAG), 
+        lattice,
+        boundaries:             (boundary_y, boundary_x),
+        algorithm,
         thermalisation_steps, 
         measurement_steps,
-        measure_struct_fact: measure_corr_len // we need the structur factor, related to the fourier transform of the spin to get the correlation length!
+        measure_struct_fact: measure_corr_len && graph.is_none() && transverse_field.is_none(), // we need the structur factor, related to the fourier transform of the spin to get the correlation length!
+        record_time_series:  time_series.is_some() || reweight_temperatures.is_some(),
+        cluster_interval:     if graph.is_none() && transverse_field.is_none() && Lz == 1 {cluster_interval} else {0},
+        domain_interval:      if transverse_field.is_none() {domain_interval} else {0},   // the Trotter slices aren't spatial domains
+        histogram_bins,
+        struct_fact_interval: if graph.is_none() && transverse_field.is_none() && Lz == 1 {if correlation_function {struct_fact_interval.max(1)} else {struct_fact_interval}} else {0}, // needs a rows x columns lattice
     };
     
+    let shape: Vec<usize> = match &graph
+    {
+        Some(graph) => vec![graph.number_of_nodes()],
+        None if Lz > 1 && transverse_field.is_none() => vec![Lz, Ly, Lx],
+        None if Ly == 1 && sigma.is_some()           => vec![Lx],    // a long-range chain is a d = 1 lattice for the image sums
+        None                                         => vec![Ly, Lx],
+    };
+    if relaxation_samples > 0
+    {
+        if graph.is_some() || transverse_field.is_some() || sigma.is_some() || Lz > 1
+        {
+            println!("No relaxation experiment for this model: only for the nearest-neighbour 2D lattices.");
+            std::process::exit(1);
+        }
+        println!("Relaxation of {relaxation_samples} lattices from the {initial_state:?} state over {measurement_steps} sweeps (no thermalisation)");
+        let now    = std::time::SystemTime::now();
+        let curves = perform_relaxation_parallel::<i8,f64,_>(Ly, Lx, &parameters, relaxation_samples, initial_state).unwrap_or_else(|e|
+        {
+            println!("Could not perform the relaxation: {e:?}");
+            std::process::exit(1);
+        });
+        println!("Calculation finished after {}s", now.elapsed().unwrap().as_secs());
+        for (index, (&temp, curve)) in parameters.temperatures.iter().zip(&curves).enumerate()
+        {
+            let file_name = ising_calculation::companion_file_name(&outputfile, &format!("relaxation_{index}_T{temp}.txt"));
+            curve.write_to_file(&file_name).unwrap_or_else(|e|
+            {
+                println!("Could not write the relaxation \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Relaxation curves saved next to \"{outputfile}\".");
+        return;
+    }
     let now     = std::time::SystemTime::now();
-    let results = perform_metropolis_computation_parallel::<i8,f64>(Ly, Lx, &parameters).unwrap_or_else(|e|      // We will use i8 spins and f64 observables:
+    let results = match (&graph, transverse_field, sigma, Lz)                                                  // We will use i8 spins and f64 observables:
+    {
+        (Some(graph), _, _, _)          => perform_graph_computation_parallel(graph, &parameters),
+        (None, Some(transverse_field), _, _) =>
+        {
+            let hamiltonian = TransverseFieldIsingHamiltonian::new(interaction_term, transverse_field, EXTERN_MAG, trotter_slices, Lx*Ly);
+            perform_transverse_field_computation_parallel::<i8,f64>(Ly, Lx, &parameters.with_hamiltonian(hamiltonian))
+        },
+        (None, None, Some(sigma), _)    =>
+        {
+            let hamiltonian = LongRangeIsingHamiltonian::new(interaction_term, EXTERN_MAG, sigma, &shape, IMAGE_SHELLS);
+            perform_long_range_computation_parallel::<i8,f64>(&parameters.with_hamiltonian(hamiltonian))
+        },
+        (None, None, None, 1)           => perform_metropolis_computation_parallel::<i8,f64,_>(Ly, Lx, &parameters),
+        (None, None, None, _)           => perform_metropolis_computation_parallel_3d::<i8,f64,_>(Lz, Ly, Lx, &parameters),
+    }.unwrap_or_else(|e|
     {
         println!("Could not perform metropolis computation: {e:?}");
         std::process::exit(1);
     });
     let elapsed_time: std::time::Duration = now.elapsed().unwrap(); 
 
+    // The exact solutions only exist for the zero-field nearest-neighbour square lattice on a torus
+    let exact_solvable = graph.is_none() && transverse_field.is_none() && sigma.is_none() && Lz == 1 && lattice == LatticeGeometry::Square
+        && boundary_x == BoundaryCondition::Periodic && boundary_y == BoundaryCondition::Periodic && EXTERN_MAG.is_zero() && interaction_term > 0_f64;
+    if exact_reference && !exact_solvable
+    {
+        println!("No exact reference for this model: only for the ferromagnetic zero-field square lattice with periodic boundaries.");
+    }
+
+    // The strip of width Ly keeps the boundaries along y, Lx -> infinity
+    let strip_solvable = graph.is_none() && transverse_field.is_none() && sigma.is_none() && Lz == 1 && lattice == LatticeGeometry::Square;
+    if transfer_matrix && !strip_solvable
+    {
+        println!("No transfer matrix for this model: only for the nearest-neighbour square lattice.");
+    }
 
     println!("Calculation finished after {}s", elapsed_time.as_secs());
     println!("Saving result as \"{outputfile}\".");
     
 
-    MonteCarloResults::write_to_file(&outputfile, &parameters.temperatures, &results, Ly, Lx, elapsed_time).unwrap_or_else(|e|
+    MonteCarloResults::write_to_file(&outputfile, &parameters.temperatures, &results, &shape, (exact_reference && exact_solvable).then_some(interaction_term), elapsed_time).unwrap_or_else(|e|
     {
         println!("Could not write to file: {e}.");
         std::process::exit(1);
     });
+    if transfer_matrix && strip_solvable
+    {
+        let file_name = ising_calculation::companion_file_name(&outputfile, "transfer_matrix.txt");
+        let solutions = TransferMatrix::new(Ly, boundary_y, interaction_term, EXTERN_MAG)
+            .and_then(|strip| parameters.temperatures.iter().map(|&temp| strip.solve(temp)).collect::<Result<Vec<StripSolution>, _>>());
+        match solutions
+        {
+            Ok(solutions) =>
+            {
+                StripSolution::write_to_file(&file_name, &parameters.temperatures, &solutions).unwrap_or_else(|e|
+                {
+                    println!("Could not write the transfer matrix solutions \"{}\": {e}.", file_name.display());
+                    std::process::exit(1);
+                });
+                println!("Transfer matrix solutions saved next to \"{outputfile}\".");
+            },
+            Err(e)        => println!("No transfer matrix for this strip: {e:?}"),
+        }
+    }
+    if let Some(format) = time_series
+    {
+        for (index, (&temp, result)) in parameters.temperatures.iter().zip(&results).enumerate()
+        {
+            let file_name = TimeSeries::file_name(&outputfile, index, temp, format);
+            result.time_series.write_to_file(&file_name, format).unwrap_or_else(|e|
+            {
+                println!("Could not write the time series \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Time series saved next to \"{outputfile}\".");
+    }
+    if parameters.struct_fact_interval > 0
+    {
+        for (index, (&temp, result)) in parameters.temperatures.iter().zip(&results).enumerate()
+        {
+            let file_name = ising_calculation::companion_file_name(&outputfile, &format!("struct_fact_{index}_T{temp}.txt"));
+            result.struct_fact.write_to_file(&file_name).unwrap_or_else(|e|
+            {
+                println!("Could not write the structure factor \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Structure factors saved next to \"{outputfile}\".");
+    }
+    if parameters.struct_fact_interval > 0 && correlation_function
+    {
+        for (index, (&temp, result)) in parameters.temperatures.iter().zip(&results).enumerate()
+        {
+            let file_name = ising_calculation::companion_file_name(&outputfile, &format!("correlation_{index}_T{temp}.txt"));
+            result.struct_fact.write_correlation_to_file(&file_name).unwrap_or_else(|e|
+            {
+                println!("Could not write the correlation function \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Correlation functions saved next to \"{outputfile}\".");
+    }
+    if parameters.struct_fact_interval == 0 && (struct_fact_interval > 0 || correlation_function)
+    {
+        println!("No full structure factor or correlation function for this model: only for 2D lattices.");
+    }
+    if parameters.cluster_interval > 0
+    {
+        for (index, (&temp, result)) in parameters.temperatures.iter().zip(&results).enumerate()
+        {
+            let file_name = ising_calculation::companion_file_name(&outputfile, &format!("clusters_{index}_T{temp}.txt"));
+            result.clusters.write_to_file(&file_name).unwrap_or_else(|e|
+            {
+                println!("Could not write the cluster statistics \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Cluster statistics saved next to \"{outputfile}\".");
+    }
+    else if cluster_interval > 0
+    {
+        println!("No cluster statistics for this model: only for 2D lattices.");
+    }
+    if parameters.domain_interval > 0
+    {
+        for (index, (&temp, result)) in parameters.temperatures.iter().zip(&results).enumerate()
+        {
+            let file_name = ising_calculation::companion_file_name(&outputfile, &format!("domains_{index}_T{temp}.csv"));
+            result.domains.write_to_file(&file_name).unwrap_or_else(|e|
+            {
+                println!("Could not write the domain series \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Domain series saved next to \"{outputfile}\".");
+    }
+    else if domain_interval > 0
+    {
+        println!("No domain series for the transverse field: the Trotter slices aren't spatial domains.");
+    }
+    if histogram_bins > 0
+    {
+        let num_spins = shape.iter().product::<usize>() as f64;
+        for (index, (&temp, result)) in parameters.temperatures.iter().zip(&results).enumerate()
+        {
+            let file_name = ising_calculation::companion_file_name(&outputfile, &format!("histogram_{index}_T{temp}.txt"));
+            Histogram::write_to_file(&file_name, &result.magnetisation_histogram, &result.energy_histogram, num_spins).unwrap_or_else(|e|
+            {
+                println!("Could not write the histograms \"{}\": {e}.", file_name.display());
+                std::process::exit(1);
+            });
+        }
+        println!("Histograms saved next to \"{outputfile}\".");
+    }
+    if let Some(reweight_temperatures) = reweight_temperatures
+    {
+        if transverse_field.is_some()
+        {
+            println!("No reweighting for the transverse field: the Suzuki-Trotter couplings depend on the temperature.");
+            return;
+        }
+        let now         = std::time::SystemTime::now();
+        let reweighting = Reweighting::new(&parameters.temperatures, &results).unwrap_or_else(|e|
+        {
+            println!("Could not reweight the runs: {e:?}");
+            std::process::exit(1);
+        });
+        let reweighted: Vec<MonteCarloResults<f64>> = reweight_temperatures.iter().map(|&temp| reweighting.results_at(temp)).collect();
+        let num_spins   = shape.iter().product::<usize>() as f64;
+        let (low, high) = parameters.temperatures.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &t| (low.min(t), high.max(t)));
+        for observable in [PeakObservable::SpecificHeat, PeakObservable::Susceptibility]
+        {
+            let (temp, value) = reweighting.locate_peak(observable, low, high, num_spins);
+            println!("{observable:?} peak: {value} at T = {temp}");
+        }
+
+        let file_name  = ising_calculation::companion_file_name(&outputfile, "reweighted.txt").to_string_lossy().into_owned();
+        MonteCarloResults::write_to_file(&file_name, &reweight_temperatures, &reweighted, &shape, (exact_reference && exact_solvable).then_some(interaction_term), now.elapsed().unwrap()).unwrap_or_else(|e|
+        {
+            println!("Could not write the reweighted results: {e}.");
+            std::process::exit(1);
+        });
+        println!("Reweighted results saved as \"{file_name}\".");
+    }
     
 
 }
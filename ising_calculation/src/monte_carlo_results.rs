@@ -3,21 +3,124 @@ use std::f64::consts::PI;
 use std::io::Write;
 use std::iter::zip;
 
-#[derive(Debug, Default, Clone, Copy)]
+use crate::{exact_solution, statistics};
+use crate::{TimeSeries, StructureFactor, ClusterMeasurements, Histogram};
+use crate::time_series::DomainSeries;
+
+#[derive(Debug, Default, Clone)]
 pub struct MonteCarloResults<T> where T: Float
 {
     pub spins_sum_avg: T,   
     pub spins_sqr_avg: T,  
+    pub spins_abs_cube_avg: T,  // <|M|^3>
+    pub spins_quad_avg: T,      // <M^4>
     pub energy_avg: T,       
     pub energy_sqr_avg: T,  
     pub struct_fact_q0: T,
     pub struct_fact_qx: T,      
+    pub struct_fact_qy: T,              // S(0, 2pi / rows)
+    pub number_of_measures: usize,
+    pub energy_tau_int: T,              // integrated autocorrelation times, in sweeps, from the bins
+    pub abs_spins_tau_int: T,
+    pub time_series: TimeSeries<T>,     // empty unless ExperimentParam::record_time_series
+    pub bins: Vec<MonteCarloResults<T>>,    // averages over consecutive blocks of the measurement phase, for the error bars
+    pub struct_fact: StructureFactor<T>,    // empty unless ExperimentParam::struct_fact_interval > 0
+    pub clusters: ClusterMeasurements<T>,   // empty unless ExperimentParam::cluster_interval > 0
+    pub domains: DomainSeries,              // empty unless ExperimentParam::domain_interval > 0
+    pub magnetisation_histogram: Histogram<T>,  // P(M), empty unless ExperimentParam::histogram_bins > 0
+    pub energy_histogram: Histogram<T>,         // P(E)
+}
+
+// Observables derived from the averages: specific heat, susceptibility, correlation length & Binder cumulant (per spin)
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct DerivedObservables<T>
+{
+    pub(crate) specific_heat: T,
+    pub(crate) susceptibility: T,
+    pub(crate) corr_length: T,          // average of the directions with more than one row/column
+    pub(crate) corr_length_x: T,
+    pub(crate) corr_length_y: T,
+    pub(crate) binder_cumulant: T,
+}
+
+impl<T> MonteCarloResults<T> where T: Float + Default
+{
+    // Adds weight * (one measurement) to the averages, weight = 1 / number of measurements
+    #[inline(always)]
+    pub fn add_measurement(&mut self, spin_sum: T, energy: T, struct_fact_q0: T, struct_fact_qx: T, struct_fact_qy: T, weight: T)
+    {
+        self.spins_sum_avg      = self.spins_sum_avg + spin_sum.abs() * weight;
+        self.spins_sqr_avg      = self.spins_sqr_avg + spin_sum.powi(2) * weight;
+        self.spins_abs_cube_avg = self.spins_abs_cube_avg + spin_sum.abs().powi(3) * weight;
+        self.spins_quad_avg     = self.spins_quad_avg + spin_sum.powi(4) * weight;
+        self.energy_avg         = self.energy_avg + energy * weight;
+        self.energy_sqr_avg     = self.energy_sqr_avg + energy.powi(2) * weight;
+        self.struct_fact_q0     = self.struct_fact_q0 + struct_fact_q0 * weight;
+        self.struct_fact_qx     = self.struct_fact_qx + struct_fact_qx * weight;
+        self.struct_fact_qy     = self.struct_fact_qy + struct_fact_qy * weight;
+    }
+    // a * self + b * other for the averages only
+    fn linear_combination(&self, a: T, other: &Self, b: T) -> Self
+    {
+        Self
+        {
+            spins_sum_avg:      a * self.spins_sum_avg + b * other.spins_sum_avg,
+            spins_sqr_avg:      a * self.spins_sqr_avg + b * other.spins_sqr_avg,
+            spins_abs_cube_avg: a * self.spins_abs_cube_avg + b * other.spins_abs_cube_avg,
+            spins_quad_avg:     a * self.spins_quad_avg + b * other.spins_quad_avg,
+            energy_avg:         a * self.energy_avg + b * other.energy_avg,
+            energy_sqr_avg:     a * self.energy_sqr_avg + b * other.energy_sqr_avg,
+            struct_fact_q0:     a * self.struct_fact_q0 + b * other.struct_fact_q0,
+            struct_fact_qx:     a * self.struct_fact_qx + b * other.struct_fact_qx,
+            struct_fact_qy:     a * self.struct_fact_qy + b * other.struct_fact_qy,
+            ..Default::default()
+        }
+    }
+    // Averages without the bin i, for i = 0..bins.len()
+    pub fn jackknife_samples(&self) -> Vec<Self>
+    {
+        let n_bins = T::from(self.bins.len()).unwrap();
+        let total  = self.bins.iter().fold(Self::default(), |acc, bin| acc.linear_combination(T::one(), bin, T::one()));
+        self.bins.iter().map(|bin| total.linear_combination(T::one() / (n_bins - T::one()), bin, -T::one() / (n_bins - T::one()))).collect()
+    }
+    // U4 = 1 - <m^4> / (3 <m^2>^2): 2/3 in the ordered phase, 0 in the disordered one & size independent at Tc
+    pub fn binder_cumulant(&self) -> T
+    {
+        T::one() - self.spins_quad_avg / (T::from(3).unwrap() * self.spins_sqr_avg.powi(2))
+    }
+    // Second-moment correlation length along q = 2pi / L: sqrt(S(0) / S(q) - 1) / q, zero without structure factor
+    fn second_moment_length(&self, struct_fact_q: T, q: T) -> T
+    {
+        if self.struct_fact_q0.is_zero() || struct_fact_q.is_zero()
+        {
+            return T::zero();
+        }
+        (self.struct_fact_q0/struct_fact_q - T::one()).abs().sqrt() / q
+    }
+    // wave_numbers: (qx, qy) = (2pi / columns, 2pi / rows), qy = None for a chain or a graph (no y direction)
+    pub(crate) fn derived_observables(&self, temp: T, num_spins: T, wave_numbers: (T, Option<T>)) -> DerivedObservables<T>
+    {
+        let (qx, qy)      = wave_numbers;
+        let corr_length_x = self.second_moment_length(self.struct_fact_qx, qx);
+        let corr_length_y = qy.map_or(T::zero(), |qy| self.second_moment_length(self.struct_fact_qy, qy));
+        DerivedObservables
+        {
+            specific_heat:   (self.energy_sqr_avg - self.energy_avg.powi(2) ) / (temp.powi(2) * num_spins),
+            susceptibility:  (self.spins_sqr_avg - self.spins_sum_avg.powi(2)) / (temp * num_spins),
+            corr_length:     if qy.is_some() {(corr_length_x + corr_length_y) / T::from(2).unwrap()} else {corr_length_x},
+            corr_length_x,
+            corr_length_y,
+            binder_cumulant: self.binder_cumulant(),
+        }
+    }
 }
 
 
-impl<T> MonteCarloResults<T> where T: Float + std::fmt::Display
+impl<T> MonteCarloResults<T> where T: Float + Default + std::fmt::Display
 {
-    pub fn write_to_file(file_name: &String, temperatures: &[T], results: &[MonteCarloResults<T>], rows: usize, cols: usize, elapsed_time: std::time::Duration ) -> std::io::Result<()>
+    // shape: [rows, cols] for a 2D lattice, [layers, rows, cols] for a 3D one: the correlation lengths are along the cols (x) & rows (y)
+    // exact_reference: Some(J) adds the exact columns of the zero-field square lattice (periodic rows x cols torus & infinite lattice)
+    pub fn write_to_file(file_name: &String, temperatures: &[T], results: &[MonteCarloResults<T>], shape: &[usize], exact_reference: Option<T>, elapsed_time: std::time::Duration ) -> std::io::Result<()>
     {
         if temperatures.len() != results.len()
         {
@@ -27,32 +130,87 @@ impl<T> MonteCarloResults<T> where T: Float + std::fmt::Display
         {
             return Err(std::io::Error::other("Temperatures should be positive"));
         }
+        let Some(&cols) = shape.last() else
+        {
+            return Err(std::io::Error::other("Lattice shape should not be empty"));
+        };
+        if exact_reference.is_some_and(|j| shape.len() != 2 || j.is_nan() || j.is_sign_negative() || j.is_zero())
+        {
+            return Err(std::io::Error::other("Exact reference only for 2D lattices with J > 0"));
+        }
         
 
         let mut file= std::fs::File::create(file_name)?;
-        writeln!(&mut file, "temp, energy_density, magnetisation, specific_heat, susceptibility, correlation length, elapsed_time: {}", elapsed_time.as_secs())?;
+        let exact_header = match exact_reference
+        {
+            Some(_) => "exact_energy_density, exact_specific_heat, onsager_energy_density, onsager_specific_heat, onsager_magnetisation, ",
+            None    => "",
+        };
+        writeln!(&mut file, "temp, energy_density, magnetisation, specific_heat, susceptibility, correlation length, correlation_length_x, correlation_length_y, binder_cumulant, tau_int_energy, tau_int_magnetisation, effective_samples_energy, effective_samples_magnetisation, \
+                               energy_density_error, magnetisation_error, specific_heat_error, susceptibility_error, correlation_length_error, correlation_length_x_error, correlation_length_y_error, binder_cumulant_error, {exact_header}elapsed_time: {}", elapsed_time.as_secs())?;
 
-        let qx        = 2_f64 * PI / cols as f64;
-        let num_spins = T::from(rows*cols).unwrap();
+        let qx        = T::from(2_f64 * PI / cols as f64).unwrap();
+        let qy        = match *shape
+        {
+            [.., rows, _] if rows > 1 => Some(T::from(2_f64 * PI / rows as f64).unwrap()),
+            _                         => None,
+        };
+        let num_spins = T::from(shape.iter().product::<usize>()).unwrap();
 
         for  (&temp, res) in zip(temperatures, results)
         {
-            let specific_heat   = (res.energy_sqr_avg - res.energy_avg.powi(2) ) / (temp.powi(2) * num_spins);
             let energy_density  = res.energy_avg / num_spins;
             let magnetisation   = res.spins_sum_avg / num_spins;
-            let susceptibility  = (res.spins_sqr_avg - res.spins_sum_avg.powi(2)) / (temp * num_spins);
-            let mut corr_length = T::zero();
-            if !res.struct_fact_q0.is_zero() && !res.struct_fact_qx.is_zero()
+            let DerivedObservables { specific_heat, susceptibility, corr_length, corr_length_x, corr_length_y, binder_cumulant } = res.derived_observables(temp, num_spins, (qx, qy));
+            let (tau_energy, tau_magnetisation) = (res.energy_tau_int, res.abs_spins_tau_int);
+            let samples_energy        = statistics::effective_samples(res.number_of_measures, tau_energy);
+            let samples_magnetisation = statistics::effective_samples(res.number_of_measures, tau_magnetisation);
+            write!(&mut file, "{temp}, {energy_density}, {magnetisation}, {specific_heat}, {susceptibility}, {corr_length}, {corr_length_x}, {corr_length_y}, {binder_cumulant}")?;
+            write!(&mut file, ", {tau_energy}, {tau_magnetisation}, {samples_energy}, {samples_magnetisation}")?;
+
+            // Binning for the averages, jackknife for the derived observables
+            let energy_error        = statistics::binning_error(&res.bins.iter().map(|bin| bin.energy_avg).collect::<Vec<T>>()) / num_spins;
+            let magnetisation_error = statistics::binning_error(&res.bins.iter().map(|bin| bin.spins_sum_avg).collect::<Vec<T>>()) / num_spins;
+            let samples             = res.jackknife_samples().iter().map(|sample| sample.derived_observables(temp, num_spins, (qx, qy))).collect::<Vec<_>>();
+            let jackknife_error     = |observable: fn(&DerivedObservables<T>) -> T| statistics::jackknife_error(&samples.iter().map(observable).collect::<Vec<T>>());
+            write!(&mut file, ", {energy_error}, {magnetisation_error}, {}, {}", jackknife_error(|d| d.specific_heat), jackknife_error(|d| d.susceptibility))?;
+            write!(&mut file, ", {}, {}, {}, {}", jackknife_error(|d| d.corr_length), jackknife_error(|d| d.corr_length_x), jackknife_error(|d| d.corr_length_y), jackknife_error(|d| d.binder_cumulant))?;
+            if let Some(j) = exact_reference
             {
-                let sf_ratio  = (res.struct_fact_q0/res.struct_fact_qx - T::one()).abs().sqrt();
-                corr_length   = sf_ratio / T::from(qx).unwrap();
+                let (t, j) = (temp.to_f64().unwrap(), j.to_f64().unwrap());
+                write!(&mut file, ", {}, {}", exact_solution::torus_energy(shape[0], cols, t, j), exact_solution::torus_specific_heat(shape[0], cols, t, j))?;
+                write!(&mut file, ", {}, {}, {}", exact_solution::onsager_energy(t, j), exact_solution::onsager_specific_heat(t, j), exact_solution::onsager_magnetisation(t, j))?;
             }
-            writeln!(&mut file, "{temp}, {energy_density}, {magnetisation}, {specific_heat}, {susceptibility}, {corr_length}")?;
+            writeln!(&mut file)?;
         }
     
         Ok(())
     }
 }
 
- 
- 
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_correlation_lengths_of_rectangular_lattices()
+    {
+        // Ornstein-Zernike S(q) = S(0) / (1 + xi^2 q^2) along each axis of a 6 x 16 lattice
+        let (rows, columns)  = (6, 16);
+        let (qx, qy)         = (2_f64 * PI / columns as f64, 2_f64 * PI / rows as f64);
+        let (xi_x, xi_y)     = (3_f64, 1.5_f64);
+        let results          = MonteCarloResults
+        {
+            struct_fact_q0: 10_f64,
+            struct_fact_qx: 10_f64 / (1_f64 + (xi_x * qx).powi(2)),
+            struct_fact_qy: 10_f64 / (1_f64 + (xi_y * qy).powi(2)),
+            ..Default::default()
+        };
+        let derived = results.derived_observables(1_f64, (rows * columns) as f64, (qx, Some(qy)));
+        assert!((derived.corr_length_x - xi_x).abs() < 1E-12 && (derived.corr_length_y - xi_y).abs() < 1E-12);
+        assert!((derived.corr_length - (xi_x + xi_y) / 2_f64).abs() < 1E-12);
+        assert_eq!(results.derived_observables(1_f64, columns as f64, (qx, None)).corr_length, derived.corr_length_x);
+    }
+}
@@ -0,0 +1,223 @@
+// Non-equilibrium relaxation: many independent lattices start from the same kind of state directly at the temperature (no thermalisation),
+// and the moments of m & e are averaged over them after every sweep. At Tc the ordered start decays like m(t) ~ t^{-beta/(nu z)},
+// and the random start grows like <m^2>(t) ~ t^{(d - 2 beta/nu)/z}, which give the dynamic exponent z without equilibrating.
+use num_traits::{AsPrimitive, Float};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{ExperimentParam, CalculationError, SpinUpdater, LatticeUpdater, UpdateAlgorithm};
+use periodic_array_2d_lib::{PeriodicArray2D, SpinValue, PhysicalObservable, SpinLattice};
+use monte_carlo_lib::{SpinEnergyFluctuation, MonteCarloRngInterface};
+use monte_carlo_lib::hamiltonian::Hamiltonian;
+use xorshifts::Xoshiro256pp;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InitialState
+{
+    #[default]
+    Ordered,    // all spins up
+    Random,     // infinite temperature
+}
+
+impl std::str::FromStr for InitialState
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "ordered"           => Ok(InitialState::Ordered),
+            "random"            => Ok(InitialState::Random),
+            _                   => Err(format!("Unknown initial state \"{s}\", expected ordered or random.")),
+        }
+    }
+}
+
+// Averages over the samples after t = 0, 1... sweeps (t = 0: the initial states), per spin. m is the signed magnetisation
+#[derive(Debug, Default, Clone)]
+pub struct RelaxationCurve<T> where T: Float
+{
+    pub samples: usize,
+    pub magnetisation: Vec<T>,
+    pub magnetisation_sqr: Vec<T>,
+    pub magnetisation_quad: Vec<T>,
+    pub energy_density: Vec<T>,
+    pub energy_density_sqr: Vec<T>,
+}
+
+impl<T> RelaxationCurve<T> where T: Float
+{
+    fn zeros(length: usize) -> Self
+    {
+        let zeros = vec![T::zero(); length];
+        Self { samples: 0, magnetisation: zeros.clone(), magnetisation_sqr: zeros.clone(), magnetisation_quad: zeros.clone(), energy_density: zeros.clone(), energy_density_sqr: zeros }
+    }
+    fn add(mut self, other: Self) -> Self
+    {
+        self.samples += other.samples;
+        for (sum, values) in [(&mut self.magnetisation, other.magnetisation), (&mut self.magnetisation_sqr, other.magnetisation_sqr), (&mut self.magnetisation_quad, other.magnetisation_quad),
+                              (&mut self.energy_density, other.energy_density), (&mut self.energy_density_sqr, other.energy_density_sqr)]
+        {
+            sum.iter_mut().zip(values).for_each(|(s, v)| *s = *s + v);
+        }
+        self
+    }
+    fn divide_by_samples(mut self) -> Self
+    {
+        let samples = T::from(self.samples.max(1)).unwrap();
+        for values in [&mut self.magnetisation, &mut self.magnetisation_sqr, &mut self.magnetisation_quad, &mut self.energy_density, &mut self.energy_density_sqr]
+        {
+            values.iter_mut().for_each(|v| *v = *v / samples);
+        }
+        self
+    }
+    #[inline(always)]
+    pub fn len(&self) -> usize
+    {
+        self.magnetisation.len()
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool
+    {
+        self.magnetisation.is_empty()
+    }
+    // 1 - <m^4> / (3 <m^2>^2)
+    pub fn binder_cumulant(&self, sweep: usize) -> T
+    {
+        T::one() - self.magnetisation_quad[sweep] / (T::from(3).unwrap() * self.magnetisation_sqr[sweep].powi(2))
+    }
+    // Standard errors of the averages over the independent samples
+    fn standard_error(&self, average: T, average_sqr: T) -> T
+    {
+        let samples = T::from(self.samples).unwrap();
+        if self.samples < 2 {T::nan()} else {((average_sqr - average.powi(2)).max(T::zero()) / (samples - T::one())).sqrt()}
+    }
+    pub fn magnetisation_error(&self, sweep: usize) -> T
+    {
+        self.standard_error(self.magnetisation[sweep], self.magnetisation_sqr[sweep])
+    }
+    pub fn energy_density_error(&self, sweep: usize) -> T
+    {
+        self.standard_error(self.energy_density[sweep], self.energy_density_sqr[sweep])
+    }
+}
+
+impl<T> RelaxationCurve<T> where T: Float + std::fmt::Display
+{
+    pub fn write_to_file(&self, file_name: &Path) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "sweep, magnetisation, magnetisation_error, magnetisation_sqr, binder_cumulant, energy_density, energy_density_error")?;
+        for sweep in 0..self.len()
+        {
+            writeln!(&mut file, "{sweep}, {}, {}, {}, {}, {}, {}", self.magnetisation[sweep], self.magnetisation_error(sweep), self.magnetisation_sqr[sweep],
+                     self.binder_cumulant(sweep), self.energy_density[sweep], self.energy_density_error(sweep))?;
+        }
+        file.flush()
+    }
+}
+
+// One relaxation curve per temperature of param, from `samples` rows x columns lattices each followed for param.measurement_steps sweeps
+// (param.thermalisation_steps is ignored). The samples of a temperature run in parallel.
+// Only the local dynamics (Metropolis & heat bath) have a dynamic exponent z: a Wolff "sweep" is a varying number of clusters,
+// the first one being almost the whole lattice from the ordered state, so the Wolff algorithm is rejected.
+pub fn perform_relaxation_parallel<S,P,H>(rows: usize, columns: usize, param: &ExperimentParam<P,H>, samples: usize, initial_state: InitialState) -> Result<Vec<RelaxationCurve<P>>, CalculationError>
+    where P:     PhysicalObservable + Send + Sync,
+          usize: AsPrimitive<P>,
+          S:     SpinValue<P> + Send,
+          H:     Hamiltonian<S,P> + Clone + Sync,
+          Xoshiro256pp: MonteCarloRngInterface<P>
+{
+    if param.temperatures.iter().any(|x| x.is_sign_negative())
+    {
+        return Err(CalculationError::NegativeTempError);
+    }
+    if param.algorithm == UpdateAlgorithm::Wolff
+    {
+        return Err(CalculationError::IncompatibleAlgorithm(String::from("The relaxation needs a local dynamics: metropolis or heat_bath")));
+    }
+    let new_lattice = ||
+    {
+        let mut spin_2d_arr = PeriodicArray2D::<S,P>::new_with_geometry(rows as i32, columns as i32, param.lattice, S::one)?;
+        spin_2d_arr.set_boundary_conditions(param.boundaries.0, param.boundaries.1)?;
+        Ok(spin_2d_arr)
+    };
+    new_lattice().map_err(CalculationError::ArrayInitError)?;
+    let length = param.measurement_steps + 1;
+
+    Ok(param.temperatures.iter().map(|&temp|
+    {
+        let hamiltonian = param.hamiltonian.at_temperature(temp);
+        (0..samples).into_par_iter().map(|_|
+        {
+            let mut my_rng       = Xoshiro256pp::from_os();
+            let mut spin_lattice = new_lattice().expect("the lattice was built once already");
+            let num_spins: P     = (spin_lattice.total_number() as usize).as_();
+            if initial_state == InitialState::Random
+            {
+                let half = P::from(0.5).unwrap();
+                (0..spin_lattice.total_number() as usize).for_each(|n| *spin_lattice.at_index_mut(n) = if my_rng.generate_rand_float(P::zero(), P::one()) < half {S::one()} else {-S::one()});
+            }
+            let mut updater         = SpinUpdater::new(param.algorithm, spin_lattice.total_number() as usize);
+            let mut spin_sum: P     = spin_lattice.sum_observable();
+            let mut total_energy: P = hamiltonian.total_energy(&spin_lattice);
+            let mut curve           = RelaxationCurve::zeros(length);
+            curve.samples           = 1;
+            for sweep in 0..length
+            {
+                let m = hamiltonian.measured_spin_sum(spin_sum) / num_spins;
+                let e = hamiltonian.measured_energy(&spin_lattice, total_energy) / num_spins;
+                (curve.magnetisation[sweep], curve.magnetisation_sqr[sweep], curve.magnetisation_quad[sweep]) = (m, m.powi(2), m.powi(4));
+                (curve.energy_density[sweep], curve.energy_density_sqr[sweep])                                = (e, e.powi(2));
+                if sweep + 1 < length
+                {
+                    let SpinEnergyFluctuation(dS, dE) = LatticeUpdater::<PeriodicArray2D<S,P>,H,S,P>::sweep(&mut updater, &mut spin_lattice, &mut my_rng, temp, &hamiltonian);
+                    spin_sum     += dS;
+                    total_energy += dE;
+                }
+            }
+            curve
+        }).reduce(|| RelaxationCurve::zeros(length), RelaxationCurve::add).divide_by_samples()
+    }).collect())
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::IsingHamiltonian;
+
+    #[test]
+    fn test_relaxation_from_ordered_and_random_states()
+    {
+        let param = ExperimentParam
+        {
+            temperatures:         vec![1_f64, 10_f64],
+            hamiltonian:          IsingHamiltonian::new(1_f64, 0_f64),
+            measurement_steps:    20,
+            ..Default::default()
+        };
+        let ordered = perform_relaxation_parallel::<i8,f64,_>(8, 8, &param, 200, InitialState::Ordered).unwrap();
+        let (cold, hot) = (&ordered[0], &ordered[1]);
+        assert_eq!((cold.len(), cold.samples), (21, 200));
+        assert_eq!((cold.magnetisation[0], cold.energy_density[0], cold.magnetisation_error(0)), (1_f64, -2_f64, 0_f64));
+        assert!((cold.binder_cumulant(0) - 2_f64 / 3_f64).abs() < 1E-12);
+        // The order survives far below Tc, and is lost after a few sweeps far above
+        assert!(cold.magnetisation[20] > 0.9_f64, "{cold:?}");
+        assert!(hot.magnetisation[20].abs() < 5_f64 * hot.magnetisation_error(20) + 0.05_f64, "{hot:?}");
+        assert!((hot.energy_density[20] + 0.2_f64).abs() < 0.05_f64, "e = {}", hot.energy_density[20]);   // -2 tanh(1/10) at high T
+
+        // Random start: <m^2> = 1 / N at t = 0, then the domains grow below Tc
+        let random = perform_relaxation_parallel::<i8,f64,_>(8, 8, &param, 200, InitialState::Random).unwrap();
+        assert!((random[0].magnetisation_sqr[0] * 64_f64 - 1_f64).abs() < 0.3_f64, "{:?}", random[0].magnetisation_sqr);
+        assert!(random[0].magnetisation_sqr[20] > 4_f64 * random[0].magnetisation_sqr[0]);
+
+        // Heat bath is a local dynamics too, the Wolff clusters aren't
+        let heat_bath = ExperimentParam { algorithm: UpdateAlgorithm::HeatBath, ..param.with_hamiltonian(param.hamiltonian) };
+        assert!(perform_relaxation_parallel::<i8,f64,_>(8, 8, &heat_bath, 10, InitialState::Ordered).is_ok());
+        let wolff = ExperimentParam { algorithm: UpdateAlgorithm::Wolff, ..param.with_hamiltonian(param.hamiltonian) };
+        assert!(matches!(perform_relaxation_parallel::<i8,f64,_>(8, 8, &wolff, 10, InitialState::Ordered), Err(CalculationError::IncompatibleAlgorithm(_))));
+    }
+}
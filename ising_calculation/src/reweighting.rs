@@ -0,0 +1,251 @@
+// Ferrenberg-Swendsen reweighting of the recorded time series (E & M of every sweep, see ExperimentParam::record_time_series), k_B = 1.
+// * A single run at beta_0 gives the averages at a nearby beta: <O>_beta = sum_n O_n exp(-(beta - beta_0) E_n) / sum_n exp(-(beta - beta_0) E_n).
+// * Several runs are combined with the multi-histogram (WHAM) equations, solved self-consistently for the partition functions Z_k:
+//       Z_k = sum_j sum_{n in run j} exp(-beta_k E_n) / (g_j D_n),     D_n = sum_i (N_i / g_i) exp(-beta_i E_n) / Z_i
+//   where g_i = 2 tau_int(E) of the run i, so that each run counts for its number of independent samples.
+//   With one run, this is the single histogram reweighting.
+// The results are only reliable where the energy histograms of the runs overlap, ie within a few standard deviations of <E> of the simulated temperatures.
+// The sampled energy shouldn't depend on the temperature: not for the Suzuki-Trotter couplings of the transverse field.
+use num_traits::Float;
+
+use crate::{statistics, CalculationError, MonteCarloResults, TimeSeries};
+
+const MAX_ITERATIONS: usize    = 10000;
+const TOLERANCE: f64           = 1E-10;    // on ln Z_k between two iterations
+const PEAK_GRID_POINTS: usize  = 64;       // coarse scan before the golden-section search
+const PEAK_ITERATIONS: usize   = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeakObservable
+{
+    SpecificHeat,
+    Susceptibility,
+}
+
+pub struct Reweighting<'a, T> where T: Float
+{
+    series: Vec<&'a TimeSeries<T>>,
+    log_inverse_g: Vec<T>,                  // -ln g_j
+    log_density: Vec<Vec<T>>,               // ln D_n of every sample of every run
+    log_partition_functions: Vec<T>,        // ln Z_k, with ln Z_0 = 0
+}
+
+// ln sum_i exp(x_i) without overflow
+fn log_sum_exp<T>(values: impl Iterator<Item = T> + Clone) -> T where T: Float
+{
+    let max = values.clone().fold(T::neg_infinity(), T::max);
+    if max == T::neg_infinity()
+    {
+        return max;
+    }
+    max + values.fold(T::zero(), |acc, x| acc + (x - max).exp()).ln()
+}
+
+impl<'a, T> Reweighting<'a, T> where T: Float + Default
+{
+    // runs[k]: results at temperatures[k], with their time series
+    pub fn new(temperatures: &[T], runs: &'a [MonteCarloResults<T>]) -> Result<Self, CalculationError>
+    {
+        if temperatures.len() != runs.len() || runs.is_empty()
+        {
+            return Err(CalculationError::ReweightingError(format!("{} temperatures for {} runs", temperatures.len(), runs.len())));
+        }
+        if temperatures.iter().any(|t| t.is_zero() || t.is_nan() || t.is_sign_negative())
+        {
+            return Err(CalculationError::NegativeTempError);
+        }
+        if runs.iter().any(|run| run.time_series.is_empty())
+        {
+            return Err(CalculationError::ReweightingError("No time series: set ExperimentParam::record_time_series".to_string()));
+        }
+
+        let betas: Vec<T>             = temperatures.iter().map(|&t| T::one() / t).collect();
+        let log_inverse_g: Vec<T>     = runs.iter().map(|run| -(T::from(2).unwrap() * run.energy_tau_int).max(T::one()).ln()).collect();
+        let log_samples: Vec<T>       = runs.iter().map(|run| T::from(run.time_series.len()).unwrap().ln()).collect();
+        let mut reweighting           = Self
+        {
+            series:                  runs.iter().map(|run| &run.time_series).collect(),
+            log_inverse_g,
+            log_density:             runs.iter().map(|run| vec![T::zero(); run.time_series.len()]).collect(),
+            log_partition_functions: vec![T::zero(); runs.len()],
+        };
+
+        for _ in 0..MAX_ITERATIONS
+        {
+            for (run, log_density) in reweighting.series.iter().zip(&mut reweighting.log_density)
+            {
+                for (log_d, &energy) in log_density.iter_mut().zip(&run.energy)
+                {
+                    *log_d = log_sum_exp((0..betas.len()).map(|i| log_samples[i] + reweighting.log_inverse_g[i] - betas[i] * energy - reweighting.log_partition_functions[i]));
+                }
+            }
+            let mut log_z: Vec<T> = betas.iter().map(|&beta| reweighting.log_unnormalised_partition_function(beta)).collect();
+            let reference         = log_z[0];
+            log_z.iter_mut().for_each(|z| *z = *z - reference);
+
+            let change = log_z.iter().zip(&reweighting.log_partition_functions).fold(T::zero(), |acc, (&new, &old)| acc.max((new - old).abs()));
+            reweighting.log_partition_functions = log_z;
+            if change < T::from(TOLERANCE).unwrap()
+            {
+                return Ok(reweighting);
+            }
+        }
+        Err(CalculationError::ReweightingError("The multi-histogram equations did not converge: the energy histograms of the runs should overlap".to_string()))
+    }
+
+    // ln(1/(g_j D_n)) - beta E_n: log of the weight of the sample n of run j at beta
+    #[inline(always)]
+    fn log_weights(&self, beta: T) -> impl Iterator<Item = (usize, usize, T)> + Clone + '_
+    {
+        self.series.iter().zip(&self.log_density).zip(&self.log_inverse_g).enumerate().flat_map(move |(j, ((run, log_density), &log_inverse_g))|
+            run.energy.iter().zip(log_density).enumerate().map(move |(n, (&energy, &log_d))| (j, n, log_inverse_g - beta * energy - log_d)))
+    }
+
+    fn log_unnormalised_partition_function(&self, beta: T) -> T
+    {
+        log_sum_exp(self.log_weights(beta).map(|(_, _, log_w)| log_w))
+    }
+
+    // ln Z(temp) - ln Z(temperatures[0])
+    pub fn log_partition_function(&self, temp: T) -> T
+    {
+        self.log_unnormalised_partition_function(T::one() / temp)
+    }
+
+    // Averages of E, |M|, M^2, |M|^3 & M^4 at temp, with the bins of the error bars made of the same part of every run.
+    // The structure factor isn't recorded (no correlation length) & the autocorrelation times are NaN.
+    pub fn results_at(&self, temp: T) -> MonteCarloResults<T>
+    {
+        let log_weights  = self.log_weights(T::one() / temp);
+        let max          = log_weights.clone().fold(T::neg_infinity(), |acc, (_, _, log_w)| acc.max(log_w));
+        let bin_of       = |j: usize, n: usize| n * statistics::NUMBER_OF_BINS / self.series[j].len();
+
+        let mut total      = T::zero();
+        let mut bin_totals = vec![T::zero(); statistics::NUMBER_OF_BINS];
+        for (j, n, log_w) in log_weights.clone()
+        {
+            let weight             = (log_w - max).exp();
+            total                  = total + weight;
+            bin_totals[bin_of(j, n)] = bin_totals[bin_of(j, n)] + weight;
+        }
+
+        let mut result = MonteCarloResults::<T>::default();
+        let mut bins   = vec![MonteCarloResults::<T>::default(); statistics::NUMBER_OF_BINS];
+        for (j, n, log_w) in log_weights
+        {
+            let weight              = (log_w - max).exp();
+            let (energy, spin_sum)  = (self.series[j].energy[n], self.series[j].spin_sum[n]);
+            let bin                 = bin_of(j, n);
+            result.add_measurement(spin_sum, energy, T::zero(), T::zero(), T::zero(), weight / total);
+            bins[bin].add_measurement(spin_sum, energy, T::zero(), T::zero(), T::zero(), weight / bin_totals[bin]);
+        }
+        result.number_of_measures = self.series.iter().map(|run| run.len()).sum();
+        result.energy_tau_int     = T::nan();
+        result.abs_spins_tau_int  = T::nan();
+        result.bins               = bins.into_iter().zip(bin_totals).filter(|(_, bin_total)| !bin_total.is_zero()).map(|(bin, _)| bin).collect();
+        result
+    }
+
+    // (temperature, value) of the maximum of C or chi (per spin) in [low, high]: scan, then golden-section search around the largest value
+    pub fn locate_peak(&self, observable: PeakObservable, low: T, high: T, num_spins: T) -> (T, T)
+    {
+        let value = |temp: T|
+        {
+            let derived = self.results_at(temp).derived_observables(temp, num_spins, (T::one(), None));
+            match observable
+            {
+                PeakObservable::SpecificHeat   => derived.specific_heat,
+                PeakObservable::Susceptibility => derived.susceptibility,
+            }
+        };
+        let step      = (high - low) / T::from(PEAK_GRID_POINTS).unwrap();
+        let grid_temp = |i: usize| low + step * T::from(i).unwrap();
+        let best      = (0..=PEAK_GRID_POINTS).map(|i| (i, value(grid_temp(i)))).fold((0, T::neg_infinity()), |acc, (i, v)| if v > acc.1 {(i, v)} else {acc}).0;
+
+        let ratio        = T::from((5_f64.sqrt() - 1_f64) / 2_f64).unwrap();
+        let (mut a, mut b) = (grid_temp(best.saturating_sub(1)), grid_temp((best + 1).min(PEAK_GRID_POINTS)));
+        let (mut c, mut d) = (b - ratio * (b - a), a + ratio * (b - a));
+        let (mut value_c, mut value_d) = (value(c), value(d));
+        for _ in 0..PEAK_ITERATIONS
+        {
+            if value_c > value_d
+            {
+                (b, d, value_d) = (d, c, value_c);
+                c               = b - ratio * (b - a);
+                value_c         = value(c);
+            }
+            else
+            {
+                (a, c, value_c) = (c, d, value_d);
+                d               = a + ratio * (b - a);
+                value_d         = value(d);
+            }
+        }
+        let temp = (a + b) / T::from(2).unwrap();
+        (temp, value(temp))
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{exact_solution, ExperimentParam, IsingHamiltonian, UpdateAlgorithm, perform_metropolis_computation_parallel};
+
+    #[test]
+    fn test_reweighting_matches_exact_torus()
+    {
+        const L: usize = 6;
+        let n_spins    = (L * L) as f64;
+        let param      = ExperimentParam
+        {
+            temperatures:         vec![2.2_f64, 2.5_f64, 2.8_f64],
+            hamiltonian:          IsingHamiltonian::new(1_f64, 0_f64),
+            algorithm:            UpdateAlgorithm::Wolff,
+            thermalisation_steps: 1000,
+            measurement_steps:    40000,
+            record_time_series:   true,
+            ..Default::default()
+        };
+        let results  = perform_metropolis_computation_parallel::<i8,f64,_>(L, L, &param).unwrap();
+        let check    = |reweighting: &Reweighting<f64>, temp: f64|
+        {
+            let result        = reweighting.results_at(temp);
+            let energy        = result.energy_avg / n_spins;
+            let specific_heat = result.derived_observables(temp, n_spins, (1_f64, None)).specific_heat;
+            let exact_energy  = exact_solution::torus_energy(L, L, temp, 1_f64);
+            let exact_heat    = exact_solution::torus_specific_heat(L, L, temp, 1_f64);
+            assert!((energy - exact_energy).abs() < 0.02, "T={temp}: <E>/N = {energy}, exact: {exact_energy}");
+            assert!((specific_heat - exact_heat).abs() < 0.06 * exact_heat, "T={temp}: C = {specific_heat}, exact: {exact_heat}");
+            assert_eq!(result.bins.len(), statistics::NUMBER_OF_BINS);
+        };
+
+        // Single histogram: the run at T = 2.5 alone
+        let single = Reweighting::new(&param.temperatures[1..2], &results[1..2]).unwrap();
+        check(&single, 2.4_f64);
+        check(&single, 2.6_f64);
+
+        // Multi-histogram: the free energy differences & the peak of C in between the runs
+        let multi = Reweighting::new(&param.temperatures, &results).unwrap();
+        check(&multi, 2.35_f64);
+        check(&multi, 2.65_f64);
+        for temp in [2.5_f64, 2.8_f64]
+        {
+            let exact   = exact_solution::torus_log_partition_function(L, L, temp, 1_f64) - exact_solution::torus_log_partition_function(L, L, 2.2_f64, 1_f64);
+            let log_z   = multi.log_partition_function(temp);
+            assert!((log_z - exact).abs() < 0.02, "T={temp}: ln Z(T)/Z(2.2) = {log_z}, exact: {exact}");
+        }
+        let exact_peak = (0..=6000).map(|i| 2.2_f64 + 1E-4 * i as f64).fold((0_f64, 0_f64), |acc, t|
+        {
+            let c = exact_solution::torus_specific_heat(L, L, t, 1_f64);
+            if c > acc.1 {(t, c)} else {acc}
+        });
+        let (peak_temp, peak_heat) = multi.locate_peak(PeakObservable::SpecificHeat, 2.2_f64, 2.8_f64, n_spins);
+        assert!((peak_temp - exact_peak.0).abs() < 0.03, "C peak at T = {peak_temp}, exact: {}", exact_peak.0);
+        assert!((peak_heat - exact_peak.1).abs() < 0.05 * exact_peak.1, "C peak = {peak_heat}, exact: {}", exact_peak.1);
+
+        assert!(Reweighting::new(&param.temperatures, &results[..2]).is_err());
+        assert!(Reweighting::new(&param.temperatures[..1], &[MonteCarloResults::<f64>::default()]).is_err());
+    }
+}
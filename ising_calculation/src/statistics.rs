@@ -0,0 +1,136 @@
+// Error analysis of correlated Monte-Carlo time series
+use num_traits::Float;
+
+const SOKAL_WINDOW_FACTOR: usize = 6;   // c in the automatic windowing condition W >= c tau_int(W)
+pub const NUMBER_OF_BINS: usize  = 32;  // bins of the measurement phase for the error bars, each needs to be >> tau_int sweeps long
+
+// Integrated autocorrelation time tau_int = 1/2 + sum_{t=1}^{W} rho(t), with the automatic window of Sokal & Madras:
+// the smallest W such that W >= c tau_int(W), so that the noise of rho(t) at large t is left out while the bias stays ~exp(-c).
+// Uncorrelated data give 1/2, and the N measurements are worth N / (2 tau_int) independent ones.
+pub fn integrated_autocorrelation_time<T>(series: &[T]) -> T where T: Float
+{
+    let half  = T::from(0.5).unwrap();
+    let n     = series.len();
+    if n < 2
+    {
+        return half;
+    }
+    let mean  = series.iter().fold(T::zero(), |acc, &x| acc + x) / T::from(n).unwrap();
+    let autocovariance = |t: usize| (0..n - t).fold(T::zero(), |acc, i| acc + (series[i] - mean) * (series[i + t] - mean)) / T::from(n - t).unwrap();
+    let variance = autocovariance(0);
+    if variance <= T::zero()
+    {
+        return half;    // constant series, eg frozen at T -> 0
+    }
+
+    let mut tau = half;
+    for window in 1..n / 2
+    {
+        tau = tau + autocovariance(window) / variance;
+        if T::from(window).unwrap() >= T::from(SOKAL_WINDOW_FACTOR).unwrap() * tau
+        {
+            break;
+        }
+    }
+    tau.max(half)
+}
+
+// tau_int from the averages of consecutive bins of bin_length measurements, without keeping the series: the variance of a bin average
+// is 2 tau_int var(x) / bin_length for bins >> tau_int (the estimate saturates at ~bin_length / 2 for shorter ones).
+// 1/2 without fluctuations or with less than 2 bins.
+pub fn binned_autocorrelation_time<T>(bin_means: &[T], bin_length: usize, variance: T) -> T where T: Float
+{
+    let half   = T::from(0.5).unwrap();
+    if bin_means.len() < 2 || variance <= T::zero()
+    {
+        return half;
+    }
+    let n_bins = T::from(bin_means.len()).unwrap();
+    let mean   = bin_means.iter().fold(T::zero(), |acc, &x| acc + x) / n_bins;
+    let spread = bin_means.iter().fold(T::zero(), |acc, &x| acc + (x - mean).powi(2)) / (n_bins - T::one());
+    (T::from(bin_length).unwrap() * spread / (T::from(2).unwrap() * variance)).max(half)
+}
+
+// N / (2 tau_int)
+#[inline(always)]
+pub fn effective_samples<T>(number_of_measures: usize, tau_int: T) -> T where T: Float
+{
+    T::from(number_of_measures).unwrap() / (T::from(2).unwrap() * tau_int)
+}
+
+// Statistical error of the average of correlated measurements from the averages of long enough bins (>> tau_int), which are independent.
+// NaN with less than 2 bins.
+pub fn binning_error<T>(bin_means: &[T]) -> T where T: Float
+{
+    let n_bins = T::from(bin_means.len()).unwrap();
+    let mean   = bin_means.iter().fold(T::zero(), |acc, &x| acc + x) / n_bins;
+    let spread = bin_means.iter().fold(T::zero(), |acc, &x| acc + (x - mean).powi(2));
+    (spread / (n_bins * (n_bins - T::one()))).sqrt()
+}
+
+// Error of a (non-linear) function of averages, from its values f_i computed without the bin i:
+// sigma^2 = (n - 1) / n sum_i (f_i - <f>)^2. NaN with less than 2 bins.
+pub fn jackknife_error<T>(leave_one_out: &[T]) -> T where T: Float
+{
+    let n_bins = T::from(leave_one_out.len()).unwrap();
+    let mean   = leave_one_out.iter().fold(T::zero(), |acc, &x| acc + x) / n_bins;
+    let spread = leave_one_out.iter().fold(T::zero(), |acc, &x| acc + (x - mean).powi(2));
+    ((n_bins - T::one()) / n_bins * spread).sqrt()
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use xorshifts::Xoshiro256pp;
+    use monte_carlo_lib::MonteCarloRngInterface;
+
+    // AR(1) process x_t+1 = a x_t + noise: rho(t) = a^t & tau_int = (1 + a) / (2 (1 - a))
+    #[test]
+    fn test_autocorrelation_time()
+    {
+        let mut rng          = Xoshiro256pp::new(1, 2, 3, 4);
+        let white: Vec<f64> = (0..200000).map(|_| rng.generate_rand_float(-0.5_f64, 0.5_f64)).collect();
+        assert!((integrated_autocorrelation_time(&white) - 0.5_f64).abs() < 0.02);
+
+        for a in [0.5_f64, 0.9_f64]
+        {
+            let mut x = 0_f64;
+            let series: Vec<f64> = white.iter().map(|w| {x = a * x + w; x}).collect();
+            let exact = (1_f64 + a) / (2_f64 * (1_f64 - a));
+            let tau   = integrated_autocorrelation_time(&series);
+            assert!((tau - exact).abs() < 0.05 * exact, "a={a}: tau_int = {tau}, exact: {exact}");
+
+            // 400 bins of 500 measurements: relative statistical error ~ sqrt(2 / 400)
+            let mean     = series.iter().sum::<f64>() / series.len() as f64;
+            let variance = series.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / series.len() as f64;
+            let bins: Vec<f64> = series.chunks(500).map(|bin| bin.iter().sum::<f64>() / 500_f64).collect();
+            let binned   = binned_autocorrelation_time(&bins, 500, variance);
+            assert!((binned - exact).abs() < 0.25 * exact, "a={a}: binned tau_int = {binned}, exact: {exact}");
+        }
+        assert_eq!(integrated_autocorrelation_time(&[1_f64; 10]), 0.5_f64);
+        assert_eq!(binned_autocorrelation_time(&[1_f64; 10], 100, 0_f64), 0.5_f64);
+        assert_eq!(effective_samples(1000, 5_f64), 100_f64);
+    }
+
+    #[test]
+    fn test_binning_and_jackknife()
+    {
+        // Error of the mean of correlated data: sigma sqrt(2 tau_int / N), sigma^2 = 1 / (12 (1 - a^2)) for the AR(1) process
+        let mut rng      = Xoshiro256pp::new(5, 6, 7, 8);
+        let (a, n)       = (0.9_f64, 320000);
+        let mut x        = 0_f64;
+        let series: Vec<f64> = (0..n).map(|_| {x = a * x + rng.generate_rand_float(-0.5_f64, 0.5_f64); x}).collect();
+        let exact        = (2_f64 * (1_f64 + a) / (2_f64 * (1_f64 - a)) / (12_f64 * (1_f64 - a * a)) / n as f64).sqrt();
+        let bins: Vec<f64> = series.chunks(n / NUMBER_OF_BINS).map(|bin| bin.iter().sum::<f64>() / bin.len() as f64).collect();
+        let error        = binning_error(&bins);
+        assert!((error - exact).abs() < 0.35 * exact, "binning: {error}, exact: {exact}");
+
+        // The jackknife of the mean is the binning error
+        let total: f64   = bins.iter().sum();
+        let leave_one_out: Vec<f64> = bins.iter().map(|b| (total - b) / (NUMBER_OF_BINS - 1) as f64).collect();
+        assert!((jackknife_error(&leave_one_out) - error).abs() < 1E-12 * error);
+        assert!(binning_error(&[1_f64]).is_nan());
+    }
+}
@@ -0,0 +1,264 @@
+use num::Complex;
+use num_traits::Float;
+use std::f64::consts::PI;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use periodic_array_2d_lib::{PhysicalObservable, SpinLattice, SpinValue};
+use monte_carlo_lib::fourier::Fft2D;
+
+// Spin structure factor S(k) = <|sum_r s_r exp(-i k.r)|^2> / N over the whole rows x columns Brillouin zone, k = 2 pi (ky / rows, kx / columns).
+// The lattice is seen as a rows x columns array (n = y * columns + x), so that k is in units of the reciprocal lattice vectors of the geometry.
+// S(0), S(0, 2 pi / columns) & S(2 pi / rows, 0) are the struct_fact_q0, struct_fact_qx & struct_fact_qy of MonteCarloResults.
+// Its inverse transform is the real-space correlation function G(r), see correlation_function().
+#[derive(Debug, Default, Clone)]
+pub struct StructureFactor<T> where T: Float
+{
+    rows: usize,
+    columns: usize,
+    sum: Vec<T>,                // sum of |sigma_k|^2 / N over the measurements, row-major (ky, kx)
+    magnetisation_sum: T,       // sum of the signed m = M / N, for the disconnected part of G(r)
+    number_of_measures: usize,
+}
+
+// Fourier transform of the spins with its buffers, one per thread
+pub struct StructureFactorMeter<P>
+{
+    fft: Fft2D<P>,
+    buffer: Vec<Complex<P>>,
+}
+
+impl<P> StructureFactorMeter<P> where P: PhysicalObservable
+{
+    pub fn new(rows: usize, columns: usize) -> Self
+    {
+        Self { fft: Fft2D::new(rows, columns), buffer: vec![Complex::default(); rows * columns] }
+    }
+}
+
+impl<T> StructureFactor<T> where T: PhysicalObservable
+{
+    pub fn new(rows: usize, columns: usize) -> Self
+    {
+        Self { rows, columns, sum: vec![T::zero(); rows * columns], magnetisation_sum: T::zero(), number_of_measures: 0 }
+    }
+
+    // Adds |sigma_k|^2 / N of the current configuration (the spins should have rows * columns sites)
+    pub fn measure<L, S>(&mut self, spins: &L, meter: &mut StructureFactorMeter<T>)
+        where L: SpinLattice<S,T>,
+              S: SpinValue<T>,
+    {
+        debug_assert_eq!(spins.total_number() as usize, self.sum.len());
+        meter.buffer.iter_mut().enumerate().for_each(|(n, z)| *z = Complex::new(spins.at_index(n).as_(), T::zero()));
+        meter.fft.transform(&mut meter.buffer);
+        let inverse_spins = T::one() / T::from(self.sum.len()).unwrap();
+        self.sum.iter_mut().zip(&meter.buffer).for_each(|(s, z)| *s += z.norm_sqr() * inverse_spins);
+        self.magnetisation_sum  += meter.buffer[0].re * inverse_spins;
+        self.number_of_measures += 1;
+    }
+}
+
+impl<T> StructureFactor<T> where T: Float
+{
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool
+    {
+        self.number_of_measures == 0
+    }
+    pub fn number_of_measures(&self) -> usize
+    {
+        self.number_of_measures
+    }
+
+    // (ky, kx) in [0, 2 pi)
+    pub fn at(&self, ky_index: usize, kx_index: usize) -> T
+    {
+        self.sum[ky_index * self.columns + kx_index] / T::from(self.number_of_measures).unwrap()
+    }
+
+    // Components of the mode in (-pi, pi]
+    fn wave_vector(&self, ky_index: usize, kx_index: usize) -> (T, T)
+    {
+        let (dy, dx) = self.displacement(ky_index, kx_index);
+        (dy * T::from(2_f64 * PI / self.rows as f64).unwrap(), dx * T::from(2_f64 * PI / self.columns as f64).unwrap())
+    }
+
+    // Index along each axis folded into (-len/2, len/2]: displacement of the minimum image in real space
+    fn displacement(&self, y_index: usize, x_index: usize) -> (T, T)
+    {
+        let fold = |index: usize, len: usize| T::from(if 2 * index > len {index as f64 - len as f64} else {index as f64}).unwrap();
+        (fold(y_index, self.rows), fold(x_index, self.columns))
+    }
+
+    // (mean radius, mean value, number of points) over the shells of the given width, for the non-empty shells by increasing radius
+    fn shell_average(&self, values: &[T], radius: impl Fn(usize, usize) -> T, shell_width: T) -> Vec<(T, T, usize)>
+    {
+        let mut shells: Vec<(T, T, usize)> = Vec::new();
+        for (y, x) in (0..self.rows).flat_map(|y| (0..self.columns).map(move |x| (y, x)))
+        {
+            let r     = radius(y, x);
+            let shell = (r / shell_width).round().to_usize().unwrap();
+            if shells.len() <= shell
+            {
+                shells.resize(shell + 1, (T::zero(), T::zero(), 0));
+            }
+            shells[shell] = (shells[shell].0 + r, shells[shell].1 + values[y * self.columns + x], shells[shell].2 + 1);
+        }
+        shells.into_iter().filter(|shell| shell.2 > 0).map(|(r, v, n)| (r / T::from(n).unwrap(), v / T::from(n).unwrap(), n)).collect()
+    }
+
+    // Averages over shells of width 2 pi / max(rows, columns) in |k|: (mean |k|, mean S, number of modes) for the non-empty shells, by increasing |k|
+    pub fn radial_average(&self) -> Vec<(T, T, usize)>
+    {
+        let average: Vec<T> = self.sum.iter().map(|&s| s / T::from(self.number_of_measures).unwrap()).collect();
+        let shell_width     = T::from(2_f64 * PI / self.rows.max(self.columns) as f64).unwrap();
+        self.shell_average(&average, |y, x| { let (ky, kx) = self.wave_vector(y, x); (kx * kx + ky * ky).sqrt() }, shell_width)
+    }
+}
+
+impl<T> StructureFactor<T> where T: PhysicalObservable
+{
+    // Connected correlation function G(r) = <s_0 s_r> - <s>^2, averaged over the lattice translations, as the inverse transform of S(k):
+    // (1/N) sum_i s_i s_(i+r) = (1/N) sum_k |sigma_k|^2 exp(i k.r) / N. Row-major rows x columns array of G(dy, dx).
+    // <s> is the signed magnetisation per spin of the same measurements: ~0 without field on a finite lattice, unless it stays in one ordered state.
+    pub fn correlation_function(&self) -> Vec<T>
+    {
+        let magnetisation = self.magnetisation_sum / T::from(self.number_of_measures).unwrap();
+        let n_spins     = self.sum.len();
+        let mut buffer: Vec<Complex<T>> = (0..n_spins).map(|n| Complex::new(self.sum[n] / T::from(self.number_of_measures).unwrap(), T::zero())).collect();
+        Fft2D::new(self.rows, self.columns).transform(&mut buffer);    // S(k) = S(-k): the forward transform is the inverse one
+        buffer.iter().map(|z| z.re / T::from(n_spins).unwrap() - magnetisation * magnetisation).collect()
+    }
+
+    // G(r) along the axes for r = 0..=L/2: (r, G(0, r), G(r, 0)), G(r, 0) being NaN beyond rows / 2
+    pub fn correlation_along_axes(&self, correlation: &[T]) -> Vec<(usize, T, T)>
+    {
+        (0..=self.rows.max(self.columns) / 2).map(|r|
+        {
+            let along_x = if r <= self.columns / 2 {correlation[r]} else {T::nan()};
+            let along_y = if r <= self.rows / 2 {correlation[r * self.columns]} else {T::nan()};
+            (r, along_x, along_y)
+        }).collect()
+    }
+
+    // Averages of G over shells of unit width in |r| (minimum image): (mean |r|, mean G, number of displacements)
+    pub fn correlation_radial_average(&self, correlation: &[T]) -> Vec<(T, T, usize)>
+    {
+        self.shell_average(correlation, |y, x| { let (dy, dx) = self.displacement(y, x); (dx * dx + dy * dy).sqrt() }, T::one())
+    }
+}
+
+impl<T> StructureFactor<T> where T: Float + std::fmt::Display
+{
+    // "kx, ky, struct_fact" for every mode (k in (-pi, pi]), then an empty line & "k, radial_struct_fact, modes" for the radial average
+    pub fn write_to_file(&self, file_name: &Path) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "kx, ky, struct_fact")?;
+        for (ky_index, kx_index) in (0..self.rows).flat_map(|y| (0..self.columns).map(move |x| (y, x)))
+        {
+            let (ky, kx) = self.wave_vector(ky_index, kx_index);
+            writeln!(&mut file, "{kx}, {ky}, {}", self.at(ky_index, kx_index))?;
+        }
+        writeln!(&mut file)?;
+        writeln!(&mut file, "k, radial_struct_fact, modes")?;
+        for (k, s, modes) in self.radial_average()
+        {
+            writeln!(&mut file, "{k}, {s}, {modes}")?;
+        }
+        file.flush()
+    }
+}
+
+impl<T> StructureFactor<T> where T: PhysicalObservable + std::fmt::Display
+{
+    // "r, correlation_x, correlation_y" along the axes, then an empty line & "r, radial_correlation, pairs" for the radial average
+    pub fn write_correlation_to_file(&self, file_name: &Path) -> std::io::Result<()>
+    {
+        let correlation = self.correlation_function();
+        let mut file    = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "r, correlation_x, correlation_y")?;
+        for (r, along_x, along_y) in self.correlation_along_axes(&correlation)
+        {
+            writeln!(&mut file, "{r}, {along_x}, {along_y}")?;
+        }
+        writeln!(&mut file)?;
+        writeln!(&mut file, "r, radial_correlation, pairs")?;
+        for (r, g, pairs) in self.correlation_radial_average(&correlation)
+        {
+            writeln!(&mut file, "{r}, {g}, {pairs}")?;
+        }
+        file.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use periodic_array_2d_lib::PeriodicArray2D;
+
+    #[test]
+    fn test_structure_factor_of_ordered_states()
+    {
+        let (rows, columns)  = (4, 6);
+        let n_spins          = (rows * columns) as f64;
+        let mut meter        = StructureFactorMeter::<f64>::new(rows, columns);
+        let lattice          = |spin: fn(usize, usize) -> i8| PeriodicArray2D::<i8,f64>::new_with(rows as i32, columns as i32, || 1_i8).map(|mut array|
+        {
+            (0..rows * columns).for_each(|n| *array.at_index_mut(n) = spin(n / columns, n % columns));
+            array
+        }).unwrap();
+
+        // Ferromagnet: S(0) = N, Neel state: S(pi, pi) = N, stripes along the rows: S(pi, 0) = N, zero elsewhere
+        for (spins, peak) in [(lattice(|_, _| 1), (0, 0)), (lattice(|y, x| if (x + y) % 2 == 0 {1} else {-1}), (rows / 2, columns / 2)), (lattice(|y, _| if y % 2 == 0 {1} else {-1}), (rows / 2, 0))]
+        {
+            let mut struct_fact = StructureFactor::new(rows, columns);
+            struct_fact.measure(&spins, &mut meter);
+            struct_fact.measure(&spins, &mut meter);
+            for (ky, kx) in (0..rows).flat_map(|y| (0..columns).map(move |x| (y, x)))
+            {
+                let expected = if (ky, kx) == peak {n_spins} else {0_f64};
+                assert!((struct_fact.at(ky, kx) - expected).abs() < 1E-10, "S({ky}, {kx}) = {}, expected {expected}", struct_fact.at(ky, kx));
+            }
+            // sum_k S(k) = N (Parseval) & every mode is in one shell
+            let radial = struct_fact.radial_average();
+            assert_eq!(radial.iter().map(|&(_, _, n)| n).sum::<usize>(), rows * columns);
+            assert!((radial.iter().map(|&(_, s, n)| s * n as f64).sum::<f64>() - n_spins).abs() < 1E-9);
+            assert!(radial.windows(2).all(|w| w[0].0 < w[1].0));
+        }
+    }
+
+    #[test]
+    fn test_correlation_function()
+    {
+        // Stripes along the rows (s = (-1)^y): G(0, dx) = 1 & G(dy, 0) = (-1)^dy, the radial average counts every displacement once
+        let (rows, columns)  = (4, 7);
+        let mut spins        = PeriodicArray2D::<i8,f64>::new_with(rows as i32, columns as i32, || 1_i8).unwrap();
+        (0..rows * columns).for_each(|n| *spins.at_index_mut(n) = if (n / columns) % 2 == 0 {1} else {-1});
+        let mut struct_fact  = StructureFactor::new(rows, columns);
+        struct_fact.measure(&spins, &mut StructureFactorMeter::new(rows, columns));
+
+        let correlation = struct_fact.correlation_function();
+        let axes        = struct_fact.correlation_along_axes(&correlation);
+        assert_eq!(axes.len(), columns / 2 + 1);
+        for (r, along_x, along_y) in axes
+        {
+            assert!((along_x - 1_f64).abs() < 1E-10, "G(0, {r}) = {along_x}");
+            assert!(if r <= rows / 2 {(along_y - if r % 2 == 0 {1_f64} else {-1_f64}).abs() < 1E-10} else {along_y.is_nan()}, "G({r}, 0) = {along_y}");
+        }
+        let radial = struct_fact.correlation_radial_average(&correlation);
+        assert_eq!(radial.iter().map(|&(_, _, n)| n).sum::<usize>(), rows * columns);
+        assert!((radial[0].1 - 1_f64).abs() < 1E-10 && radial[0].2 == 1);
+
+        // Disconnected part: one flipped spin of the ferromagnet, <s> = 1 - 2/N
+        let n_spins = (rows * columns) as f64;
+        (0..rows * columns).for_each(|n| *spins.at_index_mut(n) = if n == 0 {-1} else {1});
+        let mut struct_fact = StructureFactor::new(rows, columns);
+        struct_fact.measure(&spins, &mut StructureFactorMeter::new(rows, columns));
+        let correlation     = struct_fact.correlation_function();
+        assert!((correlation[0] - 1_f64 + (1_f64 - 2_f64 / n_spins).powi(2)).abs() < 1E-10);
+        assert!((correlation[1] - (1_f64 - 4_f64 / n_spins) + (1_f64 - 2_f64 / n_spins).powi(2)).abs() < 1E-10);
+    }
+}
@@ -0,0 +1,174 @@
+use num_traits::Float;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use monte_carlo_lib::domains::DomainGeometry;
+
+// Measurements of every sweep of the measurement phase (the ones the averages are made of): total energy & signed spin sum M
+#[derive(Debug, Default, Clone)]
+pub struct TimeSeries<T> where T: Float
+{
+    pub energy: Vec<T>,
+    pub spin_sum: Vec<T>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSeriesFormat
+{
+    #[default]
+    Csv,        // "energy, spin_sum" header, then one line per sweep
+    Binary,     // no header: little-endian f64 pairs (energy, spin_sum), eg np.fromfile(f, dtype="<f8").reshape(-1, 2)
+}
+
+impl std::str::FromStr for TimeSeriesFormat
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "csv"             => Ok(TimeSeriesFormat::Csv),
+            "binary" | "bin"  => Ok(TimeSeriesFormat::Binary),
+            _                 => Err(format!("Unknown time series format \"{s}\", expected csv or binary.")),
+        }
+    }
+}
+
+impl<T> TimeSeries<T> where T: Float
+{
+    pub fn with_capacity(capacity: usize) -> Self
+    {
+        Self { energy: Vec::with_capacity(capacity), spin_sum: Vec::with_capacity(capacity) }
+    }
+    #[inline(always)]
+    pub fn push(&mut self, energy: T, spin_sum: T)
+    {
+        self.energy.push(energy);
+        self.spin_sum.push(spin_sum);
+    }
+    #[inline(always)]
+    pub fn len(&self) -> usize
+    {
+        self.energy.len()
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool
+    {
+        self.energy.is_empty()
+    }
+}
+
+impl<T> TimeSeries<T> where T: Float + std::fmt::Display
+{
+    pub fn write_to_file(&self, file_name: &Path, format: TimeSeriesFormat) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        match format
+        {
+            TimeSeriesFormat::Csv =>
+            {
+                writeln!(&mut file, "energy, spin_sum")?;
+                for (energy, spin_sum) in self.energy.iter().zip(&self.spin_sum)
+                {
+                    writeln!(&mut file, "{energy}, {spin_sum}")?;
+                }
+            },
+            TimeSeriesFormat::Binary =>
+            {
+                for (energy, spin_sum) in self.energy.iter().zip(&self.spin_sum)
+                {
+                    file.write_all(&energy.to_f64().unwrap().to_le_bytes())?;
+                    file.write_all(&spin_sum.to_f64().unwrap().to_le_bytes())?;
+                }
+            },
+        }
+        file.flush()
+    }
+
+    // Series of the temperature number `index`, next to the summary file "dir/name.txt": "dir/name_series_{index}_T{temp}.csv" (or .bin)
+    pub fn file_name(summary_file: &str, index: usize, temp: T, format: TimeSeriesFormat) -> std::path::PathBuf
+    {
+        let extension = match format
+        {
+            TimeSeriesFormat::Csv    => "csv",
+            TimeSeriesFormat::Binary => "bin",
+        };
+        crate::companion_file_name(summary_file, &format!("series_{index}_T{temp}.{extension}"))
+    }
+}
+
+// Domain geometry every ExperimentParam::domain_interval sweeps, counted from the start of the thermalisation (the coarsening)
+#[derive(Debug, Default, Clone)]
+pub struct DomainSeries
+{
+    pub sweeps: Vec<usize>,
+    pub geometry: Vec<DomainGeometry>,
+}
+
+impl DomainSeries
+{
+    #[inline(always)]
+    pub fn push(&mut self, sweep: usize, geometry: DomainGeometry)
+    {
+        self.sweeps.push(sweep);
+        self.geometry.push(geometry);
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool
+    {
+        self.sweeps.is_empty()
+    }
+    // "sweep, domain_wall_length, number_of_domains, euler_characteristic" (nan without a 2D array)
+    pub fn write_to_file(&self, file_name: &Path) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "sweep, domain_wall_length, number_of_domains, euler_characteristic")?;
+        for (sweep, geometry) in self.sweeps.iter().zip(&self.geometry)
+        {
+            let euler = geometry.euler_characteristic.map_or(String::from("nan"), |e| e.to_string());
+            writeln!(&mut file, "{sweep}, {}, {}, {euler}", geometry.domain_wall_length, geometry.number_of_domains)?;
+        }
+        file.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_time_series_files()
+    {
+        let mut series = TimeSeries::<f64>::with_capacity(3);
+        [(-8_f64, 4_f64), (-4_f64, -2_f64), (0.5_f64, 0_f64)].iter().for_each(|&(e, m)| series.push(e, m));
+        let summary = std::env::temp_dir().join(format!("time_series_test_{}.txt", std::process::id()));
+
+        let csv = TimeSeries::file_name(summary.to_str().unwrap(), 2, 1.5_f64, TimeSeriesFormat::Csv);
+        assert_eq!(csv.file_name().unwrap().to_str().unwrap(), format!("time_series_test_{}_series_2_T1.5.csv", std::process::id()));
+        series.write_to_file(&csv, TimeSeriesFormat::Csv).unwrap();
+        assert_eq!(std::fs::read_to_string(&csv).unwrap(), "energy, spin_sum\n-8, 4\n-4, -2\n0.5, 0\n");
+
+        let binary = TimeSeries::file_name(summary.to_str().unwrap(), 2, 1.5_f64, TimeSeriesFormat::Binary);
+        series.write_to_file(&binary, TimeSeriesFormat::Binary).unwrap();
+        let bytes  = std::fs::read(&binary).unwrap();
+        let values = bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect::<Vec<f64>>();
+        assert_eq!(values, [-8_f64, 4_f64, -4_f64, -2_f64, 0.5_f64, 0_f64]);
+
+        std::fs::remove_file(csv).unwrap();
+        std::fs::remove_file(binary).unwrap();
+    }
+
+    #[test]
+    fn test_domain_series_file()
+    {
+        let mut series = DomainSeries::default();
+        series.push(0, DomainGeometry { domain_wall_length: 0, number_of_domains: 1, euler_characteristic: Some(0) });
+        series.push(10, DomainGeometry { domain_wall_length: 12, number_of_domains: 3, euler_characteristic: None });
+        let file_name = std::env::temp_dir().join(format!("domain_series_test_{}.csv", std::process::id()));
+        series.write_to_file(&file_name).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_name).unwrap(), "sweep, domain_wall_length, number_of_domains, euler_characteristic\n0, 0, 1, 0\n10, 12, 3, nan\n");
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+// Transfer matrix of the square lattice Ising model on a strip of `width` spins & infinite length, H = -J sum_<ij> s_i s_j + h sum_i s_i (k_B = 1).
+// With the row energy E_row(s) = -J sum_i s_i s_i+1 + h sum_i s_i (the bond s_width-1 s_0 only for a periodic strip), the symmetric matrix
+//      T(s, s') = exp(-beta E_row(s) / 2) prod_i exp(beta J s_i s'_i) exp(-beta E_row(s') / 2)
+// gives, from its two largest eigenvalues lambda_0 > |lambda_1|:
+//      f = -T ln(lambda_0) / width,   xi = 1 / ln(lambda_0 / |lambda_1|) (along the strip),   <s> = sum_s psi_0(s)^2 M(s) / width.
+// T is never stored: the interlayer part is a product of 2x2 matrices acting on one spin each, so that T v costs O(width 2^width).
+use std::cmp::Ordering;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use periodic_array_2d_lib::BoundaryCondition;
+
+use crate::CalculationError;
+
+pub const MAX_STRIP_WIDTH: usize = 20;
+
+const SUBSPACE_SIZE: usize       = 4;     // vectors iterated together, the convergence rate being |lambda_4 / lambda_1|
+const MAX_ITERATIONS: usize      = 100000;
+const TOLERANCE: f64             = 1E-14;
+
+#[derive(Debug, Clone, Copy)]
+pub struct StripSolution
+{
+    pub free_energy: f64,           // per spin
+    pub magnetisation: f64,         // <s>, opposite to h (see the sign convention of the Hamiltonian)
+    pub correlation_length: f64,    // infinite if lambda_0 & lambda_1 are degenerate
+    pub log_eigenvalues: [f64; 2],  // ln(lambda_0) & ln|lambda_1|
+}
+
+impl StripSolution
+{
+    // One line per temperature: the columns -> infinity limit of rows x columns lattices with the same transverse boundaries
+    pub fn write_to_file(file_name: &Path, temperatures: &[f64], solutions: &[StripSolution]) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "temperature, free_energy, magnetisation, correlation_length")?;
+        for (temp, solution) in temperatures.iter().zip(solutions)
+        {
+            writeln!(&mut file, "{temp}, {}, {}, {}", solution.free_energy, solution.magnetisation, solution.correlation_length)?;
+        }
+        file.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransferMatrix
+{
+    width: usize,
+    periodic: bool,
+    interaction_term: f64,
+    extern_mag: f64,
+}
+
+impl TransferMatrix
+{
+    // Periodic or open transverse boundaries only
+    pub fn new(width: usize, boundary: BoundaryCondition, interaction_term: f64, extern_mag: f64) -> Result<Self, CalculationError>
+    {
+        if !(2..=MAX_STRIP_WIDTH).contains(&width)
+        {
+            return Err(CalculationError::IncompatibleGeometry(format!("Strip width {width} should be in [2, {MAX_STRIP_WIDTH}]")));
+        }
+        let periodic = match boundary
+        {
+            BoundaryCondition::Periodic => true,
+            BoundaryCondition::Open     => false,
+            _                           => return Err(CalculationError::IncompatibleGeometry(format!("{boundary:?} boundaries: only periodic or open strips"))),
+        };
+        Ok(Self { width, periodic, interaction_term, extern_mag })
+    }
+    #[inline(always)]
+    pub fn width(&self) -> usize
+    {
+        self.width
+    }
+    #[inline(always)]
+    fn spin(state: usize, i: usize) -> f64
+    {
+        if (state >> i) & 1 == 1 {1_f64} else {-1_f64}
+    }
+    fn row_energy(&self, state: usize) -> f64
+    {
+        let bonds = if self.periodic {self.width} else {self.width - 1};
+        let bond  = (0..bonds).map(|i| Self::spin(state, i) * Self::spin(state, (i + 1) % self.width)).sum::<f64>();
+        let spins = (0..self.width).map(|i| Self::spin(state, i)).sum::<f64>();
+        -self.interaction_term * bond + self.extern_mag * spins
+    }
+
+    pub fn solve(&self, temp: f64) -> Result<StripSolution, CalculationError>
+    {
+        if temp <= 0_f64
+        {
+            return Err(CalculationError::NegativeTempError);
+        }
+        let beta     = 1_f64 / temp;
+        let n_states = 1_usize << self.width;
+
+        // Factors scaled by their largest value to avoid overflows: ln(lambda) = ln(lambda_scaled) + width beta |J| - beta min(E_row)
+        let energies: Vec<f64> = (0..n_states).map(|state| self.row_energy(state)).collect();
+        let min_energy         = energies.iter().copied().fold(f64::INFINITY, f64::min);
+        let half_boltzmann     = energies.iter().map(|e| (-beta * (e - min_energy) / 2_f64).exp()).collect::<Vec<f64>>();
+        let same_spin          = (beta * (self.interaction_term - self.interaction_term.abs())).exp();
+        let opposite_spin      = (-beta * (self.interaction_term + self.interaction_term.abs())).exp();
+        let log_scale          = self.width as f64 * beta * self.interaction_term.abs() - beta * min_energy;
+
+        let apply = |vector: &[f64], result: &mut Vec<f64>|
+        {
+            result.clear();
+            result.extend(vector.iter().zip(&half_boltzmann).map(|(v, d)| v * d));
+            for i in 0..self.width
+            {
+                let bit = 1_usize << i;
+                for state in (0..n_states).filter(|state| state & bit == 0)
+                {
+                    let (up, down)            = (result[state], result[state | bit]);
+                    result[state]             = same_spin * up + opposite_spin * down;
+                    result[state | bit]       = opposite_spin * up + same_spin * down;
+                }
+            }
+            result.iter_mut().zip(&half_boltzmann).for_each(|(v, d)| *v *= d);
+        };
+
+        let (values, vectors) = leading_eigenpairs(n_states, apply);
+        let magnetisation     = vectors[0].iter().enumerate().map(|(state, psi)| psi * psi * (0..self.width).map(|i| Self::spin(state, i)).sum::<f64>()).sum::<f64>() / self.width as f64;
+        let log_eigenvalues   = [values[0].ln() + log_scale, values[1].abs().ln() + log_scale];
+        let gap               = log_eigenvalues[0] - log_eigenvalues[1];
+        Ok(StripSolution
+        {
+            free_energy:        -temp * log_eigenvalues[0] / self.width as f64,
+            magnetisation,
+            correlation_length: if gap > 0_f64 {1_f64 / gap} else {f64::INFINITY},
+            log_eigenvalues,
+        })
+    }
+}
+
+// Subspace iteration with Rayleigh-Ritz: the two eigenvalues of largest modulus (& their normalised eigenvectors) of the symmetric matrix A,
+// given through apply(v, A v). The first is the (positive) Perron eigenvalue.
+fn leading_eigenpairs(dimension: usize, apply: impl Fn(&[f64], &mut Vec<f64>)) -> ([f64; 2], [Vec<f64>; 2])
+{
+    // Start with an even & an odd vector under a global spin flip (the two leading sectors in zero field), plus two arbitrary ones
+    let mut basis: Vec<Vec<f64>> = (0..SUBSPACE_SIZE).map(|k| (0..dimension).map(|state| match k
+    {
+        0 => 1_f64,
+        1 => state.count_ones() as f64 - (dimension.trailing_zeros() as f64) / 2_f64,
+        _ => ((state.wrapping_mul(2654435761) >> (4 * k)) % 1024) as f64 / 1024_f64 - 0.5_f64,
+    }).collect()).collect();
+    orthonormalise(&mut basis);
+
+    let mut images: Vec<Vec<f64>> = (0..SUBSPACE_SIZE).map(|_| Vec::with_capacity(dimension)).collect();
+    let mut previous              = [f64::NAN; 2];
+    let mut ritz                  = ([0_f64; SUBSPACE_SIZE], [[0_f64; SUBSPACE_SIZE]; SUBSPACE_SIZE]);
+    for _ in 0..MAX_ITERATIONS
+    {
+        basis.iter().zip(images.iter_mut()).for_each(|(vector, image)| apply(vector, image));
+        let mut projected = [[0_f64; SUBSPACE_SIZE]; SUBSPACE_SIZE];
+        for (a, row) in projected.iter_mut().enumerate()
+        {
+            for (b, element) in row.iter_mut().enumerate()
+            {
+                *element = dot(&basis[a], &images[b]);
+            }
+        }
+        ritz = symmetric_eigen(projected);
+
+        // New basis: images rotated onto the Ritz vectors, ie A applied to the current Ritz vectors
+        basis = (0..SUBSPACE_SIZE).map(|k| (0..dimension).map(|n| (0..SUBSPACE_SIZE).map(|b| images[b][n] * ritz.1[b][k]).sum()).collect()).collect();
+        orthonormalise(&mut basis);
+
+        let converged = (0..2).all(|k| (ritz.0[k] - previous[k]).abs() <= TOLERANCE * ritz.0[0].abs());
+        previous      = [ritz.0[0], ritz.0[1]];
+        if converged
+        {
+            break;
+        }
+    }
+    // Ritz vectors of the last Rayleigh-Ritz step (the images are still those of the basis it was computed in)
+    let values  = [ritz.0[0], ritz.0[1]];
+    let vectors = [0, 1].map(|k|
+    {
+        let vector: Vec<f64> = (0..dimension).map(|n| (0..SUBSPACE_SIZE).map(|b| images[b][n] * ritz.1[b][k]).sum()).collect();
+        let norm             = dot(&vector, &vector).sqrt();
+        vector.into_iter().map(|v| v / norm).collect()
+    });
+    (values, vectors)
+}
+
+#[inline(always)]
+fn dot(a: &[f64], b: &[f64]) -> f64
+{
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// Modified Gram-Schmidt
+fn orthonormalise(basis: &mut [Vec<f64>])
+{
+    for k in 0..basis.len()
+    {
+        let (done, rest) = basis.split_at_mut(k);
+        let vector       = &mut rest[0];
+        for previous in done.iter()
+        {
+            let projection = dot(previous, vector);
+            vector.iter_mut().zip(previous).for_each(|(v, p)| *v -= projection * p);
+        }
+        let norm = dot(vector, vector).sqrt();
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+// Cyclic Jacobi rotations: eigenvalues sorted by decreasing modulus & eigenvectors as columns
+fn symmetric_eigen<const K: usize>(mut matrix: [[f64; K]; K]) -> ([f64; K], [[f64; K]; K])
+{
+    let mut vectors = [[0_f64; K]; K];
+    (0..K).for_each(|k| vectors[k][k] = 1_f64);
+    for _ in 0..100
+    {
+        let off_diagonal = (0..K).flat_map(|a| (0..K).filter(move |&b| b != a).map(move |b| (a, b))).map(|(a, b)| matrix[a][b].powi(2)).sum::<f64>();
+        let diagonal     = (0..K).map(|a| matrix[a][a].powi(2)).sum::<f64>();
+        if off_diagonal <= f64::EPSILON.powi(2) * diagonal
+        {
+            break;
+        }
+        for p in 0..K
+        {
+            for q in p + 1..K
+            {
+                if matrix[p][q] == 0_f64
+                {
+                    continue;
+                }
+                let theta  = (matrix[q][q] - matrix[p][p]) / (2_f64 * matrix[p][q]);
+                let t      = theta.signum() / (theta.abs() + (theta * theta + 1_f64).sqrt());
+                let (c, s) = (1_f64 / (t * t + 1_f64).sqrt(), t / (t * t + 1_f64).sqrt());
+                for row in matrix.iter_mut()
+                {
+                    let (kp, kq)   = (row[p], row[q]);
+                    row[p]         = c * kp - s * kq;
+                    row[q]         = s * kp + c * kq;
+                }
+                let (row_p, row_q) = (matrix[p], matrix[q]);
+                matrix[p]          = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+                matrix[q]          = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+                for row in vectors.iter_mut()
+                {
+                    let (vp, vq)   = (row[p], row[q]);
+                    row[p]         = c * vp - s * vq;
+                    row[q]         = s * vp + c * vq;
+                }
+            }
+        }
+    }
+    let mut order: [usize; K] = std::array::from_fn(|k| k);
+    order.sort_by(|&a, &b| matrix[b][b].abs().partial_cmp(&matrix[a][a].abs()).unwrap_or(Ordering::Equal));
+    (order.map(|k| matrix[k][k]), std::array::from_fn(|row| order.map(|k| vectors[row][k])))
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::exact_solution::{onsager_energy, torus_log_partition_function};
+
+    #[test]
+    fn test_strip_limits()
+    {
+        // Decoupled spins in a field: f = -T ln(2 cosh(beta h)), <s> = -tanh(beta h), no correlations between rows
+        let strip    = TransferMatrix::new(4, BoundaryCondition::Open, 0_f64, 0.7_f64).unwrap().solve(1.3_f64).unwrap();
+        assert!((strip.free_energy + 1.3_f64 * (2_f64 * (0.7_f64 / 1.3_f64).cosh()).ln()).abs() < 1E-12);
+        assert!((strip.magnetisation + (0.7_f64 / 1.3_f64).tanh()).abs() < 1E-12);
+        assert!(strip.correlation_length < 0.1, "xi = {}", strip.correlation_length);   // lambda_1 = 0 up to rounding errors
+
+        // <s> = df/dh
+        let (temp, h) = (2.5_f64, 0.2_f64);
+        let free      = |h: f64| TransferMatrix::new(6, BoundaryCondition::Periodic, 1_f64, h).unwrap().solve(temp).unwrap().free_energy;
+        let strip     = TransferMatrix::new(6, BoundaryCondition::Periodic, 1_f64, h).unwrap().solve(temp).unwrap();
+        assert!((strip.magnetisation - (free(h + 1E-5) - free(h - 1E-5)) / 2E-5).abs() < 1E-7, "magnetisation {}", strip.magnetisation);
+        assert!(strip.magnetisation < 0_f64);
+
+        assert!(TransferMatrix::new(4, BoundaryCondition::Helical, 1_f64, 0_f64).is_err());
+        assert!(TransferMatrix::new(MAX_STRIP_WIDTH + 1, BoundaryCondition::Open, 1_f64, 0_f64).is_err());
+    }
+
+    #[test]
+    fn test_strip_two_dimensional_limit()
+    {
+        // ln(lambda_0) = lim ln(Z) / rows of the rows x width torus
+        for width in [3, 6]
+        {
+            let strip = TransferMatrix::new(width, BoundaryCondition::Periodic, 1_f64, 0_f64).unwrap().solve(2.5_f64).unwrap();
+            assert!((strip.log_eigenvalues[0] - torus_log_partition_function(400, width, 2.5_f64, 1_f64) / 400_f64).abs() < 1E-10, "L={width}");
+        }
+        // Z = sum_k lambda_k^rows: once the other eigenvalues are negligible, ln(Z) - rows ln(lambda_0) = ln(1 + exp(-rows / xi))
+        for (width, temp) in [(3, 1.5_f64), (4, 2.5_f64), (6, 2.2_f64)]
+        {
+            let strip = TransferMatrix::new(width, BoundaryCondition::Periodic, 1_f64, 0_f64).unwrap().solve(temp).unwrap();
+            let rest  = torus_log_partition_function(32, width, temp, 1_f64) - 32_f64 * strip.log_eigenvalues[0];
+            let xi    = -32_f64 / rest.exp_m1().ln();
+            assert!((strip.correlation_length / xi - 1_f64).abs() < 1E-8, "L={width}, T={temp}: xi = {}, from the torus: {xi}", strip.correlation_length);
+        }
+        // Away from Tc, wide periodic strips converge exponentially to the infinite lattice: u = d(beta f)/d(beta)
+        let temp   = 4_f64;
+        let energy = |width: usize|
+        {
+            let beta_f = |beta: f64| TransferMatrix::new(width, BoundaryCondition::Periodic, 1_f64, 0_f64).unwrap().solve(1_f64 / beta).unwrap().free_energy * beta;
+            (beta_f(1_f64 / temp + 1E-5) - beta_f(1_f64 / temp - 1E-5)) / 2E-5 - onsager_energy(temp, 1_f64)
+        };
+        let (narrow, wide) = (energy(8), energy(12));
+        assert!(wide.abs() < 5E-5 && wide.abs() < narrow.abs() / 10_f64, "u - u_Onsager: {narrow} (L=8), {wide} (L=12)");
+
+        // At Tc conformal invariance gives xi = L / (2 pi x_sigma) = 4 L / pi on periodic strips
+        let t_c = 2_f64 / (1_f64 + 2_f64.sqrt()).ln();
+        for width in [8, 12]
+        {
+            let xi = TransferMatrix::new(width, BoundaryCondition::Periodic, 1_f64, 0_f64).unwrap().solve(t_c).unwrap().correlation_length;
+            assert!((xi * std::f64::consts::FRAC_PI_4 / width as f64 - 1_f64).abs() < 0.01, "L={width}: xi = {xi}");
+        }
+        // Ordered phase: lambda_1 -> lambda_0, the correlation length grows exponentially with the width
+        let narrow = TransferMatrix::new(4, BoundaryCondition::Periodic, 1_f64, 0_f64).unwrap().solve(1.5_f64).unwrap().correlation_length;
+        let wide   = TransferMatrix::new(8, BoundaryCondition::Periodic, 1_f64, 0_f64).unwrap().solve(1.5_f64).unwrap().correlation_length;
+        assert!(wide > 10_f64 * narrow, "xi(4) = {narrow}, xi(8) = {wide}");
+    }
+}
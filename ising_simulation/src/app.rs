@@ -16,6 +16,8 @@ use spin_img::SpinImage;
 
 use real_time_data_handler::RealTimeDataHandler;
 use monte_carlo_lib::{ising_state, metropolis, SpinEnergyFluctuation};
+use monte_carlo_lib::hamiltonian::{Hamiltonian, IsingHamiltonian};
+use monte_carlo_lib::clusters::{ClusterFinder, ClusterKind, ClusterStatistics};
 use periodic_array_2d_lib::{PeriodicArray2D};
 
  
@@ -31,9 +33,11 @@ const INTERATION_TERM: f32 = 1_f32;
 const COLUMNS: i32  = 512;  
 const ROWS:    i32  = 512;  
 
-pub struct IsingSimulation
+// Generic over the model, the sliders only control the temperature & the external field
+pub struct IsingSimulation<H = IsingHamiltonian<f32>> where H: Hamiltonian<i8,f32>
 {
     spin_img: SpinImage,
+    hamiltonian: H,
     data_handler: RealTimeDataHandler,
     spin_2d: PeriodicArray2D<i8,f32>,
     my_rng: Xoshiro256p,
@@ -41,6 +45,9 @@ pub struct IsingSimulation
     magnetization: f32,
     temperature: f32,
     extern_mag: f32,
+    cluster_overlay: Option<ClusterKind>,
+    cluster_finder: ClusterFinder,
+    cluster_statistics: ClusterStatistics,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -58,6 +65,14 @@ fn new_rng() -> Xoshiro256p
 impl IsingSimulation 
 {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self
+    {
+        Self::with_hamiltonian(cc, IsingHamiltonian::new(INTERATION_TERM, 0_f32))
+    }
+}
+
+impl<H> IsingSimulation<H> where H: Hamiltonian<i8,f32>
+{
+    pub fn with_hamiltonian(cc: &eframe::CreationContext<'_>, hamiltonian: H) -> Self
     {
         cc.egui_ctx.set_theme(egui::Theme::Dark);
 
@@ -71,28 +86,33 @@ impl IsingSimulation
         let n_points      = 40_usize;  
         let n_frame_avg   = 25_usize;    
         let data_handler  = RealTimeDataHandler::new(n_points, n_frame_avg); 
+        let cluster_finder = ClusterFinder::new(spin_2d.total_number() as usize);
 
         Self 
         {
             spin_img,
+            hamiltonian,
             data_handler,
             spin_2d,
             my_rng,
             spin_total,
             magnetization,
             temperature: 2_f32,
-            extern_mag: 0_f32
+            extern_mag: 0_f32,
+            cluster_overlay: None,
+            cluster_finder,
+            cluster_statistics: ClusterStatistics::default(),
         }
     }
 
 
     fn update_physics(&mut self)
     {
+        self.hamiltonian.set_extern_mag(-self.extern_mag); // minus sign for visualization!!!
         let SpinEnergyFluctuation(delta_spin,_) = metropolis::perform_metropolis_sweep(&mut self.spin_2d,
                                                                                        &mut self.my_rng,
                                                                                        self.temperature,
-                                                                                       INTERATION_TERM,
-                                                                                       -self.extern_mag); // minus sign for visualization!!!
+                                                                                       &self.hamiltonian);
         self.spin_total   += delta_spin;
         self.magnetization = self.spin_total / self.spin_2d.total_number() as f32;
     }
@@ -102,7 +122,15 @@ impl IsingSimulation
     }
     fn update_spin_image(&mut self)
     {
-        self.spin_img.update_image(&self.spin_2d);
+        match self.cluster_overlay
+        {
+            None       => self.spin_img.update_image(&self.spin_2d),
+            Some(kind) =>
+            {
+                self.cluster_statistics = self.cluster_finder.label_clusters(kind, &self.spin_2d, &mut self.my_rng, self.temperature, &self.hamiltonian);
+                self.spin_img.update_cluster_image(self.cluster_finder.labels());
+            },
+        }
     }
     fn draw_and_handle_control_panel_ui(&mut self, ctx: &egui::Context)  
     {
@@ -155,6 +183,20 @@ impl IsingSimulation
                 })
             });
             
+            ui.separator();
+            ui.heading("Clusters");
+            ui.horizontal(|ui: &mut egui::Ui|
+            {
+                ui.radio_value(&mut self.cluster_overlay, None, "Spins");
+                ui.radio_value(&mut self.cluster_overlay, Some(ClusterKind::LikeSpin), "Like-spin");
+                ui.radio_value(&mut self.cluster_overlay, Some(ClusterKind::FortuinKasteleyn), "Fortuin-Kasteleyn");
+            });
+            if self.cluster_overlay.is_some()
+            {
+                let wraps = if self.cluster_statistics.wraps() {"wraps"} else {"doesn't wrap"};
+                ui.label(format!("Largest cluster: {:.2} of the spins, {wraps}", self.cluster_statistics.largest_fraction()));
+            }
+
             ui.separator();
             ui.heading(format!("Magnetization: {:.2}", self.magnetization));
             ui.separator();
@@ -172,7 +214,7 @@ impl IsingSimulation
     }
 }
 
-impl eframe::App for IsingSimulation 
+impl<H> eframe::App for IsingSimulation<H> where H: Hamiltonian<i8,f32>
 {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame)
     {
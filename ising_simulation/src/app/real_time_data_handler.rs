@@ -48,7 +48,7 @@ impl RealTimeDataHandler
         if self.counter > 0
         {
             self.data_cumul += data;
-            if self.counter%self.n_avg == 0
+            if self.counter.is_multiple_of(self.n_avg)
             {
                 let data_avg = self.data_cumul / self.n_avg as f32;
                 self.data_deque.pop_and_push(data_avg);
@@ -62,7 +62,7 @@ impl RealTimeDataHandler
     {
         let my_series: PlotPoints<'_> = (0..self.data_deque.len()).map(|i|
         {
-            let t = i as f64 * -1f64;
+            let t = -(i as f64);
             [t, self.data_deque.at(i) as f64]
         }).collect();
         
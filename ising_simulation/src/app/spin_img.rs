@@ -1,5 +1,14 @@
 const MY_LIGHT_BLUE: egui::Color32 = egui::Color32::from_rgb(120, 185, 181);
 const MY_DARK_BLUE: egui::Color32  = egui::Color32::from_rgb(50, 10, 107);
+const LARGEST_CLUSTER: egui::Color32 = egui::Color32::from_rgb(230, 75, 55);
+const CLUSTER_PALETTE: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(240, 190, 60),
+    egui::Color32::from_rgb(90, 170, 90),
+    egui::Color32::from_rgb(70, 130, 210),
+    egui::Color32::from_rgb(170, 100, 200),
+    egui::Color32::from_rgb(60, 190, 190),
+    egui::Color32::from_rgb(220, 130, 170),
+];
 
 use monte_carlo_lib::ising_state;
 use periodic_array_2d_lib::PeriodicArray2D;
@@ -27,7 +36,7 @@ impl SpinImage
         );
         
         let mut spin_img = Self {pixel_buffer, texture_handle, img_size};
-        spin_img.update_image(&spin_2d);
+        spin_img.update_image(spin_2d);
         spin_img
     }
     pub fn update_image(&mut self, spin_2d: &PeriodicArray2D<i8,f32>)
@@ -46,7 +55,23 @@ impl SpinImage
                 };
             }
         }
-        
+        self.upload();
+    }
+    // Cluster overlay: labels from monte_carlo_lib::clusters::ClusterFinder, the largest cluster (label 0) in red, the others cycling through the palette
+    pub fn update_cluster_image(&mut self, labels: &[usize])
+    {
+        for (pixel, &label) in self.pixel_buffer.iter_mut().zip(labels)
+        {
+            *pixel = match label
+            {
+                0     => LARGEST_CLUSTER,
+                label => CLUSTER_PALETTE[label % CLUSTER_PALETTE.len()],
+            };
+        }
+        self.upload();
+    }
+    fn upload(&mut self)
+    {
         self.texture_handle.set(
             egui::ColorImage
             {
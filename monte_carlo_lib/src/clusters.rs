@@ -0,0 +1,311 @@
+// Cluster labelling of the spins of a 2D lattice (Hoshen-Kopelman: the sites are visited in raster order & the labels of
+// bonded sites are merged with a union-find, the equivalence classes being the clusters):
+// * like-spin (geometric) clusters: neighbours with the same spin,
+// * Fortuin-Kasteleyn clusters: satisfied bonds kept with probability 1 - exp(-2 beta |J_ij|), the clusters of the Swendsen-Wang update.
+// A cluster wraps around the lattice along x (y) when it contains a closed path with a non-zero winding along the columns (rows):
+// each site keeps its displacement relative to the root of its cluster, so that a bond between two sites of the same cluster
+// whose displacements don't match closes such a path. Only the bonds of the lattice are used (nearest neighbours, even for long-range couplings).
+// The displacements are taken from the rows & columns of a 2D array, which must not be 2 sites wide (the two bonds between the same pair
+// of sites would have the same minimum image displacement): other lattices (3D arrays, graphs) only get their clusters counted.
+use super::*;
+use periodic_array_2d_lib::PeriodicArray2D;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterKind
+{
+    LikeSpin,
+    FortuinKasteleyn,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Cluster
+{
+    pub size: usize,
+    pub wraps_x: bool,
+    pub wraps_y: bool,
+}
+
+// The clusters of one configuration, by decreasing size
+#[derive(Debug, Default, Clone)]
+pub struct ClusterStatistics
+{
+    pub clusters: Vec<Cluster>,
+    pub number_of_spins: usize,
+}
+
+impl ClusterStatistics
+{
+    pub fn largest_fraction(&self) -> f64
+    {
+        self.clusters.first().map_or(0_f64, |c| c.size as f64 / self.number_of_spins as f64)
+    }
+    // Some cluster wraps around the lattice in at least one direction
+    pub fn wraps(&self) -> bool
+    {
+        self.clusters.iter().any(|c| c.wraps_x || c.wraps_y)
+    }
+    // (size s, number of clusters of size s) by increasing size
+    pub fn size_distribution(&self) -> Vec<(usize, usize)>
+    {
+        let mut distribution: Vec<(usize, usize)> = Vec::new();
+        for cluster in self.clusters.iter().rev()
+        {
+            match distribution.last_mut()
+            {
+                Some((size, count)) if *size == cluster.size => *count += 1,
+                _                                           => distribution.push((cluster.size, 1)),
+            }
+        }
+        distribution
+    }
+}
+
+// Union-find buffers, kept between labellings
+pub struct ClusterFinder
+{
+    parent: Vec<usize>,
+    offset: Vec<[i32; 2]>,      // (y, x) displacement of the site relative to its parent
+    size: Vec<usize>,           // of the clusters, on the roots
+    wraps: Vec<[bool; 2]>,      // (y, x), on the roots
+    labels: Vec<usize>,
+}
+
+impl ClusterFinder
+{
+    pub fn new(number_of_spins: usize) -> Self
+    {
+        Self
+        {
+            parent: (0..number_of_spins).collect(),
+            offset: vec![[0; 2]; number_of_spins],
+            size:   vec![1; number_of_spins],
+            wraps:  vec![[false; 2]; number_of_spins],
+            labels: vec![0; number_of_spins],
+        }
+    }
+
+    // Cluster of every site after the last labelling: 0 for the largest cluster, 1 for the second largest...
+    pub fn labels(&self) -> &[usize]
+    {
+        &self.labels
+    }
+
+    // The wrapping can be followed along axes of 1 site or at least 3
+    pub fn can_label<S, P>(spins: &PeriodicArray2D<S,P>) -> bool
+        where S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        spins.rows() != 2 && spins.columns() != 2
+    }
+
+    pub fn label_like_spin_clusters<S, P>(&mut self, spins: &PeriodicArray2D<S,P>) -> ClusterStatistics
+        where S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        self.label(spins, Some((spins.rows(), spins.columns())), |n, m, _| spins.at_index(n) == spins.at_index(m))
+    }
+
+    pub fn label_fortuin_kasteleyn_clusters<R, H, S, P>(&mut self, spins: &PeriodicArray2D<S,P>, rng: &mut R, temp: P, hamiltonian: &H) -> ClusterStatistics
+        where R: MonteCarloRngInterface<P>,
+              H: Hamiltonian<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        let two  = P::from(2.).unwrap();
+        let beta = metropolis::get_beta(temp);
+        self.label(spins, Some((spins.rows(), spins.columns())), |n, m, weight|
+        {
+            let coupling  = hamiltonian.bond_coupling(n, m, weight);
+            let satisfied = (coupling * spins.at_index(n).as_() * spins.at_index(m).as_()).is_sign_positive() && !coupling.is_zero();
+            satisfied && rng.generate_rand_float(P::zero(), P::one()) < P::one() - (-two * beta * coupling.abs()).exp()
+        })
+    }
+
+    // Number of like-spin clusters of any lattice, without their wrapping
+    pub fn count_like_spin_clusters<L, S, P>(&mut self, spins: &L) -> usize
+        where L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        self.label(spins, None, |n, m, _| spins.at_index(n) == spins.at_index(m)).clusters.len()
+    }
+
+    pub fn label_clusters<R, H, S, P>(&mut self, kind: ClusterKind, spins: &PeriodicArray2D<S,P>, rng: &mut R, temp: P, hamiltonian: &H) -> ClusterStatistics
+        where R: MonteCarloRngInterface<P>,
+              H: Hamiltonian<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        match kind
+        {
+            ClusterKind::LikeSpin         => self.label_like_spin_clusters(spins),
+            ClusterKind::FortuinKasteleyn => self.label_fortuin_kasteleyn_clusters(spins, rng, temp, hamiltonian),
+        }
+    }
+
+    // Root of n & displacement of n relative to it, with path compression
+    fn find(&mut self, n: usize) -> (usize, [i32; 2])
+    {
+        let parent = self.parent[n];
+        if parent == n
+        {
+            return (n, [0; 2]);
+        }
+        let (root, parent_offset) = self.find(parent);
+        self.parent[n]  = root;
+        self.offset[n]  = [self.offset[n][0] + parent_offset[0], self.offset[n][1] + parent_offset[1]];
+        (root, self.offset[n])
+    }
+
+    // Every bond n-m (m > n, each bond once) for which bonded(n, m, weight) is true joins two clusters.
+    // The wrapping is followed on the (rows, columns) array `shape` only.
+    fn label<L, S, P>(&mut self, spins: &L, shape: Option<(i32, i32)>, mut bonded: impl FnMut(usize, usize, P) -> bool) -> ClusterStatistics
+        where L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        let number_of_spins = spins.total_number() as usize;
+        assert_eq!(self.parent.len(), number_of_spins, "ClusterFinder of the wrong size");
+        assert!(shape.is_none_or(|(rows, columns)| rows != 2 && columns != 2), "Can't follow the wrapping along an axis of 2 sites");
+        self.parent.iter_mut().enumerate().for_each(|(n, p)| *p = n);
+        self.offset.fill([0; 2]);
+        self.size.fill(1);
+        self.wraps.fill([false; 2]);
+
+        // Minimum image displacement between neighbours, none without a shape
+        let fold         = |d: i32, len: i32| if 2 * d > len {d - len} else if 2 * d <= -len {d + len} else {d};
+        let displacement = |n: usize, m: usize| shape.map_or([0; 2], |(rows, columns)|
+        {
+            let (y_n, x_n, y_m, x_m) = (n as i32 / columns, n as i32 % columns, m as i32 / columns, m as i32 % columns);
+            [fold(y_m - y_n, rows), fold(x_m - x_n, columns)]
+        });
+        let mut bonds = Vec::new();
+        for n in 0..number_of_spins
+        {
+            spins.for_each_weighted_neighbour_index(n, |m, weight| if m > n && bonded(n, m, weight) {bonds.push(m)});
+            for m in bonds.drain(..)
+            {
+                let displacement = displacement(n, m);
+                let (root_n, offset_n) = self.find(n);
+                let (root_m, offset_m) = self.find(m);
+                let path = [offset_n[0] + displacement[0] - offset_m[0], offset_n[1] + displacement[1] - offset_m[1]];
+                if root_n == root_m
+                {
+                    self.wraps[root_n] = [self.wraps[root_n][0] || path[0] != 0, self.wraps[root_n][1] || path[1] != 0];
+                    continue;
+                }
+                // Union by size: the position of the smaller root relative to the larger one is +-path
+                let (root, child, child_offset) = if self.size[root_n] >= self.size[root_m] {(root_n, root_m, path)} else {(root_m, root_n, [-path[0], -path[1]])};
+                self.parent[child]  = root;
+                self.offset[child]  = child_offset;
+                self.size[root]    += self.size[child];
+                self.wraps[root]    = [self.wraps[root][0] || self.wraps[child][0], self.wraps[root][1] || self.wraps[child][1]];
+            }
+        }
+
+        let mut roots: Vec<usize> = (0..number_of_spins).filter(|&n| self.parent[n] == n).collect();
+        roots.sort_by_key(|&root| std::cmp::Reverse(self.size[root]));
+        let mut rank = vec![0; number_of_spins];
+        roots.iter().enumerate().for_each(|(i, &root)| rank[root] = i);
+        for n in 0..number_of_spins
+        {
+            self.labels[n] = rank[self.find(n).0];
+        }
+        ClusterStatistics
+        {
+            clusters: roots.iter().map(|&root| Cluster { size: self.size[root], wraps_y: self.wraps[root][0], wraps_x: self.wraps[root][1] }).collect(),
+            number_of_spins,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use hamiltonian::IsingHamiltonian;
+    use test_utils::{TestRng, lattice_from_fn};
+    use periodic_array_2d_lib::{PeriodicArray2D, PeriodicArray3D, LatticeGeometry, BoundaryCondition};
+
+    #[test]
+    fn test_cluster_labelling()
+    {
+        let (rows, columns) = (6, 6);
+        let lattice         = |spin: fn(i32, i32) -> i8, boundary| lattice_from_fn(rows, columns, LatticeGeometry::Square, boundary, spin);
+        let mut finder      = ClusterFinder::new((rows * columns) as usize);
+        let cluster         = |size, wraps_x, wraps_y| Cluster { size, wraps_x, wraps_y };
+
+        let uniform = finder.label_like_spin_clusters(&lattice(|_, _| 1, BoundaryCondition::Periodic));
+        assert_eq!(uniform.clusters, [cluster(36, true, true)]);
+        assert_eq!(uniform.largest_fraction(), 1_f64);
+        let open    = finder.label_like_spin_clusters(&lattice(|_, _| 1, BoundaryCondition::Open));
+        assert_eq!(open.clusters, [cluster(36, false, false)]);
+
+        // Two bands of 3 rows: both wrap along x only
+        let bands = finder.label_like_spin_clusters(&lattice(|y, _| if y < 3 {1} else {-1}, BoundaryCondition::Periodic));
+        assert_eq!(bands.clusters, [cluster(18, true, false), cluster(18, true, false)]);
+        assert_eq!(bands.size_distribution(), [(18, 2)]);
+        // A diagonal staircase of up spins wraps along both axes without any straight path, the down spins around it too
+        let staircase = finder.label_like_spin_clusters(&lattice(|y, x| if x == y || x == (y + 1) % 6 {1} else {-1}, BoundaryCondition::Periodic));
+        assert_eq!(staircase.clusters, [cluster(24, true, true), cluster(12, true, true)]);
+        // Checkerboard: only single spins, labelled by decreasing size
+        let checkerboard = finder.label_like_spin_clusters(&lattice(|y, x| if (x + y) % 2 == 0 {1} else {-1}, BoundaryCondition::Periodic));
+        assert_eq!(checkerboard.size_distribution(), [(1, 36)]);
+        assert!(!checkerboard.wraps());
+        // A 2x2 island
+        let island = finder.label_like_spin_clusters(&lattice(|y, x| if (1..3).contains(&y) && (2..4).contains(&x) {1} else {-1}, BoundaryCondition::Periodic));
+        assert_eq!(island.clusters, [cluster(32, true, true), cluster(4, false, false)]);
+        assert!((0..36).all(|n| finder.labels()[n] == if [8, 9, 14, 15].contains(&n) {1} else {0}));
+
+        // Fortuin-Kasteleyn: every satisfied bond at T -> 0, none at T -> infinity
+        let mut rng     = TestRng { state: 3 };
+        let hamiltonian = IsingHamiltonian::new(1_f64, 0_f64);
+        let spins       = lattice(|y, _| if y < 3 {1} else {-1}, BoundaryCondition::Periodic);
+        assert_eq!(finder.label_fortuin_kasteleyn_clusters(&spins, &mut rng, 1E-3_f64, &hamiltonian).clusters, bands.clusters);
+        assert_eq!(finder.label_fortuin_kasteleyn_clusters(&spins, &mut rng, 1E6_f64, &hamiltonian).size_distribution(), [(1, 36)]);
+
+        // A 2-site axis has two bonds between the same sites, a chain none along y
+        assert!(!ClusterFinder::can_label(&lattice_from_fn(2, 18, LatticeGeometry::Square, BoundaryCondition::Periodic, |_, _| 1)));
+        let chain = lattice_from_fn(1, 36, LatticeGeometry::Square, BoundaryCondition::Periodic, |_, x| if x < 30 {1} else {-1});
+        assert!(ClusterFinder::can_label(&chain));
+        assert_eq!(finder.label_like_spin_clusters(&chain).clusters, [cluster(30, false, false), cluster(6, false, false)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "axis of 2 sites")]
+    fn test_two_site_axes_are_rejected()
+    {
+        let spins = lattice_from_fn(6, 2, LatticeGeometry::Square, BoundaryCondition::Periodic, |_, _| 1);
+        ClusterFinder::new(12).label_like_spin_clusters(&spins);
+    }
+
+    #[test]
+    fn test_clusters_are_counted_on_any_lattice()
+    {
+        // Up layer 0 & down layers 1, 2: two domains, counted without any shape
+        let mut cube = PeriodicArray3D::<i8,f64>::new_with(3, 3, 3, ising_state::spin_up).unwrap();
+        (9..27).for_each(|n| *cube.at_index_mut(n) = -1);
+        assert_eq!(ClusterFinder::new(27).count_like_spin_clusters(&cube), 2);
+    }
+
+    #[test]
+    fn test_fortuin_kasteleyn_clusters_give_the_susceptibility()
+    {
+        // <M^2> = <sum_C |C|^2>: the spins of different FK clusters are uncorrelated
+        let (temp, sweeps) = (2.5_f64, 4000);
+        let hamiltonian    = IsingHamiltonian::new(1_f64, 0_f64);
+        let mut rng        = TestRng { state: 11 };
+        let mut spins      = PeriodicArray2D::<i8,f64>::new_with(8, 8, ising_state::spin_up).unwrap();
+        let mut cluster    = wolff::WolffCluster::new(64);
+        let mut finder     = ClusterFinder::new(64);
+        let (mut spin_sqr, mut cluster_sqr) = (0_f64, 0_f64);
+        for _ in 0..sweeps
+        {
+            cluster.perform_wolff_sweep(&mut spins, &mut rng, temp, &hamiltonian);
+            spin_sqr    += spins.sum_observable().powi(2) / sweeps as f64;
+            cluster_sqr += finder.label_fortuin_kasteleyn_clusters(&spins, &mut rng, temp, &hamiltonian).clusters.iter().map(|c| (c.size * c.size) as f64).sum::<f64>() / sweeps as f64;
+        }
+        assert!((spin_sqr - cluster_sqr).abs() < 0.05 * spin_sqr, "<M^2> = {spin_sqr}, <sum |C|^2> = {cluster_sqr}");
+    }
+}
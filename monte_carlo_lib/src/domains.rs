@@ -0,0 +1,128 @@
+// Geometry of the spin domains of one configuration, the quantities followed during the coarsening after a quench:
+// * domain-wall length: number of unsatisfied bonds (sign_ij s_i s_j < 0) inside the lattice, the bonds to frozen boundary spins excluded,
+// * number of domains: like-spin clusters (see clusters::ClusterFinder), up & down ones,
+// * Euler characteristic of the spin-up region: V - E + F of the complex made of the up sites, the bonds between them and the
+//   elementary faces (plaquettes, triangles or hexagons) with only up corners. As long as nothing wraps around the lattice,
+//   it is the number of up domains minus the number of holes in them.
+use super::*;
+use periodic_array_2d_lib::{PeriodicArray2D, LatticeGeometry, NeighbourSite};
+use clusters::ClusterFinder;
+
+// Corners of the faces "owned" by the site (i, j), as offsets from it: each face of the lattice appears exactly once when looping over all sites
+const SQUARE_FACES: [&[(i32, i32)]; 1]     = [&[(0, 0), (0, 1), (1, 0), (1, 1)]];
+const TRIANGULAR_FACES: [&[(i32, i32)]; 2] = [&[(0, 0), (0, 1), (1, 0)], &[(0, 1), (1, 0), (1, 1)]];
+const HONEYCOMB_FACES: [&[(i32, i32)]; 1]  = [&[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]];  // bricks, from the sites with a bond to the row below
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DomainGeometry
+{
+    pub domain_wall_length: usize,
+    pub number_of_domains: usize,
+    pub euler_characteristic: Option<i64>,  // only for the 2D arrays
+}
+
+impl DomainGeometry
+{
+    pub fn measure<L, S, P>(spins: &L, finder: &mut ClusterFinder) -> Self
+        where L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        Self
+        {
+            domain_wall_length:   domain_wall_length(spins),
+            number_of_domains:    finder.count_like_spin_clusters(spins),
+            euler_characteristic: None,
+        }
+    }
+    pub fn measure_2d<S, P>(spins: &PeriodicArray2D<S,P>, finder: &mut ClusterFinder) -> Self
+        where S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        Self { euler_characteristic: Some(euler_characteristic(spins)), ..Self::measure(spins, finder) }
+    }
+}
+
+pub fn domain_wall_length<L, S, P>(spins: &L) -> usize
+    where L: SpinLattice<S,P>,
+          S: SpinValue<P>,
+          P: PhysicalObservable,
+{
+    let topology = spins.topology();
+    (0..spins.total_number() as usize).map(|n|
+    {
+        let spin = spins.at_index(n);
+        topology.forward_neighbours(n).iter().filter(|bond| (bond.sign * spin * spins.at_index(bond.site as usize)).as_() < P::zero()).count()
+    }).sum()
+}
+
+pub fn euler_characteristic<S, P>(spins: &PeriodicArray2D<S,P>) -> i64
+    where S: SpinValue<P>,
+          P: PhysicalObservable,
+{
+    let is_up    = |n: usize| spins.at_index(n).as_() > P::zero();
+    let topology = spins.topology();
+    let (mut vertices, mut edges, mut faces) = (0_i64, 0_i64, 0_i64);
+    for i in spins.rows_range()
+    {
+        for j in spins.columns_range()
+        {
+            let n = (i * spins.columns() + j) as usize;
+            if is_up(n)
+            {
+                vertices += 1;
+                edges    += topology.forward_neighbours(n).iter().filter(|bond| is_up(bond.site as usize)).count() as i64;
+            }
+            let owned_faces: &[&[(i32, i32)]] = match spins.geometry()
+            {
+                LatticeGeometry::Square                         => &SQUARE_FACES,
+                LatticeGeometry::Triangular                     => &TRIANGULAR_FACES,
+                LatticeGeometry::Honeycomb if (i + j) % 2 == 0  => &HONEYCOMB_FACES,
+                LatticeGeometry::Honeycomb                      => &[],
+            };
+            // A face crossing an open or fixed boundary isn't part of the lattice
+            faces += owned_faces.iter().filter(|corners| corners.iter().all(|&(di, dj)| matches!(spins.resolve_neighbour(i + di, j + dj), NeighbourSite::Site(m, _) if is_up(m)))).count() as i64;
+        }
+    }
+    vertices - edges + faces
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use test_utils::lattice_from_fn;
+    use periodic_array_2d_lib::{PeriodicArray3D, BoundaryCondition};
+
+    #[test]
+    fn test_domain_geometry()
+    {
+        let (rows, columns) = (6, 6);
+        let mut finder      = ClusterFinder::new((rows * columns) as usize);
+        let geometry        = |walls, number_of_domains, euler| DomainGeometry { domain_wall_length: walls, number_of_domains, euler_characteristic: Some(euler) };
+        let square          = LatticeGeometry::Square;
+        let measure         = |finder: &mut ClusterFinder, spin: fn(i32, i32) -> i8, geometry, boundary| DomainGeometry::measure_2d(&lattice_from_fn(rows, columns, geometry, boundary, spin), finder);
+
+        // The uniform torus has the Euler characteristic of the torus, the open square the one of a disc
+        assert_eq!(measure(&mut finder, |_, _| 1, square, BoundaryCondition::Periodic), geometry(0, 1, 0));
+        assert_eq!(measure(&mut finder, |_, _| 1, square, BoundaryCondition::Open), geometry(0, 1, 1));
+        // A 2x2 island, a ring around one down spin, two islands touching by a corner & a band wrapping along x
+        assert_eq!(measure(&mut finder, |y, x| if (1..3).contains(&y) && (2..4).contains(&x) {1} else {-1}, square, BoundaryCondition::Periodic), geometry(8, 2, 1));
+        assert_eq!(measure(&mut finder, |y, x| if (1..4).contains(&y) && (1..4).contains(&x) && (y, x) != (2, 2) {1} else {-1}, square, BoundaryCondition::Periodic), geometry(16, 3, 0));
+        assert_eq!(measure(&mut finder, |y, x| if (y, x) == (1, 1) || (y, x) == (2, 2) {1} else {-1}, square, BoundaryCondition::Periodic), geometry(8, 3, 2));
+        assert_eq!(measure(&mut finder, |y, _| if y < 3 {1} else {-1}, square, BoundaryCondition::Periodic), geometry(12, 2, 0));
+
+        for other in [LatticeGeometry::Triangular, LatticeGeometry::Honeycomb]
+        {
+            assert_eq!(measure(&mut finder, |_, _| 1, other, BoundaryCondition::Periodic).euler_characteristic, Some(0));
+            assert_eq!(measure(&mut finder, |_, _| 1, other, BoundaryCondition::Open).euler_characteristic, Some(1));
+            let single = measure(&mut finder, |y, x| if (y, x) == (2, 2) {1} else {-1}, other, BoundaryCondition::Periodic);
+            assert_eq!(single, geometry(other.coordination_number(), 2, 1));
+        }
+        // Only the bonds inside the lattice count, the Euler characteristic needs the 2D array
+        let mut cube = PeriodicArray3D::<i8,f64>::new_with(3, 3, 3, ising_state::spin_up).unwrap();
+        *cube.at_index_mut(0) = -1;
+        assert_eq!(DomainGeometry::measure(&cube, &mut ClusterFinder::new(27)), DomainGeometry { domain_wall_length: 6, number_of_domains: 2, euler_characteristic: None });
+    }
+}
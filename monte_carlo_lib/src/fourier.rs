@@ -0,0 +1,205 @@
+// Fast Fourier transforms of any length: used by the long-range Hamiltonian (energies as convolutions) & by the structure factor of ising_calculation
+use num_complex::Complex;
+use periodic_array_2d_lib::PhysicalObservable;
+use std::f64::consts::PI;
+use std::iter::zip;
+
+// In-place discrete Fourier transform X_k = sum_n x_n exp(-2 pi i k n / N) of any length N: iterative radix-2 Cooley-Tukey for powers of 2,
+// Bluestein's chirp-z algorithm (a convolution of length 2^m >= 2N - 1, done with radix-2 transforms) otherwise. O(N log N) in both cases.
+#[derive(Debug, Clone)]
+pub struct Fft<P>
+{
+    len: usize,
+    algorithm: FftAlgorithm<P>,
+}
+
+#[derive(Debug, Clone)]
+enum FftAlgorithm<P>
+{
+    Radix2 { twiddles: Vec<Complex<P>> },                                           // exp(-2 pi i k / N), k < N/2
+    Bluestein { chirp: Vec<Complex<P>>, chirp_fft: Vec<Complex<P>>, inner: Box<Fft<P>> }, // exp(-i pi k^2 / N) & the transform of its padded conjugate
+}
+
+impl<P> Fft<P> where P: PhysicalObservable
+{
+    pub fn new(len: usize) -> Self
+    {
+        assert!(len > 0, "Empty Fourier transform");
+        if len.is_power_of_two()
+        {
+            let twiddles = (0..len / 2).map(|k| Complex::from_polar(P::one(), P::from(-2_f64 * PI * k as f64 / len as f64).unwrap())).collect();
+            return Self { len, algorithm: FftAlgorithm::Radix2 { twiddles } };
+        }
+        let padded_len        = (2 * len - 1).next_power_of_two();
+        let inner             = Box::new(Fft::new(padded_len));
+        let chirp: Vec<Complex<P>> = (0..len).map(|k| Complex::from_polar(P::one(), P::from(-PI * ((k * k) % (2 * len)) as f64 / len as f64).unwrap())).collect();
+        let mut chirp_fft     = vec![Complex::<P>::default(); padded_len];
+        chirp_fft[0]          = chirp[0].conj();
+        for k in 1..len
+        {
+            chirp_fft[k]              = chirp[k].conj();
+            chirp_fft[padded_len - k] = chirp[k].conj();
+        }
+        inner.transform(&mut chirp_fft);
+        Self { len, algorithm: FftAlgorithm::Bluestein { chirp, chirp_fft, inner } }
+    }
+
+    pub fn transform(&self, data: &mut [Complex<P>])
+    {
+        assert_eq!(data.len(), self.len, "Fourier transform of the wrong length");
+        match &self.algorithm
+        {
+            FftAlgorithm::Radix2 { twiddles } => Self::radix2(data, twiddles),
+            FftAlgorithm::Bluestein { chirp, chirp_fft, inner } =>
+            {
+                // X_k = w_k sum_n (x_n w_n) conj(w_(k-n)): convolution theorem, the inverse transform being conj(FFT(conj(.))) / M
+                let padded_len  = chirp_fft.len();
+                let mut buffer  = vec![Complex::<P>::default(); padded_len];
+                zip(&mut buffer, zip(data.iter(), chirp)).for_each(|(b, (&x, &w))| *b = x * w);
+                inner.transform(&mut buffer);
+                zip(&mut buffer, chirp_fft).for_each(|(b, &c)| *b = (*b * c).conj());
+                inner.transform(&mut buffer);
+                let scale = P::one() / P::from(padded_len).unwrap();
+                zip(data.iter_mut(), zip(&buffer, chirp)).for_each(|(x, (&b, &w))| *x = b.conj() * w * scale);
+            },
+        }
+    }
+
+    fn radix2(data: &mut [Complex<P>], twiddles: &[Complex<P>])
+    {
+        let n = data.len();
+        let mut j = 0;
+        for i in 1..n                                     // bit reversal permutation
+        {
+            let mut bit = n >> 1;
+            while j & bit != 0
+            {
+                j  ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j
+            {
+                data.swap(i, j);
+            }
+        }
+        let mut len = 2;
+        while len <= n
+        {
+            let stride = n / len;
+            for start in (0..n).step_by(len)
+            {
+                for k in 0..len / 2
+                {
+                    let u = data[start + k];
+                    let v = data[start + k + len / 2] * twiddles[k * stride];
+                    data[start + k]           = u + v;
+                    data[start + k + len / 2] = u - v;
+                }
+            }
+            len <<= 1;
+        }
+    }
+}
+
+// 2D transform of a row-major rows x columns array: the rows, then the columns
+pub struct Fft2D<P>
+{
+    rows: Fft<P>,
+    columns: Fft<P>,
+    column_buffer: Vec<Complex<P>>,
+}
+
+impl<P> Fft2D<P> where P: PhysicalObservable
+{
+    pub fn new(rows: usize, columns: usize) -> Self
+    {
+        Self { rows: Fft::new(rows), columns: Fft::new(columns), column_buffer: vec![Complex::default(); rows] }
+    }
+    pub fn transform(&mut self, data: &mut [Complex<P>])
+    {
+        let (rows, columns) = (self.rows.len, self.columns.len);
+        data.chunks_exact_mut(columns).for_each(|row| self.columns.transform(row));
+        for x in 0..columns
+        {
+            (0..rows).for_each(|y| self.column_buffer[y] = data[y * columns + x]);
+            self.rows.transform(&mut self.column_buffer);
+            (0..rows).for_each(|y| data[y * columns + x] = self.column_buffer[y]);
+        }
+    }
+}
+
+// Transform of a row-major [layers, rows, columns] array (1 for the missing dimensions), axis by axis
+#[derive(Debug, Clone)]
+pub struct FftGrid<P>
+{
+    shape: [usize; 3],
+    axes: [Fft<P>; 3],
+}
+
+impl<P> FftGrid<P> where P: PhysicalObservable
+{
+    pub fn new(shape: [usize; 3]) -> Self
+    {
+        Self { shape, axes: shape.map(Fft::new) }
+    }
+    pub fn transform(&self, data: &mut [Complex<P>])
+    {
+        let [layers, rows, columns] = self.shape;
+        assert_eq!(data.len(), layers * rows * columns, "Fourier transform of the wrong shape");
+        let strides    = [rows * columns, columns, 1];
+        let mut buffer = Vec::new();
+        for ((&len, &stride), fft) in zip(zip(&self.shape, &strides), &self.axes).filter(|&((&len, _), _)| len > 1)
+        {
+            buffer.resize(len, Complex::default());
+            // the first site of every line along the axis
+            for start in (0..data.len()).filter(|&n| (n / stride) % len == 0)
+            {
+                (0..len).for_each(|k| buffer[k] = data[start + k * stride]);
+                fft.transform(&mut buffer);
+                (0..len).for_each(|k| data[start + k * stride] = buffer[k]);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_fft_matches_direct_transform()
+    {
+        for len in [1, 2, 5, 6, 8, 12, 16]
+        {
+            let data: Vec<Complex<f64>> = (0..len).map(|n| Complex::new((n as f64 * 0.7).sin() + 0.3, (n * n) as f64 * 0.1)).collect();
+            let direct: Vec<Complex<f64>> = (0..len).map(|k| (0..len).map(|n| data[n] * Complex::from_polar(1_f64, -2_f64 * PI * (k * n) as f64 / len as f64)).sum()).collect();
+            let mut fast = data.clone();
+            Fft::new(len).transform(&mut fast);
+            assert!(zip(&fast, &direct).all(|(a, b)| (a - b).norm() < 1E-10), "N={len}: {fast:?} != {direct:?}");
+        }
+
+        let (rows, columns) = (3, 4);
+        let data: Vec<Complex<f64>> = (0..rows * columns).map(|n| Complex::new(n as f64, -(n as f64).sqrt())).collect();
+        let mut fast = data.clone();
+        Fft2D::new(rows, columns).transform(&mut fast);
+        for (ky, kx) in (0..rows).flat_map(|ky| (0..columns).map(move |kx| (ky, kx)))
+        {
+            let direct: Complex<f64> = (0..rows * columns).map(|n| data[n] * Complex::from_polar(1_f64, -2_f64 * PI * ((ky * (n / columns)) as f64 / rows as f64 + (kx * (n % columns)) as f64 / columns as f64))).sum();
+            assert!((fast[ky * columns + kx] - direct).norm() < 1E-10);
+        }
+
+        let shape = [2, 3, 5];
+        let data: Vec<Complex<f64>> = (0..30).map(|n| Complex::new((n as f64).cos(), 0.5 * n as f64)).collect();
+        let mut fast = data.clone();
+        FftGrid::new(shape).transform(&mut fast);
+        let coordinates = |n: usize| [n / 15, (n / 5) % 3, n % 5];
+        for (k, value) in fast.iter().enumerate()
+        {
+            let direct: Complex<f64> = (0..30).map(|n| data[n] * Complex::from_polar(1_f64, -2_f64 * PI * (0..3).map(|axis| (coordinates(k)[axis] * coordinates(n)[axis]) as f64 / shape[axis] as f64).sum::<f64>())).sum();
+            assert!((value - direct).norm() < 1E-10, "k={k}");
+        }
+    }
+}
@@ -3,7 +3,8 @@ use std::{ops::{Add, AddAssign}};
 
 use num_traits::AsPrimitive;
 use num_traits::Float;
-use periodic_array_2d_lib::{PeriodicArray2D, ArrayRngInterface};
+use periodic_array_2d_lib::{ArrayRngInterface, SpinLattice};
+use hamiltonian::Hamiltonian;
 use periodic_array_2d_lib::{SpinValue,PhysicalObservable};
 
 
@@ -61,45 +62,167 @@ impl<P> AddAssign for SpinEnergyFluctuation<P>  where P: PhysicalObservable
 }
 
 
-pub mod metropolis
+pub mod lattice
 {
     use super::*;
-    pub const MAX_BETA: f32 = 1E6;
+    pub use periodic_array_2d_lib::LatticeGeometry;
 
-    #[allow(non_snake_case)]
-    fn get_delta_energy<S, P>(spin_2d_arr: &PeriodicArray2D<S,P>, i: i32, j: i32, interaction_term: P, extern_mag: P) -> P 
-        where S: SpinValue<P>, 
-              P: PhysicalObservable,
+    // Exact critical temperatures (k_B = 1) of the zero-field Ising model:
+    // * Square:     2J / ln(1 + sqrt(2))   (Onsager)
+    // * Triangular: 4J / ln(3)             (Houtappel)
+    // * Honeycomb:  2J / ln(2 + sqrt(3))   (Wannier)
+    // Square & honeycomb are bipartite so the antiferromagnet (J<0) has the same Tc, the triangular antiferromagnet is frustrated and never orders (None).
+    pub fn exact_critical_temperature<P>(geometry: LatticeGeometry, interaction_term: P) -> Option<P> 
+        where P: PhysicalObservable
+    {
+        if interaction_term.is_zero() || (geometry == LatticeGeometry::Triangular && interaction_term.is_sign_negative())
+        {
+            return None;
+        }
+        let coupling = interaction_term.abs().to_f64()?;
+        let t_c      = match geometry
+        {
+            LatticeGeometry::Square     => 2_f64 / (1_f64 + 2_f64.sqrt()).ln(),
+            LatticeGeometry::Triangular => 4_f64 / 3_f64.ln(),
+            LatticeGeometry::Honeycomb  => 2_f64 / (2_f64 + 3_f64.sqrt()).ln(),
+        };
+        P::from(coupling * t_c)
+    }
+}
+
+// The model being simulated: the update algorithms only need the energy change of a single spin flip & the total energy,
+// so new couplings/fields only require a new implementation of this trait.
+pub mod hamiltonian
+{
+    use super::*;
+
+    pub trait Hamiltonian<S, P> where S: SpinValue<P>, P: PhysicalObservable
     {
-        let spin_LR = (spin_2d_arr.at_unchecked(i-1, j) + spin_2d_arr.at_unchecked(i+1, j)).as_();
-        let spin_UP = (spin_2d_arr.at_unchecked(i, j+1) + spin_2d_arr.at_unchecked(i, j-1)).as_();
-        let spin_ij = (spin_2d_arr.at_unchecked(i,j)).as_();
+        // Energy change when flipping the spin n
+        fn delta_energy<L: SpinLattice<S,P>>(&self, spins: &L, n: usize) -> P;
+        fn total_energy<L: SpinLattice<S,P>>(&self, spins: &L) -> P;
+
+        // Model parameters: nearest-neighbour coupling J & external field h (used by the cluster algorithms)
+        fn interaction_term(&self) -> P;
+        fn extern_mag(&self) -> P;
+        fn set_extern_mag(&mut self, extern_mag: P);
+
+        // Coupling of the bond n-m carrying the weight w (bond sign on the lattices), used by the cluster algorithms.
+        // Only anisotropic models need to override it.
+        #[inline(always)]
+        fn bond_coupling(&self, _n: usize, _m: usize, weight: P) -> P
+        {
+            self.interaction_term() * weight
+        }
+        // Model to simulate at the temperature temp: only mappings whose classical couplings depend on temp need to override it (see transverse_field)
+        fn at_temperature(&self, _temp: P) -> Self where Self: Clone
+        {
+            self.clone()
+        }
+        // Estimators of the physical observables from the simulated spins, the tracked total energy & spin sum being used as they are by default
+        #[inline(always)]
+        fn measured_energy<L: SpinLattice<S,P>>(&self, _spins: &L, total_energy: P) -> P
+        {
+            total_energy
+        }
+        #[inline(always)]
+        fn measured_spin_sum(&self, spin_sum: P) -> P
+        {
+            spin_sum
+        }
+    }
 
+    // H = -J sum_<ij> w_ij s_i s_j + h sum_i s_i   (NB: sign of h, kept from the original code, see the GUI which passes -h)
+    // The bond weights w_ij are 1 (or -1 across antiperiodic boundaries) on the lattices, arbitrary on weighted graphs.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct IsingHamiltonian<P> where P: PhysicalObservable
+    {
+        pub interaction_term: P,
+        pub extern_mag: P,
+    }
 
-        P::from(2.).unwrap() * spin_ij * (interaction_term*(spin_LR + spin_UP) - extern_mag)
+    impl<P> IsingHamiltonian<P> where P: PhysicalObservable
+    {
+        pub fn new(interaction_term: P, extern_mag: P) -> Self
+        {
+            Self { interaction_term, extern_mag }
+        }
     }
 
+    impl<S, P> Hamiltonian<S, P> for IsingHamiltonian<P> where S: SpinValue<P>, P: PhysicalObservable
+    {
+        #[inline(always)]
+        fn delta_energy<L: SpinLattice<S,P>>(&self, spins: &L, n: usize) -> P
+        {
+            let spin_neighbours = spins.weighted_neighbour_sum_at_index(n);
+            let spin_n          = spins.at_index(n).as_();
+
+            P::from(2.).unwrap() * spin_n * (self.interaction_term*spin_neighbours - self.extern_mag)
+        }
+        fn total_energy<L: SpinLattice<S,P>>(&self, spins: &L) -> P
+        {
+            let mut total_energy = P::zero();
+            for n in 0..spins.total_number() as usize
+            {
+                let forward  = spins.weighted_forward_neighbour_sum_at_index(n);
+                let boundary = spins.boundary_field_at_index(n).as_();  // bonds to frozen boundary spins
+                let spin     = spins.at_index(n).as_();
+                total_energy +=  - spin*(self.interaction_term*(forward + boundary) - self.extern_mag);
+            }
+
+            total_energy
+        }
+        #[inline(always)]
+        fn interaction_term(&self) -> P
+        {
+            self.interaction_term
+        }
+        #[inline(always)]
+        fn extern_mag(&self) -> P
+        {
+            self.extern_mag
+        }
+        #[inline(always)]
+        fn set_extern_mag(&mut self, extern_mag: P)
+        {
+            self.extern_mag = extern_mag;
+        }
+    }
+}
+
+pub mod metropolis
+{
+    use super::*;
+    pub const MAX_BETA: f32 = 1E6;
+
+    pub(crate) fn get_beta<P>(temp: P) -> P
+        where P: PhysicalObservable,
+    {
+        if temp.is_sign_positive()  {P::one()/temp} else {P::from(MAX_BETA).unwrap()}
+    }
 
     fn accept_state<R, P>(temp: P, delta_energy: P, rng: &mut R) -> bool 
         where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
               P: PhysicalObservable,
     {
-        let beta = if temp.is_sign_positive()  {P::one()/temp} else {P::from(MAX_BETA).unwrap()};
+        let beta = get_beta(temp);
         
         delta_energy.is_sign_negative() || rng.generate_rand_float(P::zero(), P::one()) < (-beta*delta_energy).exp()
     }
 
-    pub fn perform_metropolis_proposal<R, S, P>(spin_2d_arr: &mut PeriodicArray2D<S,P>, rng: &mut R, temp: P, interaction_term: P, extern_mag: P) -> SpinEnergyFluctuation<P>
+    pub fn perform_metropolis_proposal<R, L, H, S, P>(spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
         where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+              L: SpinLattice<S,P>,
+              H: Hamiltonian<S,P>,
               S: SpinValue<P>, 
               P: PhysicalObservable,
     {
-        let (i, j)        = spin_2d_arr.get_random_point(rng);
-        let delta_energy  = get_delta_energy(spin_2d_arr, i, j, interaction_term, extern_mag);
+        let n             = spins.get_random_index(rng);
+        let delta_energy  = hamiltonian.delta_energy(spins, n);
 
         if accept_state(temp, delta_energy, rng) 
         {
-            let s = spin_2d_arr.at_mut_unchecked(i, j);
+            let s = spins.at_index_mut(n);
             (*s)  = s.neg();
 
             let delta_spin = ((*s) + (*s)).as_();
@@ -112,15 +235,17 @@ pub mod metropolis
 
  
     #[allow(non_snake_case)]
-    pub fn perform_metropolis_sweep<R, S, P>(spin_2d_arr: &mut PeriodicArray2D<S,P>, rng: &mut R, temp: P, interaction_term: P, extern_mag: P) -> SpinEnergyFluctuation<P>
+    pub fn perform_metropolis_sweep<R, L, H, S, P>(spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
         where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+              L: SpinLattice<S,P>,
+              H: Hamiltonian<S,P>,
               S: SpinValue<P>, 
               P: PhysicalObservable,
     {
         let mut dS_and_dE = SpinEnergyFluctuation::default();
-        for _ in 0..spin_2d_arr.total_number()
+        for _ in 0..spins.total_number()
         {
-            dS_and_dE += perform_metropolis_proposal(spin_2d_arr, rng, temp, interaction_term, extern_mag);
+            dS_and_dE += perform_metropolis_proposal(spins, rng, temp, hamiltonian);
         }
 
         dS_and_dE
@@ -128,24 +253,583 @@ pub mod metropolis
 
  
 
-    pub fn get_total_energy<S, P>(spin_2d_arr: &PeriodicArray2D<S,P>, interaction_term: P, extern_mag: P) -> P  
-        where S: SpinValue<P>, 
-              P: PhysicalObservable
+}
+
+pub mod fourier;
+pub mod long_range;
+pub mod transverse_field;
+pub mod clusters;
+pub mod domains;
+
+// Heat-bath (Glauber) dynamics: the chosen spin is set to +-1 according to its local Boltzmann weight,
+// ie flipped with probability 1 / (1 + exp(beta dE)) instead of min(1, exp(-beta dE)).
+pub mod heat_bath
+{
+    use super::*;
+
+    pub fn perform_heat_bath_proposal<R, L, H, S, P>(spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+              L: SpinLattice<S,P>,
+              H: Hamiltonian<S,P>,
+              S: SpinValue<P>, 
+              P: PhysicalObservable,
+    {
+        let n                 = spins.get_random_index(rng);
+        let delta_energy      = hamiltonian.delta_energy(spins, n);
+        let flip_probability  = P::one() / (P::one() + (metropolis::get_beta(temp)*delta_energy).exp());
+
+        if rng.generate_rand_float(P::zero(), P::one()) < flip_probability
+        {
+            let s = spins.at_index_mut(n);
+            (*s)  = s.neg();
+
+            let delta_spin = ((*s) + (*s)).as_();
+
+            return SpinEnergyFluctuation(delta_spin, delta_energy);
+        }
+        SpinEnergyFluctuation::default()
+    }
+
+    #[allow(non_snake_case)]
+    pub fn perform_heat_bath_sweep<R, L, H, S, P>(spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+              L: SpinLattice<S,P>,
+              H: Hamiltonian<S,P>,
+              S: SpinValue<P>, 
+              P: PhysicalObservable,
+    {
+        let mut dS_and_dE = SpinEnergyFluctuation::default();
+        for _ in 0..spins.total_number()
+        {
+            dS_and_dE += perform_heat_bath_proposal(spins, rng, temp, hamiltonian);
+        }
+
+        dS_and_dE
+    }
+}
+
+// Wolff single cluster algorithm (U. Wolff, PRL 62, 361 (1989)):
+// grow a cluster from a random seed, adding satisfied bonds with probability p = 1 - exp(-2 beta |J_ij|), then flip it as a whole.
+// The external field is handled by accepting the cluster flip with the Metropolis probability of its field energy.
+// Only uses the nearest-neighbour coupling & field of the Hamiltonian, so it is valid for Hamiltonians of the Ising form.
+pub mod wolff
+{
+    use super::*;
+
+    // Buffers are kept between updates to avoid allocating a new cluster at every step
+    pub struct WolffCluster
+    {
+        in_cluster: Vec<bool>,
+        members: Vec<usize>,
+        stack: Vec<usize>,
+        grown_spins: usize,     // running totals, to know how many updates make a sweep
+        updates: usize,
+    }
+
+    impl WolffCluster
     {
-        let mut total_energy = P::zero();
-        for i in spin_2d_arr.rows_range()
+        pub fn new(number_of_spins: usize) -> Self
+        {
+            Self { in_cluster: vec![false; number_of_spins], members: Vec::new(), stack: Vec::new(), grown_spins: 0, updates: 0 }
+        }
+        #[inline(always)]
+        pub fn size(&self) -> usize
+        {
+            self.members.len()
+        }
+        // N / <cluster size> over all the updates so far (1 before the first update)
+        fn updates_per_sweep(&self, number_of_spins: usize) -> usize
+        {
+            match self.grown_spins
+            {
+                0           => 1,
+                grown_spins => (number_of_spins * self.updates).div_ceil(grown_spins),
+            }
+        }
+
+        fn grow<R, L, H, S, P>(&mut self, spins: &L, rng: &mut R, beta: P, hamiltonian: &H)
+            where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+                  L: SpinLattice<S,P>,
+                  H: Hamiltonian<S,P>,
+                  S: SpinValue<P>, 
+                  P: PhysicalObservable,
+        {
+            let interaction_term = hamiltonian.interaction_term();
+            let two             = P::from(2.).unwrap();
+            let add_probability = P::one() - (-two * beta * interaction_term.abs()).exp();
+            let seed            = spins.get_random_index(rng);
+            self.in_cluster[seed] = true;
+            self.members.push(seed);
+            self.stack.push(seed);
+
+            while let Some(n) = self.stack.pop()
+            {
+                let spin_n: P = spins.at_index(n).as_();
+                spins.for_each_weighted_neighbour_index(n, |m, weight|
+                {
+                    let coupling  = hamiltonian.bond_coupling(n, m, weight);
+                    let satisfied = (coupling * spin_n * spins.at_index(m).as_()).is_sign_positive();
+                    // Bonds of strength |J| (all the isotropic lattices) use the precomputed probability
+                    let probability = if coupling.abs() == interaction_term.abs() {add_probability} else {P::one() - (-two * beta * coupling.abs()).exp()};
+                    if !self.in_cluster[m] && satisfied && rng.generate_rand_float(P::zero(), P::one()) < probability
+                    {
+                        self.in_cluster[m] = true;
+                        self.members.push(m);
+                        self.stack.push(m);
+                    }
+                });
+            }
+        }
+
+        fn clear(&mut self)
         {
-            for j in spin_2d_arr.columns_range()
+            for &n in &self.members
             {
-                let right = spin_2d_arr.at_unchecked(i+1, j).as_();
-                let below = spin_2d_arr.at_unchecked(i, j+1).as_();
-                let spin  = spin_2d_arr.at_unchecked(i,j).as_();
-                total_energy +=  - spin*(interaction_term*(right + below) - extern_mag);
+                self.in_cluster[n] = false;
             }
+            self.members.clear();
         }
 
-        total_energy
+        #[allow(non_snake_case)]
+        pub fn perform_wolff_update<R, L, H, S, P>(&mut self, spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
+            where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+                  L: SpinLattice<S,P>,
+                  H: Hamiltonian<S,P>,
+                  S: SpinValue<P>, 
+                  P: PhysicalObservable,
+        {
+            let interaction_term = hamiltonian.interaction_term();
+            let extern_mag       = hamiltonian.extern_mag();
+            let beta             = metropolis::get_beta(temp);
+            let two              = P::from(2.).unwrap();
+
+            self.clear();
+            self.grow(spins, rng, beta, hamiltonian);
+            self.grown_spins += self.size();
+            self.updates     += 1;
+
+            // dE = 2 sum_{i in C, j not in C} J_ij s_i s_j - 2h sum_{i in C} s_i  (same sign convention for h as IsingHamiltonian)
+            // Frozen boundary spins b_i are never part of the cluster: like h, they only enter through the acceptance of the flip.
+            let mut cluster_spin   = P::zero();
+            let mut boundary_bond  = P::zero();
+            let mut boundary_field = P::zero();
+            for &n in &self.members
+            {
+                let spin_n: P   = spins.at_index(n).as_();
+                cluster_spin   += spin_n;
+                boundary_field += spin_n * spins.boundary_field_at_index(n).as_();
+                spins.for_each_weighted_neighbour_index(n, |m, weight|
+                {
+                    if !self.in_cluster[m]
+                    {
+                        boundary_bond += hamiltonian.bond_coupling(n, m, weight) * spin_n * spins.at_index(m).as_();
+                    }
+                });
+            }
+            let field_energy = two * (interaction_term * boundary_field - extern_mag * cluster_spin);
+            if field_energy.is_sign_positive() && rng.generate_rand_float(P::zero(), P::one()) >= (-beta * field_energy).exp()
+            {
+                self.clear();
+                return SpinEnergyFluctuation::default();
+            }
+
+            for &n in &self.members
+            {
+                let s = spins.at_index_mut(n);
+                (*s)  = s.neg();
+            }
+            let dS = -two * cluster_spin;
+            let dE = two * boundary_bond + field_energy;
+            SpinEnergyFluctuation(dS, dE)
+        }
+
+        // One "sweep" = N / <cluster size> cluster updates, to flip (on average) N spins like the Metropolis sweeps.
+        // The number of updates is fixed before the sweep: stopping once N spins have been grown would bias the measurements
+        // (the sweeps would preferably end right after a large cluster).
+        #[allow(non_snake_case)]
+        pub fn perform_wolff_sweep<R, L, H, S, P>(&mut self, spins: &mut L, rng: &mut R, temp: P, hamiltonian: &H) -> SpinEnergyFluctuation<P>
+            where R: MonteCarloRngInterface<P> + ArrayRngInterface, 
+                  L: SpinLattice<S,P>,
+                  H: Hamiltonian<S,P>,
+                  S: SpinValue<P>, 
+                  P: PhysicalObservable,
+        {
+            let mut dS_and_dE = SpinEnergyFluctuation::default();
+            for _ in 0..self.updates_per_sweep(spins.total_number() as usize)
+            {
+                dS_and_dE += self.perform_wolff_update(spins, rng, temp, hamiltonian);
+            }
+            dS_and_dE
+        }
     }
+}
 
- 
-}
\ No newline at end of file
+
+#[cfg(test)]
+pub(crate) mod test_utils
+{
+    use super::*;
+    use lattice::LatticeGeometry;
+    use periodic_array_2d_lib::{PeriodicArray2D, BoundaryCondition};
+
+    // Minimal splitmix64 so that the tests don't depend on the xorshifts crate (which itself depends on this one)
+    pub(crate) struct TestRng
+    {
+        pub(crate) state: u64,
+    }
+    impl TestRng
+    {
+        pub(crate) fn next(&mut self) -> u64
+        {
+            self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z  = self.state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        }
+    }
+    impl ArrayRngInterface for TestRng
+    {
+        fn generate_rand_i32(&mut self, low: i32, high: i32) -> i32 
+        {
+            low + (self.next() % (high - low) as u64) as i32
+        }
+    }
+    impl MonteCarloRngInterface<f64> for TestRng
+    {
+        fn generate_rand_float(&mut self, low: f64, high: f64) -> f64 
+        {
+            low + (high - low) * ((self.next() >> 11) as f64 / (1_u64 << 53) as f64)
+        }
+    }
+
+    // rows x columns lattice with the spin spin(i, j) on the site of row i, column j & the same boundary condition along both axes
+    pub(crate) fn lattice_from_fn(rows: i32, columns: i32, geometry: LatticeGeometry, boundary: BoundaryCondition, spin: impl Fn(i32, i32) -> i8) -> PeriodicArray2D<i8,f64>
+    {
+        let mut spins = PeriodicArray2D::<i8,f64>::new_with_geometry(rows, columns, geometry, ising_state::spin_up).unwrap();
+        spins.set_boundary_conditions(boundary, boundary).unwrap();
+        (0..rows * columns).for_each(|n| *spins.at_index_mut(n as usize) = spin(n / columns, n % columns));
+        spins
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+    use lattice::{LatticeGeometry, exact_critical_temperature};
+    use hamiltonian::IsingHamiltonian;
+    use test_utils::TestRng;
+    use periodic_array_2d_lib::{PeriodicArray2D, PeriodicArray3D, BoundaryCondition, SpinGraph};
+    use std::iter::zip;
+
+    const GEOMETRIES: [LatticeGeometry; 3] = [LatticeGeometry::Square, LatticeGeometry::Triangular, LatticeGeometry::Honeycomb];
+
+    fn mean_abs_magnetisation(geometry: LatticeGeometry, temp: f64, rng: &mut TestRng) -> f64
+    {
+        let mut spins = PeriodicArray2D::<i8,f64>::new_with_geometry(16, 16, geometry, ising_state::spin_up).unwrap();
+        let n_spins   = spins.total_number() as f64;
+        let mut m_avg = 0_f64;
+        for _ in 0..200
+        {
+            metropolis::perform_metropolis_sweep(&mut spins, rng, temp, &IsingHamiltonian::new(1_f64, 0_f64));
+        }
+        for _ in 0..200
+        {
+            metropolis::perform_metropolis_sweep(&mut spins, rng, temp, &IsingHamiltonian::new(1_f64, 0_f64));
+            m_avg += spins.sum_observable().abs() / n_spins / 200_f64;
+        }
+        m_avg
+    }
+
+    #[test]
+    fn test_ground_state_energy()
+    {
+        for geometry in GEOMETRIES
+        {
+            let spins  = PeriodicArray2D::<i8,f64>::new_with_geometry(8, 8, geometry, ising_state::spin_up).unwrap();
+            let energy = IsingHamiltonian::new(1_f64, 0_f64).total_energy(&spins) / spins.total_number() as f64;
+            let z      = geometry.coordination_number() as f64;
+            assert_eq!(energy, -z / 2_f64, "{geometry:?}: E/N = -z/2");
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_energy_fluctuation_matches_total_energy()
+    {
+        let mut rng = TestRng { state: 42 };
+        for geometry in GEOMETRIES
+        {
+            let mut spins   = PeriodicArray2D::<i8,f64>::new_with_geometry(8, 8, geometry, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+            let mut energy  = IsingHamiltonian::new(1_f64, 0.3_f64).total_energy(&spins);
+            let mut spin    = spins.sum_observable();
+            for _ in 0..20
+            {
+                let SpinEnergyFluctuation(dS, dE) = metropolis::perform_metropolis_sweep(&mut spins, &mut rng, 2_f64, &IsingHamiltonian::new(1_f64, 0.3_f64));
+                energy += dE;
+                spin   += dS;
+            }
+            let expected = IsingHamiltonian::new(1_f64, 0.3_f64).total_energy(&spins);
+            assert!((energy - expected).abs() < 1E-9, "{geometry:?}: accumulated dE {energy} vs total energy {expected}");
+            assert_eq!(spin, spins.sum_observable(), "{geometry:?}: accumulated dS");
+        }
+    }
+
+    #[test]
+    fn test_exact_critical_temperatures()
+    {
+        let t_square = exact_critical_temperature(LatticeGeometry::Square, 1_f64).unwrap();
+        let t_tri    = exact_critical_temperature(LatticeGeometry::Triangular, 1_f64).unwrap();
+        let t_honey  = exact_critical_temperature(LatticeGeometry::Honeycomb, 1_f64).unwrap();
+        assert!((t_square - 2.269185314213022).abs() < 1E-12);
+        assert!((t_tri - 3.6409569065073493).abs() < 1E-12);
+        assert!((t_honey - 1.518651435000414).abs() < 1E-12);
+        assert_eq!(exact_critical_temperature(LatticeGeometry::Square, -1_f64), Some(t_square));
+        assert_eq!(exact_critical_temperature(LatticeGeometry::Triangular, -1_f64), None);
+    }
+
+    #[test]
+    fn test_order_below_and_disorder_above_tc()
+    {
+        let mut rng = TestRng { state: 12345 };
+        for geometry in GEOMETRIES
+        {
+            let t_c = exact_critical_temperature(geometry, 1_f64).unwrap();
+            let m_low  = mean_abs_magnetisation(geometry, 0.7 * t_c, &mut rng);
+            let m_high = mean_abs_magnetisation(geometry, 1.6 * t_c, &mut rng);
+            assert!(m_low > 0.8, "{geometry:?}: <|m|> = {m_low} at 0.7 Tc");
+            assert!(m_high < 0.3, "{geometry:?}: <|m|> = {m_high} at 1.6 Tc");
+        }
+    }
+
+    #[test]
+    fn test_honeycomb_needs_even_shape()
+    {
+        assert!(PeriodicArray2D::<i8,f64>::new_with_geometry(5, 8, LatticeGeometry::Honeycomb, ising_state::spin_up).is_err());
+        assert!(PeriodicArray2D::<i8,f64>::new_with_geometry(8, 8, LatticeGeometry::Honeycomb, ising_state::spin_up).is_ok());
+        // Helical boundaries break the brick-wall parity along either axis
+        let mut spins = PeriodicArray2D::<i8,f64>::new_with_geometry(8, 8, LatticeGeometry::Honeycomb, ising_state::spin_up).unwrap();
+        for (row_boundary, column_boundary) in [(BoundaryCondition::Helical, BoundaryCondition::Periodic), (BoundaryCondition::Periodic, BoundaryCondition::Helical)]
+        {
+            assert!(spins.set_boundary_conditions(row_boundary, column_boundary).is_err(), "{row_boundary:?} x {column_boundary:?}");
+        }
+        assert!(spins.set_boundary_conditions(BoundaryCondition::Open, BoundaryCondition::Antiperiodic).is_ok());
+    }
+
+    #[test]
+    fn test_cubic_ground_state_energy()
+    {
+        let spins  = PeriodicArray3D::<i8,f64>::new_with(4, 6, 8, ising_state::spin_up).unwrap();
+        let energy = IsingHamiltonian::new(1_f64, 0.5_f64).total_energy(&spins) / spins.total_number() as f64;
+        assert_eq!(energy, -2.5_f64, "E/N = -z/2 J + h");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_wolff_energy_fluctuation_matches_total_energy()
+    {
+        let mut rng     = TestRng { state: 7 };
+        let mut spins   = PeriodicArray3D::<i8,f64>::new_with(6, 6, 6, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+        let mut cluster = wolff::WolffCluster::new(spins.total_number() as usize);
+        let mut energy  = IsingHamiltonian::new(1_f64, 0.2_f64).total_energy(&spins);
+        let mut spin    = spins.sum_observable();
+        for _ in 0..50
+        {
+            let SpinEnergyFluctuation(dS, dE) = cluster.perform_wolff_update(&mut spins, &mut rng, 4_f64, &IsingHamiltonian::new(1_f64, 0.2_f64));
+            energy += dE;
+            spin   += dS;
+        }
+        let expected = IsingHamiltonian::new(1_f64, 0.2_f64).total_energy(&spins);
+        assert!((energy - expected).abs() < 1E-9, "accumulated dE {energy} vs total energy {expected}");
+        assert_eq!(spin, spins.sum_observable(), "accumulated dS");
+    }
+
+    #[test]
+    fn test_cubic_wolff_order_below_and_disorder_above_tc()
+    {
+        const CUBIC_TC: f64 = 4.5115; // No exact solution in 3D, best Monte-Carlo estimates
+        let mut rng         = TestRng { state: 99 };
+        for (temp, ordered) in [(0.7 * CUBIC_TC, true), (1.5 * CUBIC_TC, false)]
+        {
+            let mut spins   = PeriodicArray3D::<i8,f64>::new_with(8, 8, 8, ising_state::spin_up).unwrap();
+            let mut cluster = wolff::WolffCluster::new(spins.total_number() as usize);
+            let n_spins     = spins.total_number() as f64;
+            let mut m_avg   = 0_f64;
+            for _ in 0..50
+            {
+                cluster.perform_wolff_sweep(&mut spins, &mut rng, temp, &IsingHamiltonian::new(1_f64, 0_f64));
+            }
+            for _ in 0..100
+            {
+                cluster.perform_wolff_sweep(&mut spins, &mut rng, temp, &IsingHamiltonian::new(1_f64, 0_f64));
+                m_avg += spins.sum_observable().abs() / n_spins / 100_f64;
+            }
+            match ordered
+            {
+                true  => assert!(m_avg > 0.8, "<|m|> = {m_avg} at 0.7 Tc"),
+                false => assert!(m_avg < 0.3, "<|m|> = {m_avg} at 1.5 Tc"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_boundary_conditions_ground_state_energy()
+    {
+        const L: i32 = 8;
+        let n_spins  = (L*L) as f64;
+        let l        = L as f64;
+        let cases    = 
+        [
+            (BoundaryCondition::Periodic,     BoundaryCondition::Periodic,     -2_f64*n_spins),
+            (BoundaryCondition::Helical,      BoundaryCondition::Helical,      -2_f64*n_spins),
+            (BoundaryCondition::Periodic,     BoundaryCondition::Antiperiodic, -2_f64*n_spins + 2_f64*l),   // L bonds change sign
+            (BoundaryCondition::Open,         BoundaryCondition::Open,         -2_f64*l*(l - 1_f64)),
+            (BoundaryCondition::FixedUp,      BoundaryCondition::Periodic,     -2_f64*n_spins - l),   // L wrapping bonds replaced by 2L bonds to the frozen spins
+            (BoundaryCondition::FixedDown,    BoundaryCondition::Periodic,     -2_f64*n_spins + 3_f64*l),
+        ];
+        for (row_boundary, column_boundary, expected) in cases
+        {
+            let mut spins = PeriodicArray2D::<i8,f64>::new_with(L, L, ising_state::spin_up).unwrap();
+            spins.set_boundary_conditions(row_boundary, column_boundary).unwrap();
+            let energy = IsingHamiltonian::new(1_f64, 0_f64).total_energy(&spins);
+            assert_eq!(energy, expected, "{row_boundary:?} x {column_boundary:?}");
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_boundary_conditions_energy_fluctuations()
+    {
+        let mut rng = TestRng { state: 2024 };
+        let boundaries = [BoundaryCondition::Open, BoundaryCondition::FixedUp, BoundaryCondition::FixedDown, BoundaryCondition::Antiperiodic, BoundaryCondition::Helical];
+        for geometry in GEOMETRIES
+        {
+            for (&row_boundary, &column_boundary) in zip(&boundaries, boundaries.iter().rev())
+            {
+                let mut spins   = PeriodicArray2D::<i8,f64>::new_with_geometry(8, 8, geometry, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+                if spins.set_boundary_conditions(row_boundary, column_boundary).is_err()
+                {
+                    assert_eq!(geometry, LatticeGeometry::Honeycomb);
+                    assert!(BoundaryCondition::Helical == row_boundary || BoundaryCondition::Helical == column_boundary);
+                    continue;
+                }
+                let mut cluster = wolff::WolffCluster::new(spins.total_number() as usize);
+                let mut energy  = IsingHamiltonian::new(1_f64, 0.2_f64).total_energy(&spins);
+                for _ in 0..10
+                {
+                    let SpinEnergyFluctuation(_, dE_metropolis) = metropolis::perform_metropolis_sweep(&mut spins, &mut rng, 2_f64, &IsingHamiltonian::new(1_f64, 0.2_f64));
+                    let SpinEnergyFluctuation(_, dE_wolff)      = cluster.perform_wolff_update(&mut spins, &mut rng, 2_f64, &IsingHamiltonian::new(1_f64, 0.2_f64));
+                    energy += dE_metropolis + dE_wolff;
+                }
+                let expected = IsingHamiltonian::new(1_f64, 0.2_f64).total_energy(&spins);
+                assert!((energy - expected).abs() < 1E-9, "{geometry:?}, {row_boundary:?} x {column_boundary:?}: accumulated dE {energy} vs total energy {expected}");
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_helical_energy_fluctuations()
+    {
+        let mut rng     = TestRng { state: 4242 };
+        let hamiltonian = IsingHamiltonian::new(1_f64, 0.2_f64);
+        for geometry in [LatticeGeometry::Square, LatticeGeometry::Triangular]
+        {
+            for (rows, columns) in [(4, 4), (5, 7)]
+            {
+                let mut spins = PeriodicArray2D::<i8,f64>::new_with_geometry(rows, columns, geometry, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+                spins.set_boundary_conditions(BoundaryCondition::Helical, BoundaryCondition::Helical).unwrap();
+                // Every local dE is the change of the total energy
+                for n in 0..(rows*columns) as usize
+                {
+                    let before = hamiltonian.total_energy(&spins);
+                    let dE     = hamiltonian.delta_energy(&spins, n);
+                    *spins.at_index_mut(n) = -spins.at_index(n);
+                    assert!((hamiltonian.total_energy(&spins) - before - dE).abs() < 1E-9, "{geometry:?} {rows}x{columns}: flipping {n}");
+                }
+                let mut energy = hamiltonian.total_energy(&spins);
+                for _ in 0..10
+                {
+                    let SpinEnergyFluctuation(_, dE_metropolis) = metropolis::perform_metropolis_sweep(&mut spins, &mut rng, 2_f64, &hamiltonian);
+                    let SpinEnergyFluctuation(_, dE_heat_bath)  = heat_bath::perform_heat_bath_sweep(&mut spins, &mut rng, 2_f64, &hamiltonian);
+                    energy += dE_metropolis + dE_heat_bath;
+                }
+                let expected = hamiltonian.total_energy(&spins);
+                assert!((energy - expected).abs() < 1E-9, "{geometry:?} {rows}x{columns}: accumulated dE {energy} vs total energy {expected}");
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_weighted_graph_energy_fluctuations()
+    {
+        // Random graph with frustrated, weighted bonds: all three algorithms must keep track of the energy
+        const NODES: usize = 40;
+        let mut rng     = TestRng { state: 31 };
+        let edges: Vec<(usize, usize, f64)> = (0..3*NODES).map(|k| (k % NODES, rng.generate_rand_i32(0, NODES as i32) as usize, rng.generate_rand_float(-0.5, 1.5)))
+                                                         .filter(|&(a, b, _)| a != b)
+                                                         .collect();
+        let mut graph   = SpinGraph::<i8,f64>::new_with(NODES, &edges, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+        let mut cluster = wolff::WolffCluster::new(NODES);
+        let hamiltonian = IsingHamiltonian::new(1_f64, 0.1_f64);
+        let mut energy  = hamiltonian.total_energy(&graph);
+        let mut spin    = graph.sum_observable();
+        for _ in 0..20
+        {
+            for SpinEnergyFluctuation(dS, dE) in [metropolis::perform_metropolis_sweep(&mut graph, &mut rng, 1.5_f64, &hamiltonian),
+                                                  heat_bath::perform_heat_bath_sweep(&mut graph, &mut rng, 1.5_f64, &hamiltonian),
+                                                  cluster.perform_wolff_update(&mut graph, &mut rng, 1.5_f64, &hamiltonian)]
+            {
+                energy += dE;
+                spin   += dS;
+            }
+        }
+        let expected = hamiltonian.total_energy(&graph);
+        assert!((energy - expected).abs() < 1E-9, "accumulated dE {energy} vs total energy {expected}");
+        assert_eq!(spin, graph.sum_observable(), "accumulated dS");
+    }
+
+    #[test]
+    fn test_graph_matches_lattice()
+    {
+        // The 8x8 periodic square lattice written as an edge list, with couplings w = 2
+        const L: usize = 8;
+        let edges: Vec<(usize, usize, f64)> = (0..L*L).flat_map(|n| [(n, (n/L)*L + (n + 1) % L, 2_f64), (n, (n + L) % (L*L), 2_f64)]).collect();
+        let mut rng     = TestRng { state: 5 };
+        let lattice     = PeriodicArray2D::<i8,f64>::new_with(L as i32, L as i32, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+        let graph       = SpinGraph::<i8,f64>::new_with(L*L, &edges, ||0).unwrap();
+        let mut graph_spins = graph.clone();
+        (0..L*L).for_each(|n| *graph_spins.at_index_mut(n) = lattice.at_index(n));
+        let lattice_energy = IsingHamiltonian::new(2_f64, 0.3_f64).total_energy(&lattice);
+        let graph_energy   = IsingHamiltonian::new(1_f64, 0.3_f64).total_energy(&graph_spins);
+        assert_eq!(lattice_energy, graph_energy);
+        assert!((0..L*L).all(|n| IsingHamiltonian::new(2_f64, 0.3_f64).delta_energy(&lattice, n) == IsingHamiltonian::new(1_f64, 0.3_f64).delta_energy(&graph_spins, n)));
+    }
+
+    #[test]
+    fn test_heat_bath_order_below_and_disorder_above_tc()
+    {
+        let mut rng = TestRng { state: 77 };
+        let t_c     = exact_critical_temperature(LatticeGeometry::Square, 1_f64).unwrap();
+        for (temp, ordered) in [(0.7 * t_c, true), (1.6 * t_c, false)]
+        {
+            let mut spins = PeriodicArray2D::<i8,f64>::new_with(16, 16, ising_state::spin_up).unwrap();
+            let mut m_avg = 0_f64;
+            for step in 0..400
+            {
+                heat_bath::perform_heat_bath_sweep(&mut spins, &mut rng, temp, &IsingHamiltonian::new(1_f64, 0_f64));
+                if step >= 200
+                {
+                    m_avg += spins.sum_observable().abs() / 256_f64 / 200_f64;
+                }
+            }
+            match ordered
+            {
+                true  => assert!(m_avg > 0.8, "<|m|> = {m_avg} at 0.7 Tc"),
+                false => assert!(m_avg < 0.3, "<|m|> = {m_avg} at 1.6 Tc"),
+            }
+        }
+    }
+}
@@ -0,0 +1,436 @@
+// Ising model with power-law couplings J(r) = J / r^(d + sigma) between all the pairs of a periodic (hyper)cubic lattice:
+//      H = -J sum_{i<j} K(r_ij) s_i s_j + h sum_i s_i   (same sign convention for h as IsingHamiltonian)
+// The periodic images are summed once & for all into the effective coupling K(r) of each displacement r, so that the
+// Hamiltonian only needs a table of N couplings. sigma > 2 gives the short-range universality class, 0 < sigma < d/2 mean-field.
+// The total energy is a convolution of the spins with K: it's computed in O(N log N) from the Fourier transform of K.
+use super::*;
+use crate::fourier::FftGrid;
+use num_complex::Complex;
+
+#[derive(Debug, Clone)]
+pub struct LongRangeIsingHamiltonian<P> where P: PhysicalObservable
+{
+    pub interaction_term: P,
+    pub extern_mag: P,
+    sigma: P,
+    dimension: usize,
+    shape: [usize; 3],          // (layers, rows, columns), 1 for the missing dimensions
+    couplings: Vec<P>,          // K(r) for every displacement r, flattened like the lattice (K(0) = 0)
+    cumulative_couplings: Vec<P>, // sum_{r' < r} K(r') over the displacements r != 0, used by the cluster algorithm
+    coupling_spectrum: Vec<P>,  // K(q), real since K(r) = K(-r)
+    fft: FftGrid<P>,
+}
+
+impl<P> LongRangeIsingHamiltonian<P> where P: PhysicalObservable
+{
+    // lattice_shape: [columns], [rows, columns] or [layers, rows, columns] of the periodic lattice,
+    // image_shells: number of shells of periodic images summed explicitly, the remaining ones being approximated by an integral.
+    pub fn new(interaction_term: P, extern_mag: P, sigma: P, lattice_shape: &[usize], image_shells: usize) -> Self
+    {
+        assert!((1..=3).contains(&lattice_shape.len()), "Long-range couplings are only implemented in 1, 2 & 3 dimensions");
+        assert!(lattice_shape.iter().all(|&l| l > 0), "Lattice lengths need to be > 0");
+        assert!(sigma.is_sign_positive() && !sigma.is_zero(), "sigma needs to be > 0 for the image sums to converge");
+
+        let dimension = lattice_shape.len();
+        let mut shape = [1_usize; 3];
+        shape[3 - dimension..].copy_from_slice(lattice_shape);
+
+        let sigma_f64  = sigma.to_f64().unwrap();
+        let exponent   = dimension as f64 + sigma_f64;
+        let shells     = image_shells as i64;
+        let image_range = |axis: usize| if axis >= 3 - dimension {-shells..=shells} else {0..=0};  // no images along the padded axes
+
+        // Images outside of the summed box: sum_{|r| > R} r^-(d+sigma) ~ (1/V) int_R^inf S_d r^(d-1) r^-(d+sigma) dr = S_d R^-sigma / (sigma V),
+        // R being the radius of the sphere with the same volume as the (2 shells + 1)^d summed cells (S_d = d V_d).
+        let cell_volume  = shape.iter().product::<usize>() as f64;
+        let unit_ball    = [2_f64, std::f64::consts::PI, 4_f64 * std::f64::consts::PI / 3_f64][dimension - 1];
+        let radius       = ((2 * image_shells + 1).pow(dimension as u32) as f64 * cell_volume / unit_ball).powf(1_f64 / dimension as f64);
+        let tail         = dimension as f64 * unit_ball * radius.powf(-sigma_f64) / (sigma_f64 * cell_volume);
+
+        // Minimal image of a displacement, so that the summed images are centred around it
+        let minimal      = |d: usize, l: usize| if 2 * d > l {d as i64 - l as i64} else {d as i64};
+        let [layers, rows, columns] = shape;
+        let mut couplings = vec![P::zero(); layers * rows * columns];
+        for dz in 0..layers
+        {
+            for dy in 0..rows
+            {
+                for dx in 0..columns
+                {
+                    let (z, y, x)     = (minimal(dz, layers), minimal(dy, rows), minimal(dx, columns));
+                    let mut coupling  = 0_f64;
+                    for nz in image_range(0)
+                    {
+                        for ny in image_range(1)
+                        {
+                            for nx in image_range(2)
+                            {
+                                let r = [z + nz * layers as i64, y + ny * rows as i64, x + nx * columns as i64];
+                                let r_sqr = r.iter().map(|&c| (c * c) as f64).sum::<f64>();
+                                if r_sqr > 0_f64
+                                {
+                                    coupling += r_sqr.powf(-exponent / 2_f64);
+                                }
+                            }
+                        }
+                    }
+                    if dz + dy + dx > 0
+                    {
+                        couplings[(dz * rows + dy) * columns + dx] = P::from(coupling + tail).unwrap();
+                    }
+                }
+            }
+        }
+
+        let mut cumulative_couplings = vec![P::zero(); couplings.len()];
+        for k in 1..couplings.len()
+        {
+            cumulative_couplings[k] = cumulative_couplings[k - 1] + couplings[k];
+        }
+
+        let fft                   = FftGrid::new(shape);
+        let mut coupling_spectrum = couplings.iter().map(|&k| Complex::new(k, P::zero())).collect::<Vec<_>>();
+        fft.transform(&mut coupling_spectrum);
+        let coupling_spectrum     = coupling_spectrum.into_iter().map(|k| k.re).collect();
+
+        Self { interaction_term, extern_mag, sigma, dimension, shape, couplings, cumulative_couplings, coupling_spectrum, fft }
+    }
+    #[inline(always)]
+    pub fn sigma(&self) -> P
+    {
+        self.sigma
+    }
+    // Shape of the lattice the couplings were computed for, as given to new()
+    #[inline(always)]
+    pub fn lattice_shape(&self) -> &[usize]
+    {
+        &self.shape[3 - self.dimension..]
+    }
+    // Effective coupling K(r) (without J) of the flattened displacement r, periodic images included
+    #[inline(always)]
+    pub fn coupling(&self, displacement: usize) -> P
+    {
+        self.couplings[displacement]
+    }
+    // Site reached from n after the flattened displacement r
+    #[inline(always)]
+    fn shifted_index(&self, n: usize, displacement: usize) -> usize
+    {
+        let [layers, rows, columns] = self.shape;
+        let (z, y, x)    = (n / (rows * columns), (n / columns) % rows, n % columns);
+        let (dz, dy, dx) = (displacement / (rows * columns), (displacement / columns) % rows, displacement % columns);
+        (((z + dz) % layers) * rows + (y + dy) % rows) * columns + (x + dx) % columns
+    }
+    // sum_m K(r_nm) s_m, O(N): only for single spin flips, the total energy goes through the Fourier transform
+    pub fn local_field<S, L>(&self, spins: &L, n: usize) -> P
+        where S: SpinValue<P>,
+              L: SpinLattice<S,P>,
+    {
+        debug_assert_eq!(spins.total_number() as usize, self.couplings.len(), "Lattice & couplings shapes differ");
+        let [layers, rows, columns] = self.shape;
+        let (z, y, x)   = (n / (rows * columns), (n / columns) % rows, n % columns);
+        let mut field   = P::zero();
+        let mut k       = 0;
+        for dz in 0..layers
+        {
+            let layer_start = ((z + dz) % layers) * rows;
+            for dy in 0..rows
+            {
+                let row_start = (layer_start + (y + dy) % rows) * columns;
+                for dx in 0..columns
+                {
+                    field += self.couplings[k] * spins.at_index(row_start + (x + dx) % columns).as_();
+                    k     += 1;
+                }
+            }
+        }
+        field
+    }
+}
+
+impl<S, P> Hamiltonian<S, P> for LongRangeIsingHamiltonian<P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    // O(N) per spin flip: a Metropolis sweep costs O(N^2), prefer the cluster algorithm for large lattices
+    #[inline(always)]
+    fn delta_energy<L: SpinLattice<S,P>>(&self, spins: &L, n: usize) -> P
+    {
+        let spin_n = spins.at_index(n).as_();
+        P::from(2.).unwrap() * spin_n * (self.interaction_term*self.local_field(spins, n) - self.extern_mag)
+    }
+    // -J/2 sum_n s_n (K * s)_n + h sum_n s_n, the convolution being sum_q K(q) |s(q)|^2 / N (Parseval): O(N log N)
+    fn total_energy<L: SpinLattice<S,P>>(&self, spins: &L) -> P
+    {
+        debug_assert_eq!(spins.total_number() as usize, self.couplings.len(), "Lattice & couplings shapes differ");
+        let mut spectrum: Vec<Complex<P>> = (0..spins.total_number() as usize).map(|n| Complex::new(spins.at_index(n).as_(), P::zero())).collect();
+        let spin_sum   = spectrum.iter().fold(P::zero(), |acc, s| acc + s.re);
+        self.fft.transform(&mut spectrum);
+        let pair_sum   = spectrum.iter().zip(&self.coupling_spectrum).fold(P::zero(), |acc, (s, &k)| acc + k * s.norm_sqr()) / P::from(spectrum.len()).unwrap();
+        -P::from(0.5).unwrap() * self.interaction_term * pair_sum + self.extern_mag * spin_sum
+    }
+    #[inline(always)]
+    fn interaction_term(&self) -> P
+    {
+        self.interaction_term
+    }
+    #[inline(always)]
+    fn extern_mag(&self) -> P
+    {
+        self.extern_mag
+    }
+    #[inline(always)]
+    fn set_extern_mag(&mut self, extern_mag: P)
+    {
+        self.extern_mag = extern_mag;
+    }
+}
+
+// Luijten-Bloete cluster algorithm (E. Luijten & H. Bloete, Int. J. Mod. Phys. C 6, 359 (1995)): Wolff clusters where every
+// member is tried against all the N-1 other sites, bond k being placed with p_k = 1 - exp(-2 beta |J| K_k).
+// Instead of drawing N random numbers per member, the distance to the next placed bond is drawn from the cumulative
+// probability 1 - exp(-2 beta |J| sum_{l<k} K_l), so that building a cluster costs O(|C| log N) instead of O(N |C|).
+// The energy isn't followed bond by bond (the bonds between the cluster & the rest of the lattice would cost O(|C| (N - |C|))):
+// it's recomputed with the Fourier transform (O(N log N)) before & after each sweep, so that a sweep costs O(N log N).
+pub struct LongRangeCluster
+{
+    in_cluster: Vec<bool>,
+    members: Vec<usize>,
+    stack: Vec<usize>,
+    grown_spins: usize,     // running totals, to know how many updates make a sweep
+    updates: usize,
+}
+
+impl LongRangeCluster
+{
+    pub fn new(number_of_spins: usize) -> Self
+    {
+        Self { in_cluster: vec![false; number_of_spins], members: Vec::new(), stack: Vec::new(), grown_spins: 0, updates: 0 }
+    }
+    #[inline(always)]
+    pub fn size(&self) -> usize
+    {
+        self.members.len()
+    }
+    // N / <cluster size> over all the updates so far (1 before the first update)
+    fn updates_per_sweep(&self, number_of_spins: usize) -> usize
+    {
+        match self.grown_spins
+        {
+            0           => 1,
+            grown_spins => (number_of_spins * self.updates).div_ceil(grown_spins),
+        }
+    }
+
+    fn grow<R, L, S, P>(&mut self, spins: &L, rng: &mut R, beta: P, hamiltonian: &LongRangeIsingHamiltonian<P>)
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface,
+              L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        let rate       = P::from(2.).unwrap() * beta * hamiltonian.interaction_term.abs();
+        let cumulative = &hamiltonian.cumulative_couplings;
+        let last       = cumulative.len() - 1;
+        let seed       = spins.get_random_index(rng);
+        self.in_cluster[seed] = true;
+        self.members.push(seed);
+        self.stack.push(seed);
+
+        while let Some(n) = self.stack.pop()
+        {
+            let spin_n: P = spins.at_index(n).as_();
+            let mut k     = 0; // bonds to the displacements 1..=k have already been tried
+            while k < last
+            {
+                // next placed bond: smallest k' > k with cumulative[k'] > cumulative[k] + Exp(rate)
+                let target = cumulative[k] - rng.generate_rand_float(P::zero(), P::one()).max(P::min_positive_value()).ln() / rate;
+                k         += 1 + cumulative[k + 1..].partition_point(|&c| c <= target);
+                if k > last
+                {
+                    break;
+                }
+                let m         = hamiltonian.shifted_index(n, k);
+                let satisfied = (hamiltonian.interaction_term * spin_n * spins.at_index(m).as_()).is_sign_positive();
+                if !self.in_cluster[m] && satisfied
+                {
+                    self.in_cluster[m] = true;
+                    self.members.push(m);
+                    self.stack.push(m);
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self)
+    {
+        for &n in &self.members
+        {
+            self.in_cluster[n] = false;
+        }
+        self.members.clear();
+    }
+
+    // Grows a cluster & flips it (the field being accepted with the Metropolis probability of its energy): returns dS
+    fn update_cluster<R, L, S, P>(&mut self, spins: &mut L, rng: &mut R, temp: P, hamiltonian: &LongRangeIsingHamiltonian<P>) -> P
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface,
+              L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        let beta = metropolis::get_beta(temp);
+        let two  = P::from(2.).unwrap();
+
+        self.clear();
+        self.grow(spins, rng, beta, hamiltonian);
+        self.grown_spins += self.size();
+        self.updates     += 1;
+
+        let cluster_spin = self.members.iter().fold(P::zero(), |acc, &n| acc + spins.at_index(n).as_());
+        let field_energy = -two * hamiltonian.extern_mag * cluster_spin;
+        if field_energy.is_sign_positive() && rng.generate_rand_float(P::zero(), P::one()) >= (-beta * field_energy).exp()
+        {
+            self.clear();
+            return P::zero();
+        }
+        for &n in &self.members
+        {
+            let s = spins.at_index_mut(n);
+            (*s)  = s.neg();
+        }
+        -two * cluster_spin
+    }
+
+    // One cluster update: dE from the total energies before & after, O(N log N) (perform_cluster_sweep() pays it once per sweep)
+    #[allow(non_snake_case)]
+    pub fn perform_cluster_update<R, L, S, P>(&mut self, spins: &mut L, rng: &mut R, temp: P, hamiltonian: &LongRangeIsingHamiltonian<P>) -> SpinEnergyFluctuation<P>
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface,
+              L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        let energy = hamiltonian.total_energy(spins);
+        let dS     = self.update_cluster(spins, rng, temp, hamiltonian);
+        SpinEnergyFluctuation(dS, hamiltonian.total_energy(spins) - energy)
+    }
+
+    // As many cluster updates as needed to flip (on average) N spins, see wolff::WolffCluster::perform_wolff_sweep()
+    #[allow(non_snake_case)]
+    pub fn perform_cluster_sweep<R, L, S, P>(&mut self, spins: &mut L, rng: &mut R, temp: P, hamiltonian: &LongRangeIsingHamiltonian<P>) -> SpinEnergyFluctuation<P>
+        where R: MonteCarloRngInterface<P> + ArrayRngInterface,
+              L: SpinLattice<S,P>,
+              S: SpinValue<P>,
+              P: PhysicalObservable,
+    {
+        let energy = hamiltonian.total_energy(spins);
+        let mut dS = P::zero();
+        for _ in 0..self.updates_per_sweep(spins.total_number() as usize)
+        {
+            dS += self.update_cluster(spins, rng, temp, hamiltonian);
+        }
+        SpinEnergyFluctuation(dS, hamiltonian.total_energy(spins) - energy)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use test_utils::TestRng;
+    use periodic_array_2d_lib::{PeriodicArray2D, PeriodicArray3D};
+
+    #[test]
+    fn test_long_range_short_range_limit()
+    {
+        // sigma -> infinity: only the nearest neighbours survive
+        let spins       = PeriodicArray2D::<i8,f64>::new_with(8, 8, ising_state::spin_up).unwrap();
+        let hamiltonian = LongRangeIsingHamiltonian::new(1_f64, 0_f64, 40_f64, &[8, 8], 2);
+        let energy      = hamiltonian.total_energy(&spins) / spins.total_number() as f64;
+        assert!((energy + 2_f64).abs() < 1E-5, "E/N = {energy}"); // next nearest neighbours: 2^-21
+        assert!((hamiltonian.coupling(1) - hamiltonian.coupling(7)).abs() < 1E-12, "K(r) = K(-r)");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_long_range_energy_fluctuations()
+    {
+        let mut rng     = TestRng { state: 11 };
+        let mut spins   = PeriodicArray2D::<i8,f64>::new_with(6, 5, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+        let mut cluster = LongRangeCluster::new(spins.total_number() as usize);
+        let hamiltonian = LongRangeIsingHamiltonian::new(1_f64, 0.2_f64, 0.8_f64, &[6, 5], 1);
+        let mut energy  = hamiltonian.total_energy(&spins);
+        let mut spin    = spins.sum_observable();
+        for _ in 0..20
+        {
+            for SpinEnergyFluctuation(dS, dE) in [metropolis::perform_metropolis_sweep(&mut spins, &mut rng, 6_f64, &hamiltonian),
+                                                  cluster.perform_cluster_update(&mut spins, &mut rng, 6_f64, &hamiltonian)]
+            {
+                energy += dE;
+                spin   += dS;
+            }
+        }
+        let expected = hamiltonian.total_energy(&spins);
+        assert!((energy - expected).abs() < 1E-9, "accumulated dE {energy} vs total energy {expected}");
+        assert_eq!(spin, spins.sum_observable(), "accumulated dS");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_long_range_fourier_energy_matches_pair_sum()
+    {
+        let mut rng = TestRng { state: 5 };
+        let mut random_spin = ||if rng.next().is_multiple_of(2) {1} else {-1};
+        let chain   = PeriodicArray2D::<i8,f64>::new_with(1, 7, &mut random_spin).unwrap();
+        let square  = PeriodicArray2D::<i8,f64>::new_with(6, 5, &mut random_spin).unwrap();
+        let cube    = PeriodicArray3D::<i8,f64>::new_with(3, 4, 2, &mut random_spin).unwrap();
+        fn pair_sum<L: SpinLattice<i8,f64>>(spins: &L, hamiltonian: &LongRangeIsingHamiltonian<f64>) -> f64
+        {
+            (0..spins.total_number() as usize).map(|n| -0.5_f64 * hamiltonian.interaction_term * spins.at_index(n) as f64 * hamiltonian.local_field(spins, n) + hamiltonian.extern_mag * spins.at_index(n) as f64).sum()
+        }
+        let chain_hamiltonian  = LongRangeIsingHamiltonian::new(1_f64, 0.3_f64, 0.5_f64, &[7], 2);
+        let square_hamiltonian = LongRangeIsingHamiltonian::new(-0.7_f64, 0.1_f64, 1.2_f64, &[6, 5], 1);
+        let cube_hamiltonian   = LongRangeIsingHamiltonian::new(1_f64, 0_f64, 0.8_f64, &[3, 4, 2], 1);
+        for (energy, expected) in [(chain_hamiltonian.total_energy(&chain), pair_sum(&chain, &chain_hamiltonian)),
+                                   (square_hamiltonian.total_energy(&square), pair_sum(&square, &square_hamiltonian)),
+                                   (cube_hamiltonian.total_energy(&cube), pair_sum(&cube, &cube_hamiltonian))]
+        {
+            assert!((energy - expected).abs() < 1E-10, "O(N log N) energy {energy} vs pair sum {expected}");
+        }
+
+        // A cluster sweep reports the change of the total energy
+        let mut spins   = square;
+        let mut cluster = LongRangeCluster::new(30);
+        let ferro       = LongRangeIsingHamiltonian::new(1_f64, 0.1_f64, 1.2_f64, &[6, 5], 1);
+        let (energy, spin) = (ferro.total_energy(&spins), spins.sum_observable());
+        let SpinEnergyFluctuation(dS, dE) = cluster.perform_cluster_sweep(&mut spins, &mut rng, 3_f64, &ferro);
+        assert!((energy + dE - ferro.total_energy(&spins)).abs() < 1E-10);
+        assert_eq!(spin + dS, spins.sum_observable());
+    }
+
+    #[test]
+    fn test_long_range_cluster_matches_exact_average()
+    {
+        // 3x3 lattice: average energy over the 2^9 states vs Luijten-Bloete cluster updates
+        let temp        = 15_f64;
+        let hamiltonian = LongRangeIsingHamiltonian::new(1_f64, 0.3_f64, 0.8_f64, &[3, 3], 2);
+        let mut spins   = PeriodicArray2D::<i8,f64>::new_with(3, 3, ising_state::spin_up).unwrap();
+        let (mut z, mut energy_sum) = (0_f64, 0_f64);
+        for state in 0..(1 << 9)
+        {
+            (0..9).for_each(|n| *spins.at_index_mut(n) = if (state >> n) & 1 == 1 {1} else {-1});
+            let energy  = hamiltonian.total_energy(&spins);
+            z          += (-energy / temp).exp();
+            energy_sum += energy * (-energy / temp).exp();
+        }
+        let exact_energy = energy_sum / z;
+
+        let mut rng     = TestRng { state: 3 };
+        let mut cluster = LongRangeCluster::new(9);
+        let mut energy  = hamiltonian.total_energy(&spins);
+        let mut average = 0_f64;
+        for _ in 0..40000
+        {
+            energy  += cluster.perform_cluster_update(&mut spins, &mut rng, temp, &hamiltonian).1;
+            average += energy / 40000_f64;
+        }
+        assert!((average - exact_energy).abs() < 0.02 * exact_energy.abs(), "<E> = {average}, exact: {exact_energy}");
+    }
+}
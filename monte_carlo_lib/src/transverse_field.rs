@@ -0,0 +1,245 @@
+// Transverse-field (quantum) Ising model  H_Q = -J sum_<ij> sz_i sz_j - Gamma sum_i sx_i + h sum_i sz_i  in 1D or 2D,
+// simulated through the Suzuki-Trotter mapping onto a classical model with M copies ("Trotter slices") of the lattice
+// stacked along an extra periodic imaginary-time axis, the slowest axis of the simulated lattice
+// (a chain of L spins becomes a M x L PeriodicArray2D, a Ly x Lx lattice a M x Ly x Lx PeriodicArray3D):
+//      H_cl = -(J/M) sum_k sum_<ij> s_ik s_jk - (K/beta) sum_k sum_i s_ik s_i(k+1) + (h/M) sum_ik s_ik,   K = -ln(tanh(beta Gamma / M)) / 2
+// The imaginary-time coupling depends on beta, hence at_temperature(). The Trotter error is O((beta Gamma / M)^2).
+use super::*;
+use hamiltonian::Hamiltonian;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransverseFieldIsingHamiltonian<P> where P: PhysicalObservable
+{
+    pub interaction_term: P,
+    pub transverse_field: P,
+    pub extern_mag: P,
+    trotter_slices: usize,
+    slice_size: usize,      // number of quantum spins
+    beta: P,
+    time_coupling: P,       // K / beta
+}
+
+impl<P> TransverseFieldIsingHamiltonian<P> where P: PhysicalObservable
+{
+    // The couplings are the ones at temperature 1 until at_temperature() is called
+    pub fn new(interaction_term: P, transverse_field: P, extern_mag: P, trotter_slices: usize, slice_size: usize) -> Self
+    {
+        assert!(trotter_slices >= 2, "Needs at least 2 Trotter slices");
+        assert!(transverse_field.is_sign_positive() && !transverse_field.is_zero(), "The transverse field needs to be > 0 (Gamma = 0 is the classical model)");
+        let hamiltonian = Self { interaction_term, transverse_field, extern_mag, trotter_slices, slice_size, beta: P::one(), time_coupling: P::zero() };
+        hamiltonian.with_beta(P::one())
+    }
+    // Classical couplings at the temperature temp (also available through Hamiltonian::at_temperature())
+    pub fn at_temperature(&self, temp: P) -> Self
+    {
+        self.with_beta(metropolis::get_beta(temp))
+    }
+    fn with_beta(mut self, beta: P) -> Self
+    {
+        let x              = beta * self.transverse_field / self.slices();
+        self.beta          = beta;
+        self.time_coupling = -x.tanh().ln() / (P::from(2.).unwrap() * beta);
+        self
+    }
+    #[inline(always)]
+    fn slices(&self) -> P
+    {
+        P::from(self.trotter_slices).unwrap()
+    }
+    #[inline(always)]
+    pub fn trotter_slices(&self) -> usize
+    {
+        self.trotter_slices
+    }
+    #[inline(always)]
+    pub fn slice_size(&self) -> usize
+    {
+        self.slice_size
+    }
+    // Same spin at the next & previous imaginary time
+    #[inline(always)]
+    fn time_neighbours(&self, n: usize) -> (usize, usize)
+    {
+        let total = self.slice_size * self.trotter_slices;
+        ((n + self.slice_size) % total, (n + total - self.slice_size) % total)
+    }
+}
+
+impl<S, P> Hamiltonian<S, P> for TransverseFieldIsingHamiltonian<P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    #[inline(always)]
+    fn delta_energy<L: SpinLattice<S,P>>(&self, spins: &L, n: usize) -> P
+    {
+        debug_assert_eq!(spins.total_number() as usize, self.slice_size * self.trotter_slices, "Lattice & Trotter slices shapes differ");
+        let (next, previous) = self.time_neighbours(n);
+        let time_sum: P      = (spins.at_index(next) + spins.at_index(previous)).as_();
+        let space_sum        = spins.weighted_neighbour_sum_at_index(n) - time_sum;
+        let spin_n: P        = spins.at_index(n).as_();
+
+        P::from(2.).unwrap() * spin_n * (self.interaction_term/self.slices()*space_sum + self.time_coupling*time_sum - self.extern_mag/self.slices())
+    }
+    fn total_energy<L: SpinLattice<S,P>>(&self, spins: &L) -> P
+    {
+        let mut total_energy = P::zero();
+        for n in 0..spins.total_number() as usize
+        {
+            let (next, _)   = self.time_neighbours(n);
+            let time: P     = spins.at_index(next).as_();
+            let space       = spins.weighted_forward_neighbour_sum_at_index(n) - time;
+            let spin        = spins.at_index(n).as_();
+            total_energy   += - spin*(self.interaction_term/self.slices()*space + self.time_coupling*time - self.extern_mag/self.slices());
+        }
+        total_energy
+    }
+    // Couplings of the classical model: per slice spatial coupling & field
+    #[inline(always)]
+    fn interaction_term(&self) -> P
+    {
+        self.interaction_term / self.slices()
+    }
+    #[inline(always)]
+    fn extern_mag(&self) -> P
+    {
+        self.extern_mag / self.slices()
+    }
+    #[inline(always)]
+    fn set_extern_mag(&mut self, extern_mag: P)
+    {
+        self.extern_mag = extern_mag * self.slices();
+    }
+    #[inline(always)]
+    fn bond_coupling(&self, n: usize, m: usize, weight: P) -> P
+    {
+        let (next, previous) = self.time_neighbours(n);
+        match m == next || m == previous
+        {
+            true  => self.time_coupling * weight,
+            false => self.interaction_term / self.slices() * weight,
+        }
+    }
+    fn at_temperature(&self, temp: P) -> Self
+    {
+        TransverseFieldIsingHamiltonian::at_temperature(self, temp)
+    }
+    // <H_Q> = -d ln Z / d beta:
+    //      -(J/M) <sum_k sum_<ij> s_ik s_jk> + (h/M) <sum s> - N Gamma coth(2 beta Gamma/M) + Gamma/(M sinh(2 beta Gamma/M)) <sum_k sum_i s_ik s_i(k+1)>
+    fn measured_energy<L: SpinLattice<S,P>>(&self, spins: &L, _total_energy: P) -> P
+    {
+        let two_x       = P::from(2.).unwrap() * self.beta * self.transverse_field / self.slices();
+        let time_factor = self.transverse_field / (self.slices() * two_x.sinh());
+        let mut energy  = -P::from(self.slice_size).unwrap() * self.transverse_field / two_x.tanh();
+        for n in 0..spins.total_number() as usize
+        {
+            let (next, _)   = self.time_neighbours(n);
+            let time: P     = spins.at_index(next).as_();
+            let space       = spins.weighted_forward_neighbour_sum_at_index(n) - time;
+            let spin: P     = spins.at_index(n).as_();
+            energy         += spin*(-self.interaction_term/self.slices()*space + time_factor*time + self.extern_mag/self.slices());
+        }
+        energy
+    }
+    // Magnetisation of the quantum spins: average over the slices
+    #[inline(always)]
+    fn measured_spin_sum(&self, spin_sum: P) -> P
+    {
+        spin_sum / self.slices()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use test_utils::TestRng;
+    use periodic_array_2d_lib::{PeriodicArray2D, PeriodicArray3D};
+
+    // <H> = Tr(H exp(-beta H)) / Tr(exp(-beta H)) for the periodic chain of 3 quantum spins, exp by scaling & squaring
+    fn exact_transverse_field_chain_energy(interaction_term: f64, transverse_field: f64, beta: f64) -> f64
+    {
+        const STATES: usize = 8;
+        let product  = |a: &Vec<Vec<f64>>, b: &Vec<Vec<f64>>| (0..STATES).map(|i| (0..STATES).map(|j| (0..STATES).map(|k| a[i][k]*b[k][j]).sum()).collect()).collect::<Vec<Vec<f64>>>();
+        let sz       = |state: usize, i: usize| if (state >> i) & 1 == 1 {1_f64} else {-1_f64};
+        let mut h    = vec![vec![0_f64; STATES]; STATES];
+        for state in 0..STATES
+        {
+            h[state][state] = -interaction_term * (0..3).map(|i| sz(state, i) * sz(state, (i + 1) % 3)).sum::<f64>();
+            (0..3).for_each(|i| h[state][state ^ (1 << i)] = -transverse_field);
+        }
+        let squarings  = 10;
+        let scaled     = h.iter().map(|row| row.iter().map(|x| -beta * x / (1 << squarings) as f64).collect()).collect::<Vec<Vec<f64>>>();
+        let mut term   = (0..STATES).map(|i| (0..STATES).map(|j| if i == j {1_f64} else {0_f64}).collect()).collect::<Vec<Vec<f64>>>();
+        let mut exp_h  = term.clone();
+        for order in 1..15
+        {
+            term  = product(&term, &scaled).iter().map(|row| row.iter().map(|x| x / order as f64).collect()).collect();
+            exp_h = exp_h.iter().zip(&term).map(|(a, b)| a.iter().zip(b).map(|(x, y)| x + y).collect()).collect();
+        }
+        for _ in 0..squarings
+        {
+            exp_h = product(&exp_h, &exp_h);
+        }
+        let h_exp_h = product(&h, &exp_h);
+        (0..STATES).map(|i| h_exp_h[i][i]).sum::<f64>() / (0..STATES).map(|i| exp_h[i][i]).sum::<f64>()
+    }
+
+    #[test]
+    fn test_transverse_field_free_spins()
+    {
+        // J = 0: the Trotter decomposition is exact & E/N = -Gamma tanh(beta Gamma)
+        let mut rng     = TestRng { state: 8 };
+        let temp        = 0.5_f64;
+        let hamiltonian = TransverseFieldIsingHamiltonian::new(0_f64, 1_f64, 0_f64, 8, 4).at_temperature(temp);
+        let mut spins   = PeriodicArray2D::<i8,f64>::new_with(8, 4, ising_state::spin_up).unwrap();
+        let mut energy  = 0_f64;
+        for step in 0..20000
+        {
+            metropolis::perform_metropolis_sweep(&mut spins, &mut rng, temp, &hamiltonian);
+            if step >= 1000
+            {
+                energy += hamiltonian.measured_energy(&spins, 0_f64) / 4_f64 / 19000_f64;
+            }
+        }
+        let exact = -(1_f64 / temp).tanh();
+        assert!((energy - exact).abs() < 0.01, "E/N = {energy}, exact: {exact}");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_transverse_field_energy_fluctuations()
+    {
+        let mut rng     = TestRng { state: 21 };
+        let hamiltonian = TransverseFieldIsingHamiltonian::new(1_f64, 0.7_f64, 0.2_f64, 6, 16).at_temperature(1.5_f64);
+        let mut spins   = PeriodicArray3D::<i8,f64>::new_with(6, 4, 4, ||if rng.next().is_multiple_of(2) {1} else {-1}).unwrap();
+        let mut cluster = wolff::WolffCluster::new(spins.total_number() as usize);
+        let mut energy  = hamiltonian.total_energy(&spins);
+        for _ in 0..20
+        {
+            energy += metropolis::perform_metropolis_sweep(&mut spins, &mut rng, 1.5_f64, &hamiltonian).1;
+            energy += cluster.perform_wolff_update(&mut spins, &mut rng, 1.5_f64, &hamiltonian).1;
+        }
+        let expected = hamiltonian.total_energy(&spins);
+        assert!((energy - expected).abs() < 1E-9, "accumulated dE {energy} vs total energy {expected}");
+    }
+
+    #[test]
+    fn test_transverse_field_chain_matches_exact_diagonalisation()
+    {
+        let temp        = 1_f64;
+        let exact       = exact_transverse_field_chain_energy(1_f64, 1_f64, 1_f64 / temp);
+        let hamiltonian = TransverseFieldIsingHamiltonian::new(1_f64, 1_f64, 0_f64, 32, 3).at_temperature(temp);
+        let mut rng     = TestRng { state: 17 };
+        let mut spins   = PeriodicArray2D::<i8,f64>::new_with(32, 3, ising_state::spin_up).unwrap();
+        let mut cluster = wolff::WolffCluster::new(spins.total_number() as usize);
+        let mut energy  = 0_f64;
+        for step in 0..21000
+        {
+            cluster.perform_wolff_sweep(&mut spins, &mut rng, temp, &hamiltonian);
+            if step >= 1000
+            {
+                energy += hamiltonian.measured_energy(&spins, 0_f64) / 20000_f64;
+            }
+        }
+        assert!((energy - exact).abs() < 0.01 * exact.abs(), "<H> = {energy}, exact: {exact}");
+    }
+}
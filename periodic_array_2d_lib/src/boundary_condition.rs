@@ -0,0 +1,51 @@
+use crate::PeriodicArrayError;
+
+// Boundary conditions along one axis of the 2D array:
+// * Periodic:     the usual torus, i = -1 is i = L-1
+// * Open:         no neighbour outside the lattice (free surface)
+// * FixedUp/Down: the neighbours outside the lattice are frozen spins +1/-1
+// * Antiperiodic: periodic, but the bonds crossing the boundary change sign (inserts an interface)
+// * Helical:      crossing the boundary shifts the other coordinate by one, ie the sites form one long chain n = i*columns + j
+//                 (j*rows + i for the rows), which closes on itself mod N when both axes are helical
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryCondition
+{
+    #[default]
+    Periodic,
+    Open,
+    FixedUp,
+    FixedDown,
+    Antiperiodic,
+    Helical,
+}
+
+// What sits at a (possibly outside) neighbouring position: a spin of the array (with the sign of the bond), a frozen boundary spin, or nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighbourSite<S>
+{
+    Site(usize, S),
+    Fixed(S),
+    Absent,
+}
+
+impl std::str::FromStr for BoundaryCondition
+{
+    type Err = PeriodicArrayError;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "periodic"            => Ok(BoundaryCondition::Periodic),
+            "open" | "free"       => Ok(BoundaryCondition::Open),
+            "fixed_up"            => Ok(BoundaryCondition::FixedUp),
+            "fixed_down"          => Ok(BoundaryCondition::FixedDown),
+            "antiperiodic"        => Ok(BoundaryCondition::Antiperiodic),
+            "helical"             => Ok(BoundaryCondition::Helical),
+            _ => Err(PeriodicArrayError
+            {
+                from: String::from("BoundaryCondition::from_str()"),
+                message: format!("Unknown boundary condition \"{s}\", expected periodic, open, fixed_up, fixed_down, antiperiodic or helical.")
+            })
+        }
+    }
+}
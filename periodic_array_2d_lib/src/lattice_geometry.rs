@@ -0,0 +1,93 @@
+use crate::PeriodicArrayError;
+
+// Different 2D lattices are all embedded in the same rectangular (rows x columns) periodic array, only the neighbour offsets change:
+// * Square:      4 neighbours, up/down/left/right
+// * Triangular:  6 neighbours, the square ones + one diagonal ((i-1, j+1) & (i+1, j-1))
+// * Honeycomb:   3 neighbours, "brick wall" representation: left/right + up or down depending on the parity of i+j
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LatticeGeometry
+{
+    #[default]
+    Square,
+    Triangular,
+    Honeycomb,
+}
+
+const SQUARE_NEIGHBOURS: [(i32, i32); 4]       = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const TRIANGULAR_NEIGHBOURS: [(i32, i32); 6]   = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, 1), (1, -1)];
+const HONEYCOMB_EVEN_NEIGHBOURS: [(i32, i32); 3] = [(0, -1), (0, 1), (1, 0)];
+const HONEYCOMB_ODD_NEIGHBOURS: [(i32, i32); 3]  = [(0, -1), (0, 1), (-1, 0)];
+
+// "Forward" neighbours: every bond appears exactly once when looping over all sites (used for the total energy)
+const SQUARE_FORWARD: [(i32, i32); 2]          = [(1, 0), (0, 1)];
+const TRIANGULAR_FORWARD: [(i32, i32); 3]      = [(1, 0), (0, 1), (1, -1)];
+const HONEYCOMB_EVEN_FORWARD: [(i32, i32); 2]  = [(0, 1), (1, 0)];
+const HONEYCOMB_ODD_FORWARD: [(i32, i32); 1]   = [(0, 1)];
+
+impl LatticeGeometry
+{
+    #[inline(always)]
+    pub fn coordination_number(&self) -> usize
+    {
+        match self
+        {
+            LatticeGeometry::Square     => 4,
+            LatticeGeometry::Triangular => 6,
+            LatticeGeometry::Honeycomb  => 3,
+        }
+    }
+    #[inline(always)]
+    pub fn neighbour_offsets(&self, i: i32, j: i32) -> &'static [(i32, i32)]
+    {
+        match self
+        {
+            LatticeGeometry::Square     => &SQUARE_NEIGHBOURS,
+            LatticeGeometry::Triangular => &TRIANGULAR_NEIGHBOURS,
+            LatticeGeometry::Honeycomb if (i + j) % 2 == 0 => &HONEYCOMB_EVEN_NEIGHBOURS,
+            LatticeGeometry::Honeycomb  => &HONEYCOMB_ODD_NEIGHBOURS,
+        }
+    }
+    #[inline(always)]
+    pub fn forward_offsets(&self, i: i32, j: i32) -> &'static [(i32, i32)]
+    {
+        match self
+        {
+            LatticeGeometry::Square     => &SQUARE_FORWARD,
+            LatticeGeometry::Triangular => &TRIANGULAR_FORWARD,
+            LatticeGeometry::Honeycomb if (i + j) % 2 == 0 => &HONEYCOMB_EVEN_FORWARD,
+            LatticeGeometry::Honeycomb  => &HONEYCOMB_ODD_FORWARD,
+        }
+    }
+    // The brick wall only closes onto itself (and stays bipartite) for an even number of rows & columns
+    pub fn check_shape(&self, rows: i32, columns: i32) -> Result<(), PeriodicArrayError>
+    {
+        if *self == LatticeGeometry::Honeycomb && (rows % 2 != 0 || columns % 2 != 0)
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("LatticeGeometry::check_shape()"),
+                message: String::from("Honeycomb lattice needs an even number of rows & columns.")
+            })
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for LatticeGeometry
+{
+    type Err = PeriodicArrayError;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "square"     => Ok(LatticeGeometry::Square),
+            "triangular" => Ok(LatticeGeometry::Triangular),
+            "honeycomb"  => Ok(LatticeGeometry::Honeycomb),
+            _ => Err(PeriodicArrayError
+            {
+                from: String::from("LatticeGeometry::from_str()"),
+                message: format!("Unknown lattice \"{s}\", expected square, triangular or honeycomb.")
+            })
+        }
+    }
+}
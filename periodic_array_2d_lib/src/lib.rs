@@ -1,9 +1,21 @@
 pub mod array_rng_interface;
+pub mod lattice_geometry;
+pub mod boundary_condition;
+pub mod spin_lattice;
+pub mod periodic_array_3d;
+pub mod topology;
+pub mod spin_graph;
 use std::marker::PhantomData;
 
 
 use std::{ops::AddAssign};
 pub use array_rng_interface::ArrayRngInterface;
+pub use lattice_geometry::LatticeGeometry;
+pub use boundary_condition::{BoundaryCondition, NeighbourSite};
+pub use spin_lattice::SpinLattice;
+pub use periodic_array_3d::PeriodicArray3D;
+pub use topology::{Bond, NeighbourTable};
+pub use spin_graph::SpinGraph;
 use num_traits::{AsPrimitive, Float, FromPrimitive, Num};
 
 
@@ -32,6 +44,10 @@ pub struct PeriodicArray2D<S, P> where S: SpinValue<P>, P: PhysicalObservable
     rows: i32,
     columns: i32,
     number_of_spins: i32,
+    geometry: LatticeGeometry,
+    row_boundary: BoundaryCondition,
+    column_boundary: BoundaryCondition,
+    topology: NeighbourTable<S>,
     _phantom: PhantomData<P> // The PeriodicArray should "know" that it can readily convert its values to <P>, therefor keep a zero-sized phantom data, to be able to "store" the generic <P> parameter
 }
 
@@ -71,6 +87,18 @@ impl From <&str> for PeriodicArrayError
     }
 }
 
+impl From <std::io::Error> for PeriodicArrayError  
+{
+    fn from(error: std::io::Error) -> Self
+    {
+        PeriodicArrayError 
+        {
+            from: String::from("std::io::Error"),
+            message: error.to_string(),
+        }
+    }
+}
+
 //helper function: modulo without % operator
 trait MonteCarloModulo
 {
@@ -146,15 +174,72 @@ impl<S,P> PeriodicArray2D<S,P> where S: SpinValue<P>, P: PhysicalObservable
     {
         0..self.number_of_spins
     }
+    #[inline(always)]
+    pub fn geometry(&self) -> LatticeGeometry
+    {
+        self.geometry
+    }
+    // (rows axis, columns axis)
+    #[inline(always)]
+    pub fn boundary_conditions(&self) -> (BoundaryCondition, BoundaryCondition)
+    {
+        (self.row_boundary, self.column_boundary)
+    }
+    pub fn set_boundary_conditions(&mut self, row_boundary: BoundaryCondition, column_boundary: BoundaryCondition) -> Result<(), PeriodicArrayError>
+    {
+        // Going through a helical boundary shifts the other coordinate by one, which breaks the parity pattern of the brick wall
+        if self.geometry == LatticeGeometry::Honeycomb && (row_boundary == BoundaryCondition::Helical || column_boundary == BoundaryCondition::Helical)
+        {
+            return Err(PeriodicArrayError 
+            {
+                from: String::from("PeriodicArray2D::set_boundary_conditions()"),
+                message: String::from("Honeycomb lattice can't have helical boundary conditions.")
+            })
+        }
+        self.row_boundary    = row_boundary;
+        self.column_boundary = column_boundary;
+        self.topology        = self.build_topology();
+        Ok(())
+    }
     pub fn new_with(rows: i32, columns: i32, generator: impl FnMut()-> S) -> Result<Self, PeriodicArrayError>
+    {        
+        Self::new_with_geometry(rows, columns, LatticeGeometry::Square, generator)
+    }    
+    pub fn new_with_geometry(rows: i32, columns: i32, geometry: LatticeGeometry, generator: impl FnMut()-> S) -> Result<Self, PeriodicArrayError>
     {        
         let n_elements: usize  = Self::get_total_elements_usize(rows, columns)?;
+        geometry.check_shape(rows, columns)?;
         
         let mut data: Vec<S> = vec![S::zero(); n_elements];
         data.fill_with(generator);
 
-        Ok(PeriodicArray2D {data, rows, columns, number_of_spins: n_elements as i32, _phantom: PhantomData})
+        let mut spin_2d_arr = PeriodicArray2D 
+        {
+            data, rows, columns, number_of_spins: n_elements as i32, geometry,
+            row_boundary:    BoundaryCondition::Periodic,
+            column_boundary: BoundaryCondition::Periodic,
+            topology:        NeighbourTable::from_fn(0, |_| (Vec::new(), Vec::new())),
+            _phantom:        PhantomData
+        };
+        spin_2d_arr.topology = spin_2d_arr.build_topology();
+        Ok(spin_2d_arr)
     }    
+    // Resolve the neighbours of every site once (geometry + boundary conditions), so that the hot loops only read the table
+    fn build_topology(&self) -> NeighbourTable<S>
+    {
+        NeighbourTable::from_fn(self.number_of_spins as usize, |n|
+        {
+            let (i, j)  = (n as i32 / self.columns, n as i32 % self.columns);
+            let all     = self.geometry.neighbour_offsets(i, j).iter().map(|&(di, dj)| self.resolve_neighbour(i+di, j+dj)).collect();
+            let forward = self.geometry.forward_offsets(i, j).iter().map(|&(di, dj)| self.resolve_neighbour(i+di, j+dj)).collect();
+            (all, forward)
+        })
+    }
+    #[inline(always)]
+    pub fn topology(&self) -> &NeighbourTable<S>
+    {
+        &self.topology
+    }
     #[inline(always)]
     fn get_index(&self, i: i32, j: i32) -> usize
     {
@@ -178,6 +263,65 @@ impl<S,P> PeriodicArray2D<S,P> where S: SpinValue<P>, P: PhysicalObservable
         let index = self.get_index(i,j);
         &mut self.data[index]
     }
+    // Where the neighbour at (i,j) (possibly outside the array) really is, according to the boundary conditions.
+    // A helical axis makes the sites one chain (row major for helical columns, column major for helical rows): a step out of the array is carried
+    // into the other coordinate, as in an index n +- 1, n +- columns, and the other boundary only applies where the chain itself ends.
+    // With both axes helical the chain n = i*columns + j closes on itself (mod N), so that every bond is resolved the same way from both ends.
+    // NB: at_unchecked() & at_mut_unchecked() always wrap periodically, this is the function which knows about the boundaries.
+    #[inline(always)]
+    pub fn resolve_neighbour(&self, i: i32, j: i32) -> NeighbourSite<S>
+    {
+        let (mut i, mut j) = (i, j);
+        let mut sign       = S::one();
+        if self.column_boundary == BoundaryCondition::Helical
+        {
+            (i, j) = (i + j.div_euclid(self.columns), j.rem_euclid(self.columns));
+            if self.row_boundary == BoundaryCondition::Helical
+            {
+                i = i.rem_euclid(self.rows);
+            }
+        }
+        else if self.row_boundary == BoundaryCondition::Helical
+        {
+            (i, j) = (i.rem_euclid(self.rows), j + i.div_euclid(self.rows));
+        }
+        // Columns first: a corner outside both axes takes the column boundary
+        for (coordinate, size, boundary) in [(&mut j, self.columns, self.column_boundary), (&mut i, self.rows, self.row_boundary)]
+        {
+            if *coordinate < 0 || *coordinate >= size
+            {
+                match boundary
+                {
+                    BoundaryCondition::Periodic     => {},
+                    BoundaryCondition::Open         => return NeighbourSite::Absent,
+                    BoundaryCondition::FixedUp      => return NeighbourSite::Fixed(S::one()),
+                    BoundaryCondition::FixedDown    => return NeighbourSite::Fixed(-S::one()),
+                    BoundaryCondition::Antiperiodic => sign = -sign,
+                    BoundaryCondition::Helical      => unreachable!("helical coordinates are carried above"),
+                }
+                *coordinate = coordinate.modulo(size);
+            }
+        }
+        NeighbourSite::Site((i*self.columns + j) as usize, sign)
+    }
+    // Sum of all the neighbouring spins of (i,j), according to the lattice geometry & boundary conditions (frozen boundary spins included)
+    #[inline(always)]
+    pub fn neighbour_sum(&self, i: i32, j: i32) -> S
+    {
+        SpinLattice::neighbour_sum_at_index(self, self.get_index(i, j))
+    }
+    // Same, but only over the "forward" neighbours inside the array, so that summing s_ij * forward_neighbour_sum(i,j) counts each bond once
+    #[inline(always)]
+    pub fn forward_neighbour_sum(&self, i: i32, j: i32) -> S
+    {
+        SpinLattice::forward_neighbour_sum_at_index(self, self.get_index(i, j))
+    }
+    // Sum of the frozen boundary spins next to (i,j): they act as a local magnetic field J*b_ij
+    #[inline(always)]
+    pub fn boundary_field(&self, i: i32, j: i32) -> S
+    {
+        self.topology.boundary_field(self.get_index(i, j))
+    }
     #[inline(always)]
     pub fn sum(&self) -> S
     {
@@ -194,3 +338,37 @@ impl<S,P> PeriodicArray2D<S,P> where S: SpinValue<P>, P: PhysicalObservable
         (x / self.columns, x % self.columns )
     }
 }
+
+impl<S,P> SpinLattice<S,P> for PeriodicArray2D<S,P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    #[inline(always)]
+    fn total_number(&self) -> i32
+    {
+        self.number_of_spins
+    }
+    #[inline(always)]
+    fn columns(&self) -> i32
+    {
+        self.columns
+    }
+    #[inline(always)]
+    fn at_index(&self, n: usize) -> S
+    {
+        self.data[n]
+    }
+    #[inline(always)]
+    fn at_index_mut(&mut self, n: usize) -> &mut S
+    {
+        &mut self.data[n]
+    }
+    #[inline(always)]
+    fn topology(&self) -> &NeighbourTable<S>
+    {
+        &self.topology
+    }
+    #[inline(always)]
+    fn sum_observable(&self) -> P
+    {
+        PeriodicArray2D::sum_observable(self)
+    }
+}
@@ -0,0 +1,200 @@
+use std::marker::PhantomData;
+
+use crate::{ArrayRngInterface, MonteCarloModulo, NeighbourSite, NeighbourTable, PeriodicArrayError, PhysicalObservable, SpinLattice, SpinValue};
+
+// Simple cubic version of PeriodicArray2D: (layers, rows, columns) = (z, y, x), stored layer after layer.
+pub struct PeriodicArray3D<S, P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    data: Vec<S>,
+    layers: i32,
+    rows: i32,
+    columns: i32,
+    number_of_spins: i32,
+    topology: NeighbourTable<S>,
+    _phantom: PhantomData<P>
+}
+
+const CUBIC_NEIGHBOURS: [(i32, i32, i32); 6] = [(-1, 0, 0), (1, 0, 0), (0, -1, 0), (0, 1, 0), (0, 0, -1), (0, 0, 1)];
+const CUBIC_FORWARD: [(i32, i32, i32); 3]    = [(1, 0, 0), (0, 1, 0), (0, 0, 1)];
+
+impl<S,P> PeriodicArray3D<S,P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    fn get_total_elements_usize(layers: i32, rows: i32, columns: i32) -> Result<usize, PeriodicArrayError>
+    {
+        if layers <= 0 || rows <= 0 || columns <= 0
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("PeriodicArray3D::new()"),
+                message: String::from("Layers, rows & columns need to be > 0.")
+            })
+        };
+        let n_elements: usize = (layers*rows*columns) as usize;
+        Ok(n_elements)
+    }
+    #[inline(always)]
+    pub fn layers(&self) -> i32
+    {
+        self.layers
+    }
+    #[inline(always)]
+    pub fn rows(&self) -> i32
+    {
+        self.rows
+    }
+    #[inline(always)]
+    pub fn columns(&self) -> i32
+    {
+        self.columns
+    }
+    #[inline(always)]
+    pub fn shape(&self) -> (i32, i32, i32)
+    {
+        (self.layers, self.rows, self.columns)
+    }
+    #[inline(always)]
+    pub fn layers_range(&self) -> std::ops::Range<i32>
+    {
+        0..self.layers()
+    }
+    #[inline(always)]
+    pub fn rows_range(&self) -> std::ops::Range<i32>
+    {
+        0..self.rows()
+    }
+    #[inline(always)]
+    pub fn columns_range(&self) -> std::ops::Range<i32>
+    {
+        0..self.columns()
+    }
+    #[inline(always)]
+    pub fn total_number(&self) -> i32
+    {
+        self.number_of_spins
+    }
+    #[inline(always)]
+    pub fn all_range(&self) -> std::ops::Range<i32>
+    {
+        0..self.number_of_spins
+    }
+    pub fn new_with(layers: i32, rows: i32, columns: i32, generator: impl FnMut()-> S) -> Result<Self, PeriodicArrayError>
+    {
+        let n_elements: usize  = Self::get_total_elements_usize(layers, rows, columns)?;
+
+        let mut data: Vec<S> = vec![S::zero(); n_elements];
+        data.fill_with(generator);
+
+        let mut spin_3d_arr = PeriodicArray3D 
+        {
+            data, layers, rows, columns, number_of_spins: n_elements as i32,
+            topology: NeighbourTable::from_fn(0, |_| (Vec::new(), Vec::new())),
+            _phantom: PhantomData
+        };
+        spin_3d_arr.topology = spin_3d_arr.build_topology();
+        Ok(spin_3d_arr)
+    }
+    fn build_topology(&self) -> NeighbourTable<S>
+    {
+        NeighbourTable::from_fn(self.number_of_spins as usize, |n|
+        {
+            let (k, i, j) = self.get_coordinates(n);
+            let site      = |&(dk, di, dj): &(i32, i32, i32)| NeighbourSite::Site(self.get_index(k+dk, i+di, j+dj), S::one());
+            (CUBIC_NEIGHBOURS.iter().map(site).collect(), CUBIC_FORWARD.iter().map(site).collect())
+        })
+    }
+    #[inline(always)]
+    fn get_index(&self, k: i32, i: i32, j: i32) -> usize
+    {
+        let k = k.modulo(self.layers);
+        let i = i.modulo(self.rows);
+        let j = j.modulo(self.columns);
+        ((k*self.rows + i)*self.columns + j) as usize
+    }
+    #[inline(always)]
+    fn get_coordinates(&self, n: usize) -> (i32, i32, i32)
+    {
+        let n = n as i32;
+        let layer_size = self.rows*self.columns;
+        (n / layer_size, (n % layer_size) / self.columns, n % self.columns)
+    }
+    #[inline(always)]
+    pub fn reset(&mut self, generator: impl FnMut()-> S)
+    {
+        self.data.fill_with(generator);
+    }
+    #[inline(always)]
+    pub fn at_unchecked(&self, k: i32, i: i32, j: i32) -> S
+    {
+        self.data[self.get_index(k,i,j)]
+    }
+    #[inline(always)]
+    pub fn at_mut_unchecked(&mut self, k: i32, i: i32, j: i32) -> &mut S
+    {
+        let index = self.get_index(k,i,j);
+        &mut self.data[index]
+    }
+    #[inline(always)]
+    pub fn neighbour_sum(&self, k: i32, i: i32, j: i32) -> S
+    {
+        SpinLattice::neighbour_sum_at_index(self, self.get_index(k, i, j))
+    }
+    #[inline(always)]
+    pub fn forward_neighbour_sum(&self, k: i32, i: i32, j: i32) -> S
+    {
+        SpinLattice::forward_neighbour_sum_at_index(self, self.get_index(k, i, j))
+    }
+    #[inline(always)]
+    pub fn sum(&self) -> S
+    {
+        self.data.iter().fold(S::zero(), |acc, &x| acc + x)
+    }
+    #[inline(always)]
+    pub fn sum_observable(&self) -> P
+    {
+        self.data.iter().fold(P::default(), |acc, &x| acc + x.as_())
+    }
+    pub fn get_random_point<R: ArrayRngInterface>(&self, rng: &mut R) -> (i32, i32, i32)
+    {
+        let x: i32 = rng.generate_rand_i32(0, self.number_of_spins);
+        self.get_coordinates(x as usize)
+    }
+}
+
+impl<S,P> SpinLattice<S,P> for PeriodicArray3D<S,P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    #[inline(always)]
+    fn total_number(&self) -> i32
+    {
+        self.number_of_spins
+    }
+    #[inline(always)]
+    fn columns(&self) -> i32
+    {
+        self.columns
+    }
+    #[inline(always)]
+    fn rows(&self) -> i32
+    {
+        self.rows
+    }
+    #[inline(always)]
+    fn at_index(&self, n: usize) -> S
+    {
+        self.data[n]
+    }
+    #[inline(always)]
+    fn at_index_mut(&mut self, n: usize) -> &mut S
+    {
+        &mut self.data[n]
+    }
+    #[inline(always)]
+    fn topology(&self) -> &NeighbourTable<S>
+    {
+        &self.topology
+    }
+    #[inline(always)]
+    fn sum_observable(&self) -> P
+    {
+        PeriodicArray3D::sum_observable(self)
+    }
+}
@@ -0,0 +1,207 @@
+use std::marker::PhantomData;
+
+use crate::{NeighbourSite, NeighbourTable, PeriodicArrayError, PhysicalObservable, SpinLattice, SpinValue};
+
+// Spins living on the nodes of an arbitrary graph (random regular graphs, small worlds, connectomes...).
+// Every edge (a, b, w) couples the two nodes with J_ab = w*J, w = 1 for unweighted edge lists.
+#[derive(Debug, Clone)]
+pub struct SpinGraph<S, P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    data: Vec<S>,
+    topology: NeighbourTable<S>,
+    weights: Vec<P>,          // aligned with topology.neighbours()
+    forward_weights: Vec<P>,  // aligned with topology.forward_neighbours()
+    _phantom: PhantomData<P>
+}
+
+impl<S,P> SpinGraph<S,P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    pub fn new_with(number_of_nodes: usize, edges: &[(usize, usize, P)], generator: impl FnMut()-> S) -> Result<Self, PeriodicArrayError>
+    {
+        if number_of_nodes == 0 || number_of_nodes > i32::MAX as usize
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("SpinGraph::new_with()"),
+                message: String::from("Number of nodes needs to be > 0 and fit in an i32.")
+            })
+        }
+        if let Some(&(a, b, _)) = edges.iter().find(|&&(a, b, _)| a >= number_of_nodes || b >= number_of_nodes)
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("SpinGraph::new_with()"),
+                message: format!("Edge ({a}, {b}) out of range for {number_of_nodes} nodes.")
+            })
+        }
+        // s_a^2 = 1: a self-coupling would only shift the energy, but the local fields would count it twice
+        if let Some(&(a, _, _)) = edges.iter().find(|&&(a, b, _)| a == b)
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("SpinGraph::new_with()"),
+                message: format!("Self-loop ({a}, {a}): a node can't be coupled to itself.")
+            })
+        }
+
+        let mut all     = vec![Vec::new(); number_of_nodes];
+        let mut forward = vec![Vec::new(); number_of_nodes];
+        for &(a, b, w) in edges
+        {
+            all[a].push((b, w));
+            all[b].push((a, w));
+            forward[a].push((b, w));
+        }
+        let weights         = all.iter().flatten().map(|&(_, w)| w).collect();
+        let forward_weights = forward.iter().flatten().map(|&(_, w)| w).collect();
+        let as_sites        = |list: &Vec<(usize, P)>| list.iter().map(|&(m, _)| NeighbourSite::Site(m, S::one())).collect();
+        let topology        = NeighbourTable::from_fn(number_of_nodes, |n| (as_sites(&all[n]), as_sites(&forward[n])));
+
+        let mut data: Vec<S> = vec![S::zero(); number_of_nodes];
+        data.fill_with(generator);
+
+        Ok(SpinGraph {data, topology, weights, forward_weights, _phantom: PhantomData})
+    }
+    // Edge list file: one edge per line "a b" or "a b weight", nodes numbered from 0, '#' starts a comment.
+    // The number of nodes is the largest node index + 1.
+    pub fn from_edge_list_file(file_name: &str, generator: impl FnMut()-> S) -> Result<Self, PeriodicArrayError>
+    {
+        let content   = std::fs::read_to_string(file_name)?;
+        let mut edges = Vec::new();
+        for (line_number, line) in content.lines().enumerate()
+        {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty()
+            {
+                continue;
+            }
+            let parse_error = || PeriodicArrayError
+            {
+                from: String::from("SpinGraph::from_edge_list_file()"),
+                message: format!("Could not parse line {}: \"{line}\"", line_number + 1)
+            };
+            let fields: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == ',').filter(|f| !f.is_empty()).collect();
+            let (a, b, w): (usize, usize, P) = match fields.as_slice()
+            {
+                [a, b]    => (a.parse().map_err(|_| parse_error())?, b.parse().map_err(|_| parse_error())?, P::one()),
+                [a, b, w] => (a.parse().map_err(|_| parse_error())?, b.parse().map_err(|_| parse_error())?, w.parse::<f64>().ok().and_then(P::from).ok_or_else(parse_error)?),
+                _         => return Err(parse_error()),
+            };
+            edges.push((a, b, w));
+        }
+        let number_of_nodes = edges.iter().map(|&(a, b, _)| a.max(b) + 1).max().unwrap_or(0);
+        Self::new_with(number_of_nodes, &edges, generator)
+    }
+    #[inline(always)]
+    pub fn number_of_nodes(&self) -> usize
+    {
+        self.data.len()
+    }
+    #[inline(always)]
+    pub fn number_of_edges(&self) -> usize
+    {
+        self.forward_weights.len()
+    }
+    #[inline(always)]
+    pub fn reset(&mut self, generator: impl FnMut()-> S)
+    {
+        self.data.fill_with(generator);
+    }
+    #[inline(always)]
+    pub fn sum_observable(&self) -> P
+    {
+        self.data.iter().fold(P::default(), |acc, &x| acc + x.as_())
+    }
+}
+
+impl<S,P> SpinLattice<S,P> for SpinGraph<S,P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    #[inline(always)]
+    fn total_number(&self) -> i32
+    {
+        self.data.len() as i32
+    }
+    // No spatial structure: all the nodes are on a single "row"
+    #[inline(always)]
+    fn columns(&self) -> i32
+    {
+        self.data.len() as i32
+    }
+    #[inline(always)]
+    fn at_index(&self, n: usize) -> S
+    {
+        self.data[n]
+    }
+    #[inline(always)]
+    fn at_index_mut(&mut self, n: usize) -> &mut S
+    {
+        &mut self.data[n]
+    }
+    #[inline(always)]
+    fn topology(&self) -> &NeighbourTable<S>
+    {
+        &self.topology
+    }
+    #[inline(always)]
+    fn weighted_neighbour_sum_at_index(&self, n: usize) -> P
+    {
+        let topology = &self.topology;
+        topology.neighbours(n).iter().zip(&self.weights[topology.neighbour_range(n)]).fold(P::zero(), |acc, (bond, &w)| acc + w*self.data[bond.site as usize].as_())
+    }
+    #[inline(always)]
+    fn weighted_forward_neighbour_sum_at_index(&self, n: usize) -> P
+    {
+        let topology = &self.topology;
+        topology.forward_neighbours(n).iter().zip(&self.forward_weights[topology.forward_range(n)]).fold(P::zero(), |acc, (bond, &w)| acc + w*self.data[bond.site as usize].as_())
+    }
+    #[inline(always)]
+    fn for_each_weighted_neighbour_index(&self, n: usize, mut f: impl FnMut(usize, P))
+    {
+        let topology = &self.topology;
+        for (bond, &w) in topology.neighbours(n).iter().zip(&self.weights[topology.neighbour_range(n)])
+        {
+            f(bond.site as usize, w);
+        }
+    }
+    #[inline(always)]
+    fn sum_observable(&self) -> P
+    {
+        SpinGraph::sum_observable(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn test_edge_list_file()
+    {
+        let file_name = std::env::temp_dir().join(format!("spin_graph_test_edges_{}.txt", std::process::id()));
+        std::fs::write(&file_name, "# triangle + weighted tail\n0 1\n1 2\n2 0\n2 3 0.5 # weak bond\n").unwrap();
+        let graph = SpinGraph::<i8,f64>::from_edge_list_file(file_name.to_str().unwrap(), ||1).unwrap();
+        assert_eq!(graph.number_of_nodes(), 4);
+        assert_eq!(graph.number_of_edges(), 4);
+        assert_eq!(graph.weighted_neighbour_sum_at_index(2), 2.5);
+        assert_eq!(graph.weighted_neighbour_sum_at_index(3), 0.5);
+        assert_eq!((0..4).map(|n| graph.weighted_forward_neighbour_sum_at_index(n)).sum::<f64>(), 3.5);
+
+        std::fs::write(&file_name, "0 1\n1 x\n").unwrap();
+        assert!(SpinGraph::<i8,f64>::from_edge_list_file(file_name.to_str().unwrap(), ||1).is_err());
+        std::fs::remove_file(&file_name).unwrap();
+        assert!(SpinGraph::<i8,f64>::new_with(3, &[(0, 3, 1_f64)], ||1).is_err());
+    }
+
+    #[test]
+    fn test_self_loops_are_rejected()
+    {
+        assert!(SpinGraph::<i8,f64>::new_with(3, &[(0, 1, 1_f64), (1, 1, 1_f64)], ||1).is_err());
+        let file_name = std::env::temp_dir().join(format!("spin_graph_test_self_loop_{}.txt", std::process::id()));
+        std::fs::write(&file_name, "0 1\n2 2 0.5\n").unwrap();
+        assert!(SpinGraph::<i8,f64>::from_edge_list_file(file_name.to_str().unwrap(), ||1).is_err());
+        std::fs::remove_file(&file_name).unwrap();
+        assert!(NeighbourTable::<i8>::from_edges(3, &[(0, 1), (2, 2)]).is_err());
+    }
+}
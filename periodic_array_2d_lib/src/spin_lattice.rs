@@ -0,0 +1,69 @@
+use crate::{ArrayRngInterface, NeighbourTable, PhysicalObservable, SpinValue};
+
+// Common interface of the 2D & 3D periodic arrays, so that the Monte-Carlo algorithms don't need to know the dimension.
+// Sites are addressed by their flat index n in the data buffer, the columns being the fastest axis (x = n % columns).
+// Implementors only provide the spins & their NeighbourTable, all the neighbour sums are read from the table.
+pub trait SpinLattice<S, P> where S: SpinValue<P>, P: PhysicalObservable
+{
+    fn total_number(&self) -> i32;
+    fn columns(&self) -> i32;
+    // Rows of one layer: total_number / columns in 2D, 1 for a graph
+    #[inline(always)]
+    fn rows(&self) -> i32
+    {
+        self.total_number() / self.columns()
+    }
+    fn at_index(&self, n: usize) -> S;
+    fn at_index_mut(&mut self, n: usize) -> &mut S;
+    fn topology(&self) -> &NeighbourTable<S>;
+    // All neighbours (bond signs & frozen boundary spins included)
+    #[inline(always)]
+    fn neighbour_sum_at_index(&self, n: usize) -> S
+    {
+        let topology = self.topology();
+        topology.neighbours(n).iter().fold(topology.boundary_field(n), |acc, bond| acc + bond.sign*self.at_index(bond.site as usize))
+    }
+    // Only the forward neighbours inside the lattice, each bond being counted once when summing over n
+    #[inline(always)]
+    fn forward_neighbour_sum_at_index(&self, n: usize) -> S
+    {
+        self.topology().forward_neighbours(n).iter().fold(S::zero(), |acc, bond| acc + bond.sign*self.at_index(bond.site as usize))
+    }
+    // Sum of the frozen spins around n (fixed boundaries), zero for periodic lattices
+    #[inline(always)]
+    fn boundary_field_at_index(&self, n: usize) -> S
+    {
+        self.topology().boundary_field(n)
+    }
+    // Calls f(m, sign) for every neighbour m of n inside the lattice, sign being the sign of the bond (-1 across an antiperiodic boundary)
+    #[inline(always)]
+    fn for_each_neighbour_index(&self, n: usize, mut f: impl FnMut(usize, S))
+    {
+        for bond in self.topology().neighbours(n)
+        {
+            f(bond.site as usize, bond.sign);
+        }
+    }
+    // Weighted versions used by the Hamiltonians: every bond carries a coupling weight w (J_ij = w*J).
+    // Lattices have w = sign, only graphs with weighted edges (see SpinGraph) need to override them.
+    #[inline(always)]
+    fn weighted_neighbour_sum_at_index(&self, n: usize) -> P
+    {
+        self.neighbour_sum_at_index(n).as_()
+    }
+    #[inline(always)]
+    fn weighted_forward_neighbour_sum_at_index(&self, n: usize) -> P
+    {
+        self.forward_neighbour_sum_at_index(n).as_()
+    }
+    #[inline(always)]
+    fn for_each_weighted_neighbour_index(&self, n: usize, mut f: impl FnMut(usize, P))
+    {
+        self.for_each_neighbour_index(n, |m, sign| f(m, sign.as_()));
+    }
+    fn sum_observable(&self) -> P;
+    fn get_random_index<R: ArrayRngInterface>(&self, rng: &mut R) -> usize
+    {
+        rng.generate_rand_i32(0, self.total_number()) as usize
+    }
+}
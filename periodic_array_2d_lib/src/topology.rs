@@ -0,0 +1,208 @@
+use num_traits::Num;
+
+use crate::{NeighbourSite, PeriodicArrayError};
+
+// A bond to the neighbouring site `site`, `sign` being -1 for bonds crossing an antiperiodic boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bond<S>
+{
+    pub site: u32,
+    pub sign: S,
+}
+
+// Precomputed neighbour lists of every site (compressed row storage: the neighbours of n are neighbours[starts[n]..starts[n+1]]).
+// The lattices build it once from their geometry & boundary conditions, so the update loops never have to wrap indices.
+// Any graph can be described this way (see from_edges()).
+#[derive(Debug, Clone)]
+pub struct NeighbourTable<S>
+{
+    neighbours: Vec<Bond<S>>,
+    neighbour_starts: Vec<u32>,
+    forward: Vec<Bond<S>>,
+    forward_starts: Vec<u32>,
+    boundary_field: Vec<S>,
+}
+
+impl<S> NeighbourTable<S> where S: Num + Copy
+{
+    // site_neighbours(n) returns (all neighbours, forward neighbours) of the site n, forward neighbours counting each bond once.
+    pub fn from_fn(number_of_sites: usize, mut site_neighbours: impl FnMut(usize) -> (Vec<NeighbourSite<S>>, Vec<NeighbourSite<S>>)) -> Self
+    {
+        let mut table = Self
+        {
+            neighbours:       Vec::new(),
+            neighbour_starts: vec![0],
+            forward:          Vec::new(),
+            forward_starts:   vec![0],
+            boundary_field:   vec![S::zero(); number_of_sites],
+        };
+        for n in 0..number_of_sites
+        {
+            let (all, forward) = site_neighbours(n);
+            for neighbour in all
+            {
+                match neighbour
+                {
+                    NeighbourSite::Site(m, sign) => table.neighbours.push(Bond { site: m as u32, sign }),
+                    NeighbourSite::Fixed(s)      => table.boundary_field[n] = table.boundary_field[n] + s,
+                    NeighbourSite::Absent        => {},
+                }
+            }
+            for neighbour in forward
+            {
+                if let NeighbourSite::Site(m, sign) = neighbour
+                {
+                    table.forward.push(Bond { site: m as u32, sign });
+                }
+            }
+            table.neighbour_starts.push(table.neighbours.len() as u32);
+            table.forward_starts.push(table.forward.len() as u32);
+        }
+        table
+    }
+    // Arbitrary (undirected) graph, each edge (a, b) being listed once
+    pub fn from_edges(number_of_sites: usize, edges: &[(usize, usize)]) -> Result<Self, PeriodicArrayError>
+    {
+        if let Some(&(a, b)) = edges.iter().find(|&&(a, b)| a >= number_of_sites || b >= number_of_sites)
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("NeighbourTable::from_edges()"),
+                message: format!("Edge ({a}, {b}) out of range for {number_of_sites} sites.")
+            })
+        }
+        if let Some(&(a, _)) = edges.iter().find(|&&(a, b)| a == b)
+        {
+            return Err(PeriodicArrayError
+            {
+                from: String::from("NeighbourTable::from_edges()"),
+                message: format!("Self-loop ({a}, {a}): a site can't be its own neighbour.")
+            })
+        }
+        let mut all     = vec![Vec::new(); number_of_sites];
+        let mut forward = vec![Vec::new(); number_of_sites];
+        for &(a, b) in edges
+        {
+            all[a].push(NeighbourSite::Site(b, S::one()));
+            all[b].push(NeighbourSite::Site(a, S::one()));
+            forward[a].push(NeighbourSite::Site(b, S::one()));
+        }
+        Ok(Self::from_fn(number_of_sites, |n| (std::mem::take(&mut all[n]), std::mem::take(&mut forward[n]))))
+    }
+    #[inline(always)]
+    pub fn number_of_sites(&self) -> usize
+    {
+        self.boundary_field.len()
+    }
+    // Position of the neighbours of n in the flattened table, to attach extra data (ie bond weights) to the bonds
+    #[inline(always)]
+    pub fn neighbour_range(&self, n: usize) -> std::ops::Range<usize>
+    {
+        self.neighbour_starts[n] as usize..self.neighbour_starts[n + 1] as usize
+    }
+    #[inline(always)]
+    pub fn forward_range(&self, n: usize) -> std::ops::Range<usize>
+    {
+        self.forward_starts[n] as usize..self.forward_starts[n + 1] as usize
+    }
+    #[inline(always)]
+    pub fn neighbours(&self, n: usize) -> &[Bond<S>]
+    {
+        &self.neighbours[self.neighbour_range(n)]
+    }
+    #[inline(always)]
+    pub fn forward_neighbours(&self, n: usize) -> &[Bond<S>]
+    {
+        &self.forward[self.forward_range(n)]
+    }
+    #[inline(always)]
+    pub fn boundary_field(&self, n: usize) -> S
+    {
+        self.boundary_field[n]
+    }
+    #[inline(always)]
+    pub fn coordination_number(&self, n: usize) -> usize
+    {
+        self.neighbours(n).len()
+    }
+    #[inline(always)]
+    pub fn number_of_bonds(&self) -> usize
+    {
+        self.forward.len()
+    }
+}
+
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+    use crate::{BoundaryCondition, LatticeGeometry, PeriodicArray2D, PeriodicArray3D, SpinLattice};
+
+    #[test]
+    fn test_lattice_tables()
+    {
+        let square  = PeriodicArray2D::<i8,f64>::new_with(6, 8, ||1).unwrap();
+        let honey   = PeriodicArray2D::<i8,f64>::new_with_geometry(6, 8, LatticeGeometry::Honeycomb, ||1).unwrap();
+        let cubic   = PeriodicArray3D::<i8,f64>::new_with(4, 4, 4, ||1).unwrap();
+        assert!((0..48).all(|n| square.topology().coordination_number(n) == 4));
+        assert_eq!(square.topology().number_of_bonds(), 2*48);
+        assert!((0..48).all(|n| honey.topology().coordination_number(n) == 3));
+        assert_eq!(honey.topology().number_of_bonds(), 3*48/2);
+        assert!((0..64).all(|n| cubic.topology().coordination_number(n) == 6));
+    }
+
+    #[test]
+    fn test_open_boundaries_table()
+    {
+        let mut spins = PeriodicArray2D::<i8,f64>::new_with(4, 4, ||1).unwrap();
+        spins.set_boundary_conditions(BoundaryCondition::Open, BoundaryCondition::FixedDown).unwrap();
+        let table = spins.topology();
+        assert_eq!(table.coordination_number(0), 2, "corner");
+        assert_eq!(table.boundary_field(0), -1, "frozen spin left of the corner");
+        assert_eq!(table.coordination_number(5), 4, "bulk");
+        assert_eq!(table.number_of_bonds(), 2*4*3);
+    }
+
+    #[test]
+    fn test_tables_are_symmetric()
+    {
+        use BoundaryCondition::*;
+        let boundaries = [Periodic, Open, FixedUp, Antiperiodic, Helical];
+        for geometry in [LatticeGeometry::Square, LatticeGeometry::Triangular, LatticeGeometry::Honeycomb]
+        {
+            for (row_boundary, column_boundary) in boundaries.iter().flat_map(|&r| boundaries.iter().map(move |&c| (r, c)))
+            {
+                let mut spins = PeriodicArray2D::<i8,f64>::new_with_geometry(4, 6, geometry, ||1).unwrap();
+                if spins.set_boundary_conditions(row_boundary, column_boundary).is_err()
+                {
+                    continue;
+                }
+                let table = spins.topology();
+                for n in 0..24
+                {
+                    for bond in table.neighbours(n)
+                    {
+                        let count  = |from: usize, to: usize| table.neighbours(from).iter().filter(|b| b.site as usize == to && b.sign == bond.sign).count();
+                        assert_eq!(count(n, bond.site as usize), count(bond.site as usize, n), "{geometry:?}, {row_boundary:?} x {column_boundary:?}: bond {n} -> {}", bond.site);
+                    }
+                }
+                assert_eq!(2*table.number_of_bonds(), (0..24).map(|n| table.coordination_number(n)).sum::<usize>(), "{geometry:?}, {row_boundary:?} x {column_boundary:?}");
+            }
+        }
+        // Helical x helical: one chain closed mod N, the neighbours of n being n -+ columns & n -+ 1
+        let mut spins = PeriodicArray2D::<i8,f64>::new_with(4, 4, ||1).unwrap();
+        spins.set_boundary_conditions(BoundaryCondition::Helical, BoundaryCondition::Helical).unwrap();
+        assert_eq!(spins.topology().neighbours(0).iter().map(|b| b.site).collect::<Vec<_>>(), vec![12, 4, 15, 1]);
+        assert_eq!(spins.topology().neighbours(15).iter().map(|b| b.site).collect::<Vec<_>>(), vec![11, 3, 14, 0]);
+    }
+
+    #[test]
+    fn test_from_edges()
+    {
+        let ring = NeighbourTable::<i8>::from_edges(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]).unwrap();
+        assert!((0..5).all(|n| ring.coordination_number(n) == 2));
+        assert_eq!(ring.neighbours(0).iter().map(|b| b.site).collect::<Vec<_>>(), vec![1, 4]);
+        assert!(NeighbourTable::<i8>::from_edges(3, &[(0, 3)]).is_err());
+    }
+}